@@ -1,7 +1,7 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
 use lftes::Buffer;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 // Note: These benchmarks are limited by lack of slot recycling
 // They measure setup/teardown overhead more than raw throughput
@@ -10,7 +10,7 @@ fn bench_buffer_lifecycle(c: &mut Criterion) {
     c.bench_function("buffer_lifecycle", |b| {
         b.iter(|| {
             let buffer = Buffer::<u64>::builder().capacity(1024).build().unwrap();
-            let handle = buffer.start();
+            let mut handle = buffer.start();
 
             let producer = buffer.producer();
             for i in 0..100 {
@@ -21,7 +21,7 @@ fn bench_buffer_lifecycle(c: &mut Criterion) {
 
             let mut consumer = buffer.consumer();
             let mut count = 0;
-            while consumer.try_next().is_some() && count < 100 {
+            while matches!(consumer.try_next(), Ok(Some(_))) && count < 100 {
                 count += 1;
             }
 
@@ -35,7 +35,7 @@ fn bench_multi_producer(c: &mut Criterion) {
     c.bench_function("multi_producer_4x50", |b| {
         b.iter(|| {
             let buffer = Buffer::<u64>::builder().capacity(4096).build().unwrap();
-            let handle = buffer.start();
+            let mut handle = buffer.start();
 
             let mut threads = vec![];
             for _ in 0..4 {
@@ -67,7 +67,7 @@ fn bench_vs_crossbeam(c: &mut Criterion) {
     group.bench_function("lftes", |b| {
         b.iter(|| {
             let buffer = Buffer::<u64>::builder().capacity(1024).build().unwrap();
-            let handle = buffer.start();
+            let mut handle = buffer.start();
             let producer = buffer.producer();
 
             for i in 0..100 {
@@ -103,9 +103,94 @@ fn bench_vs_crossbeam(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_sequencing_pass(c: &mut Criterion) {
+    let buffer = Buffer::<u64>::builder().capacity(1024).build().unwrap();
+    let producer = buffer.producer();
+
+    c.bench_function("sequence_available_1024", |b| {
+        b.iter(|| {
+            for i in 0..1024 {
+                producer.push(black_box(i)).unwrap();
+            }
+            black_box(buffer.sequence_available());
+        });
+    });
+}
+
+fn bench_consumer_batch(c: &mut Criterion) {
+    let buffer = Buffer::<u64>::builder().capacity(1024).build().unwrap();
+    let producer = buffer.producer();
+    let mut consumer = buffer.consumer();
+
+    c.bench_function("try_next_batch_1024", |b| {
+        b.iter(|| {
+            for i in 0..1024 {
+                producer.push(black_box(i)).unwrap();
+            }
+            buffer.sequence_available();
+            black_box(consumer.try_next_batch(1024).unwrap());
+        });
+    });
+}
+
+/// Draining 1024 sequenced events one `try_next` at a time against one batch read.
+/// Only the drain is timed; pushing and sequencing the events is left out.
+fn bench_consume_throughput(c: &mut Criterion) {
+    let mut group = c.benchmark_group("consume_throughput");
+    group.throughput(Throughput::Elements(1024));
+    let buffer = Buffer::<u64>::builder().capacity(1024).build().unwrap();
+    let producer = buffer.producer();
+    let mut consumer = buffer.consumer();
+    let fill = || {
+        for i in 0..1024 {
+            producer.push(black_box(i)).unwrap();
+        }
+        buffer.sequence_available();
+    };
+
+    group.bench_function("per_slot", |b| {
+        b.iter_custom(|iters| {
+            let mut elapsed = Duration::ZERO;
+            for _ in 0..iters {
+                fill();
+                let start = Instant::now();
+                while let Some(event) = consumer.try_next().unwrap() {
+                    black_box(event);
+                }
+                elapsed += start.elapsed();
+            }
+            elapsed
+        });
+    });
+    group.bench_function("batched", |b| {
+        b.iter_custom(|iters| {
+            let mut elapsed = Duration::ZERO;
+            for _ in 0..iters {
+                fill();
+                let start = Instant::now();
+                black_box(consumer.try_next_batch(1024).unwrap());
+                elapsed += start.elapsed();
+            }
+            elapsed
+        });
+    });
+    group.finish();
+}
+
+fn bench_build_large(c: &mut Criterion) {
+    // 1 GiB of slots, which the OS hands out zeroed without touching them
+    c.bench_function("build_1<<24", |b| {
+        b.iter(|| black_box(Buffer::<u64>::builder().capacity(1 << 24).build().unwrap()));
+    });
+}
+
 criterion_group!(
     benches,
     bench_buffer_lifecycle,
+    bench_build_large,
+    bench_sequencing_pass,
+    bench_consumer_batch,
+    bench_consume_throughput,
     bench_multi_producer,
     bench_vs_crossbeam,
 );
@@ -1,6 +1,4 @@
 use lftes::Buffer;
-use std::thread;
-use std::time::Duration;
 
 fn main() {
     println!("lftes - Lock-Free Temporal Event Store Demo\n");
@@ -11,7 +9,7 @@ fn main() {
     println!("Created buffer with 256 slots");
 
     // Start the sequencer
-    let handle = buffer.start();
+    let mut handle = buffer.start();
     println!("Started sequencer thread\n");
 
     // Create a producer
@@ -23,14 +21,14 @@ fn main() {
         producer.push(i * 100).unwrap();
     }
 
-    // Give sequencer time to process
-    thread::sleep(Duration::from_millis(50));
+    // Wait until everything pushed has been sequenced
+    buffer.flush();
 
     // Create a consumer and read events
     println!("\nConsuming events:");
     let mut consumer = buffer.consumer();
 
-    while let Some(event) = consumer.try_next() {
+    while let Some(event) = consumer.try_next().unwrap() {
         println!(
             "  seq={} payload={} ts={} producer={}",
             event.sequence, event.payload, event.timestamp, event.producer_id
@@ -41,7 +39,7 @@ fn main() {
     println!("\nDeterministic replay (second consumer):");
     let mut consumer2 = buffer.consumer();
 
-    while let Some(event) = consumer2.try_next() {
+    while let Some(event) = consumer2.try_next().unwrap() {
         println!("  seq={} payload={}", event.sequence, event.payload);
     }
 
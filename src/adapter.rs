@@ -0,0 +1,246 @@
+use crate::consumer::{Consumer, Event};
+use crate::error::ConsumerError;
+use std::time::{Duration, Instant};
+
+/// Common read surface for consumers and the adapters layered on top of them
+pub trait EventSource {
+    type Item;
+
+    /// Read the next item if one is available without waiting
+    fn try_next(&mut self) -> Result<Option<Self::Item>, ConsumerError>;
+
+    /// Block until the next item is available
+    fn next(&mut self) -> Result<Self::Item, ConsumerError>;
+
+    /// Block until the next item is available or `timeout` elapses
+    fn next_timeout(&mut self, timeout: Duration) -> Result<Self::Item, ConsumerError>;
+
+    /// Read up to `max` currently available items.
+    /// A lag detected after the first item ends the batch and is reported by the next call.
+    fn try_next_batch(&mut self, max: usize) -> Result<Vec<Self::Item>, ConsumerError> {
+        let mut items = Vec::new();
+        while items.len() < max {
+            match self.try_next() {
+                Ok(Some(item)) => items.push(item),
+                Ok(None) => break,
+                Err(_) if !items.is_empty() => break,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(items)
+    }
+
+    /// Skip items that don't match `predicate`. Skipped items still advance the cursor.
+    fn filter<F>(self, predicate: F) -> Filter<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> bool,
+    {
+        Filter {
+            source: self,
+            predicate,
+        }
+    }
+
+    /// Transform each item with `f`
+    fn map<U, F>(self, f: F) -> Map<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(Self::Item) -> U,
+    {
+        Map { source: self, f }
+    }
+}
+
+impl<T, M> EventSource for Consumer<T, M>
+where
+    T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
+{
+    type Item = Event<T, M>;
+
+    fn try_next(&mut self) -> Result<Option<Event<T, M>>, ConsumerError> {
+        Consumer::try_next(self)
+    }
+
+    fn next(&mut self) -> Result<Event<T, M>, ConsumerError> {
+        Consumer::next(self)
+    }
+
+    fn next_timeout(&mut self, timeout: Duration) -> Result<Event<T, M>, ConsumerError> {
+        Consumer::next_timeout(self, timeout)
+    }
+
+    fn try_next_batch(&mut self, max: usize) -> Result<Vec<Event<T, M>>, ConsumerError> {
+        Consumer::try_next_batch(self, max)
+    }
+}
+
+/// Adapter returned by `EventSource::filter`
+pub struct Filter<S, F> {
+    source: S,
+    predicate: F,
+}
+
+impl<S, F> Filter<S, F> {
+    /// Get the wrapped source back
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+impl<S, F> EventSource for Filter<S, F>
+where
+    S: EventSource,
+    F: FnMut(&S::Item) -> bool,
+{
+    type Item = S::Item;
+
+    fn try_next(&mut self) -> Result<Option<S::Item>, ConsumerError> {
+        while let Some(item) = self.source.try_next()? {
+            if (self.predicate)(&item) {
+                return Ok(Some(item));
+            }
+        }
+        Ok(None)
+    }
+
+    fn next(&mut self) -> Result<S::Item, ConsumerError> {
+        loop {
+            let item = self.source.next()?;
+            if (self.predicate)(&item) {
+                return Ok(item);
+            }
+        }
+    }
+
+    fn next_timeout(&mut self, timeout: Duration) -> Result<S::Item, ConsumerError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let item = self.source.next_timeout(remaining)?;
+            if (self.predicate)(&item) {
+                return Ok(item);
+            }
+        }
+    }
+}
+
+/// Adapter returned by `EventSource::map`
+pub struct Map<S, F> {
+    source: S,
+    f: F,
+}
+
+impl<S, F> Map<S, F> {
+    /// Get the wrapped source back
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+impl<S, F, U> EventSource for Map<S, F>
+where
+    S: EventSource,
+    F: FnMut(S::Item) -> U,
+{
+    type Item = U;
+
+    fn try_next(&mut self) -> Result<Option<U>, ConsumerError> {
+        Ok(self.source.try_next()?.map(&mut self.f))
+    }
+
+    fn next(&mut self) -> Result<U, ConsumerError> {
+        self.source.next().map(&mut self.f)
+    }
+
+    fn next_timeout(&mut self, timeout: Duration) -> Result<U, ConsumerError> {
+        self.source.next_timeout(timeout).map(&mut self.f)
+    }
+
+    fn try_next_batch(&mut self, max: usize) -> Result<Vec<U>, ConsumerError> {
+        let items = self.source.try_next_batch(max)?;
+        Ok(items.into_iter().map(&mut self.f).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::Buffer;
+
+    #[test]
+    fn filter_skips_non_matching_events() {
+        let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        let mut handle = buffer.start();
+        let producer = buffer.producer();
+        for i in 0..6 {
+            producer.push(i).unwrap();
+        }
+
+        let mut evens = buffer.consumer_filtered(|payload| payload % 2 == 0);
+        let seen: Vec<u64> = (0..3)
+            .map(|_| evens.next_timeout(Duration::from_secs(5)).unwrap().payload)
+            .collect();
+        assert_eq!(seen, vec![0, 2, 4]);
+
+        // Odd events were consumed too, so the cursor is past the whole stream
+        assert!(evens.try_next().unwrap().is_none());
+        assert!(evens.into_inner().try_next().unwrap().is_none());
+
+        handle.stop();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn filter_on_event_metadata() {
+        let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        let mut handle = buffer.start();
+        let producer = buffer.producer();
+        for i in 0..4 {
+            producer.push(i).unwrap();
+        }
+
+        let mut late = buffer.consumer().filter(|event| event.sequence >= 2);
+        assert_eq!(late.next_timeout(Duration::from_secs(5)).unwrap().payload, 2);
+
+        handle.stop();
+        handle.join().unwrap();
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Order {
+        id: u64,
+        seq: u64,
+    }
+
+    #[test]
+    fn map_composes_with_filter_and_batch() {
+        let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        let mut handle = buffer.start();
+        let producer = buffer.producer();
+        for i in 0..6 {
+            producer.push(i).unwrap();
+        }
+        while buffer.next_seq.load(std::sync::atomic::Ordering::Acquire) < 6 {
+            std::thread::yield_now();
+        }
+
+        let mut orders = buffer
+            .consumer_filtered(|payload| *payload >= 3)
+            .map(|event| Order {
+                id: event.payload * 10,
+                seq: event.sequence,
+            });
+
+        let first = orders.next_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(first, Order { id: 30, seq: 3 });
+
+        let rest = orders.try_next_batch(10).unwrap();
+        let ids: Vec<u64> = rest.iter().map(|order| order.id).collect();
+        assert_eq!(ids, vec![40, 50]);
+
+        handle.stop();
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,222 @@
+use std::fs::File;
+use std::io;
+use std::ptr::NonNull;
+
+/// Size of a (2 MB) huge page; smaller rings are not worth mapping on huge pages
+pub(crate) const HUGE_PAGE: usize = 2 << 20;
+
+/// Size of an ordinary page, and so the alignment of every mapping
+pub(crate) const PAGE: usize = 4096;
+
+/// Pin the calling thread to `core`
+pub(crate) fn pin_to_core(core: usize) -> io::Result<()> {
+    pin_to_cores(&[core])
+}
+
+/// Let the calling thread run only on `cores`
+#[cfg(target_os = "linux")]
+pub(crate) fn pin_to_cores(cores: &[usize]) -> io::Result<()> {
+    if cores.is_empty() || cores.iter().any(|&core| core >= libc::CPU_SETSIZE as usize) {
+        return Err(io::Error::from(io::ErrorKind::InvalidInput));
+    }
+    // SAFETY: cpu_set_t is plain data, and 0 targets the calling thread
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for &core in cores {
+            libc::CPU_SET(core, &mut set);
+        }
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Move the calling thread to `SCHED_FIFO` at `priority` (1-99). Usually needs `CAP_SYS_NICE`.
+#[cfg(target_os = "linux")]
+pub(crate) fn set_priority(priority: i32) -> io::Result<()> {
+    let param = libc::sched_param {
+        sched_priority: priority,
+    };
+    // SAFETY: pthread_self is always valid for the calling thread
+    let rc = unsafe { libc::pthread_setschedparam(libc::pthread_self(), libc::SCHED_FIFO, &param) };
+    if rc != 0 {
+        return Err(io::Error::from_raw_os_error(rc));
+    }
+    Ok(())
+}
+
+/// Lock `len` bytes at `addr` into RAM, faulting them in. Limited by `RLIMIT_MEMLOCK`.
+#[cfg(target_os = "linux")]
+pub(crate) fn lock_memory(addr: *const u8, len: usize) -> io::Result<()> {
+    // SAFETY: mlock only changes paging for the range; it never reads or writes it
+    if unsafe { libc::mlock(addr.cast(), len) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// The soft `RLIMIT_MEMLOCK` in bytes, or `None` if it is unlimited
+#[cfg(target_os = "linux")]
+pub(crate) fn memlock_limit() -> Option<usize> {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: getrlimit only writes the struct it is given
+    if unsafe { libc::getrlimit(libc::RLIMIT_MEMLOCK, &mut limit) } != 0
+        || limit.rlim_cur == libc::RLIM_INFINITY
+    {
+        return None;
+    }
+    Some(limit.rlim_cur as usize)
+}
+
+/// Undo `lock_memory` for the same range
+#[cfg(target_os = "linux")]
+pub(crate) fn unlock_memory(addr: *const u8, len: usize) {
+    // SAFETY: As for mlock
+    unsafe { libc::munlock(addr.cast(), len) };
+}
+
+/// Map `len` bytes of zeroed memory, returning the mapping and its length rounded up
+/// to whole pages. With `huge`, the pages are 1 GB or 2 MB ones if the system has them
+/// reserved, otherwise ordinary pages with transparent huge pages requested.
+#[cfg(target_os = "linux")]
+pub(crate) fn map_anonymous(len: usize, huge: bool) -> Option<(NonNull<u8>, usize)> {
+    if !huge {
+        let len = len.next_multiple_of(PAGE);
+        return Some((map(len, 0)?, len));
+    }
+
+    const GIGANTIC: usize = 1 << 30;
+    if len >= GIGANTIC {
+        let len = len.next_multiple_of(GIGANTIC);
+        if let Some(ptr) = map(len, libc::MAP_HUGETLB | libc::MAP_HUGE_1GB) {
+            return Some((ptr, len));
+        }
+    }
+    let len = len.next_multiple_of(HUGE_PAGE);
+    if let Some(ptr) = map(len, libc::MAP_HUGETLB | libc::MAP_HUGE_2MB) {
+        return Some((ptr, len));
+    }
+    let ptr = map(len, 0)?;
+    // SAFETY: Only advice for our own mapping; without THP it fails and changes nothing
+    unsafe { libc::madvise(ptr.as_ptr().cast(), len, libc::MADV_HUGEPAGE) };
+    Some((ptr, len))
+}
+
+#[cfg(target_os = "linux")]
+fn map(len: usize, flags: libc::c_int) -> Option<NonNull<u8>> {
+    // SAFETY: An anonymous private mapping aliases nothing
+    let ptr = unsafe {
+        libc::mmap(
+            std::ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | flags,
+            -1,
+            0,
+        )
+    };
+    if ptr == libc::MAP_FAILED {
+        return None;
+    }
+    NonNull::new(ptr.cast())
+}
+
+/// Release a mapping from `map_anonymous` or `map_file`
+#[cfg(target_os = "linux")]
+pub(crate) fn unmap(ptr: NonNull<u8>, len: usize) {
+    // SAFETY: The caller owns the whole mapping and nothing refers to it any more
+    unsafe { libc::munmap(ptr.as_ptr().cast(), len) };
+}
+
+/// Map the first `len` bytes of `file` shared, so writes to the mapping reach the file
+#[cfg(target_os = "linux")]
+pub(crate) fn map_file(file: &File, len: usize) -> io::Result<NonNull<u8>> {
+    use std::os::fd::AsRawFd;
+    // SAFETY: The caller holds the file's lock, so no other process maps it meanwhile
+    let ptr = unsafe {
+        libc::mmap(
+            std::ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_SHARED,
+            file.as_raw_fd(),
+            0,
+        )
+    };
+    if ptr == libc::MAP_FAILED {
+        return Err(io::Error::last_os_error());
+    }
+    NonNull::new(ptr.cast()).ok_or_else(|| io::ErrorKind::AddrNotAvailable.into())
+}
+
+/// Write a shared mapping's dirty pages back to its file, waiting until they are on disk
+#[cfg(target_os = "linux")]
+pub(crate) fn sync_mapping(ptr: NonNull<u8>, len: usize) -> io::Result<()> {
+    // SAFETY: msync only writes back pages of our own mapping
+    if unsafe { libc::msync(ptr.as_ptr().cast(), len, libc::MS_SYNC) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Take an exclusive lock on `file`, failing with `WouldBlock` if another handle has it.
+/// Released when the file is closed.
+#[cfg(target_os = "linux")]
+pub(crate) fn lock_file(file: &File) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+    // SAFETY: flock only changes the lock on our own descriptor
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn pin_to_cores(_cores: &[usize]) -> io::Result<()> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn set_priority(_priority: i32) -> io::Result<()> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn lock_memory(_addr: *const u8, _len: usize) -> io::Result<()> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn memlock_limit() -> Option<usize> {
+    None
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn unlock_memory(_addr: *const u8, _len: usize) {}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn map_anonymous(_len: usize, _huge: bool) -> Option<(NonNull<u8>, usize)> {
+    None
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn unmap(_ptr: NonNull<u8>, _len: usize) {}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn map_file(_file: &File, _len: usize) -> io::Result<NonNull<u8>> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn sync_mapping(_ptr: NonNull<u8>, _len: usize) -> io::Result<()> {
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn lock_file(_file: &File) -> io::Result<()> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
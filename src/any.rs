@@ -0,0 +1,189 @@
+use crate::adapter::EventSource;
+use crate::consumer::{Consumer, Event};
+use crate::error::{ConsumerError, PushError};
+use crate::producer::Producer;
+use std::any::TypeId;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem::{size_of, MaybeUninit};
+use std::time::{Duration, Instant};
+
+/// One event of any `Copy` type up to `N` bytes, tagged with its `TypeId`.
+///
+/// A `Buffer<AnyEvent>` carries several event types in one sequenced stream: producers
+/// push with `push_any`, and consumers either check each event with `is`/`downcast` or
+/// wrap themselves in `typed::<E>()` to see only the events of one type.
+#[derive(Clone, Copy)]
+pub struct AnyEvent<const N: usize = 48> {
+    type_id: TypeId,
+    bytes: [MaybeUninit<u8>; N],
+}
+
+impl<const N: usize> AnyEvent<N> {
+    /// Wrap `event`. Types larger than `N` bytes are rejected at compile time.
+    pub fn new<E: Copy + Send + 'static>(event: E) -> Self {
+        const { assert!(size_of::<E>() <= N, "event type does not fit in AnyEvent<N>") };
+        let mut bytes = [MaybeUninit::uninit(); N];
+        // SAFETY: `bytes` has room for an `E`, and the unaligned write needs no alignment
+        unsafe { bytes.as_mut_ptr().cast::<E>().write_unaligned(event) };
+        Self {
+            type_id: TypeId::of::<E>(),
+            bytes,
+        }
+    }
+
+    /// Whether this event holds an `E`
+    pub fn is<E: 'static>(&self) -> bool {
+        self.type_id == TypeId::of::<E>()
+    }
+
+    /// The event as an `E`, if that is its type
+    pub fn downcast<E: Copy + 'static>(&self) -> Option<E> {
+        // SAFETY: The tag matches, so `new` wrote an `E` at the start of `bytes`
+        self.is::<E>()
+            .then(|| unsafe { self.bytes.as_ptr().cast::<E>().read_unaligned() })
+    }
+
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+}
+
+impl<const N: usize> fmt::Debug for AnyEvent<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AnyEvent")
+            .field("type_id", &self.type_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<const N: usize> Producer<AnyEvent<N>> {
+    /// Tag `event` with its type and push it
+    pub fn push_any<E: Copy + Send + 'static>(&self, event: E) -> Result<(), PushError> {
+        self.push(AnyEvent::new(event))
+    }
+}
+
+impl<const N: usize> Consumer<AnyEvent<N>> {
+    /// Read only the events of type `E`; see `Typed`
+    pub fn typed<E: Copy + 'static>(self) -> Typed<Self, E> {
+        Typed::new(self)
+    }
+}
+
+/// Adapter over a source of `AnyEvent`s that yields only the events of type `E`,
+/// unwrapped. Events of other types still advance the cursor.
+pub struct Typed<S, E> {
+    source: S,
+    _event: PhantomData<fn() -> E>,
+}
+
+impl<S, E> Typed<S, E> {
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            _event: PhantomData,
+        }
+    }
+
+    /// Get the wrapped source back
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+fn unwrap<E: Copy + 'static, const N: usize>(event: Event<AnyEvent<N>>) -> Option<Event<E>> {
+    Some(Event {
+        sequence: event.sequence,
+        generation: event.generation,
+        timestamp: event.timestamp,
+        producer_id: event.producer_id,
+        flags: event.flags,
+        late: event.late,
+        metadata: event.metadata,
+        payload: event.payload.downcast()?,
+    })
+}
+
+impl<S, E, const N: usize> EventSource for Typed<S, E>
+where
+    S: EventSource<Item = Event<AnyEvent<N>>>,
+    E: Copy + 'static,
+{
+    type Item = Event<E>;
+
+    fn try_next(&mut self) -> Result<Option<Event<E>>, ConsumerError> {
+        while let Some(event) = self.source.try_next()? {
+            if let Some(event) = unwrap(event) {
+                return Ok(Some(event));
+            }
+        }
+        Ok(None)
+    }
+
+    fn next(&mut self) -> Result<Event<E>, ConsumerError> {
+        loop {
+            if let Some(event) = unwrap(self.source.next()?) {
+                return Ok(event);
+            }
+        }
+    }
+
+    fn next_timeout(&mut self, timeout: Duration) -> Result<Event<E>, ConsumerError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if let Some(event) = unwrap(self.source.next_timeout(remaining)?) {
+                return Ok(event);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::Buffer;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Trade {
+        price: f64,
+        qty: u32,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Quote {
+        bid: u16,
+        ask: u16,
+    }
+
+    #[test]
+    fn mixed_types_share_one_stream() {
+        let buffer = Buffer::<AnyEvent>::builder().capacity(16).build().unwrap();
+        let producer = buffer.producer();
+        let mut all = buffer.consumer();
+        let mut trades = buffer.consumer().typed::<Trade>();
+        let mut quotes = buffer.consumer().typed::<Quote>();
+
+        let trade = Trade {
+            price: 101.5,
+            qty: 7,
+        };
+        let quote = Quote { bid: 100, ask: 102 };
+        producer.push_any(quote).unwrap();
+        producer.push_any(trade).unwrap();
+        producer.push_any(3u8).unwrap();
+        buffer.flush();
+
+        let first = all.try_next().unwrap().unwrap().payload;
+        assert!(first.is::<Quote>());
+        assert_eq!(first.downcast::<Trade>(), None);
+        assert_eq!(first.downcast::<Quote>(), Some(quote));
+
+        let event = trades.try_next().unwrap().unwrap();
+        assert_eq!((event.sequence, event.payload), (1, trade));
+        assert!(trades.try_next().unwrap().is_none());
+        assert_eq!(quotes.try_next().unwrap().unwrap().payload, quote);
+        assert!(quotes.try_next().unwrap().is_none());
+    }
+}
@@ -0,0 +1,232 @@
+use crate::buffer::Buffer;
+use crate::consumer::{Consumer, Event};
+use crate::error::{BuildError, ConsumerError, PushError};
+use crate::producer::Producer;
+use crate::sequencer::SequencerHandle;
+use crate::slot::SlotWriteGuard;
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::mem::MaybeUninit;
+use std::sync::Arc;
+
+/// A buffer for large events that keeps them out of the ring.
+///
+/// A `Buffer<T>` slot grows with `T`, so a 1 KB event makes every slot 1 KB and the
+/// sequencer's scan over slot states touches a fresh page every few slots. Here each
+/// slot stays one cache line and holds the index of an arena cell; the event is
+/// written into that cell, and each slot owns its cell for good. Once `T` is bigger
+/// than a cache line or two, this is usually the better layout.
+pub struct ArenaBuffer<T> {
+    buffer: Arc<Buffer<u32>>,
+    arena: Box<[UnsafeCell<MaybeUninit<T>>]>,
+}
+
+// SAFETY: A cell is written only by the producer that has claimed the slot holding its
+// index, and read only by consumers of a sequenced event in that slot, exactly like
+// an inline payload
+unsafe impl<T: Send> Sync for ArenaBuffer<T> {}
+unsafe impl<T: Send> Send for ArenaBuffer<T> {}
+
+impl<T> ArenaBuffer<T>
+where
+    T: Copy + Send + 'static,
+{
+    /// `capacity` slots, as for `Buffer`, with an arena cell per slot
+    pub fn new(capacity: usize) -> Result<Arc<Self>, BuildError> {
+        let buffer = Buffer::builder().capacity(capacity).build()?;
+        for (index, slot) in buffer.slots.iter().enumerate() {
+            // SAFETY: Nothing else can reach the buffer yet
+            unsafe { SlotWriteGuard::new(slot) }.write_payload(index as u32);
+        }
+        Ok(Arc::new(Self {
+            buffer,
+            arena: (0..capacity)
+                .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+                .collect(),
+        }))
+    }
+
+    /// Start the sequencer thread
+    pub fn start(&self) -> SequencerHandle {
+        self.buffer.start()
+    }
+
+    /// Run one sequencing pass on the calling thread; see `Buffer::sequence_available`
+    pub fn sequence_available(&self) -> usize {
+        self.buffer.sequence_available()
+    }
+
+    /// Block until every event pushed before the call has been sequenced
+    pub fn flush(&self) {
+        self.buffer.flush();
+    }
+
+    /// Stop accepting events and sequence everything already pushed; see `Buffer::close`
+    pub fn close(&self) {
+        self.buffer.close();
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    pub fn producer(self: &Arc<Self>) -> ArenaProducer<T> {
+        ArenaProducer {
+            arena: self.clone(),
+            producer: self.buffer.producer(),
+        }
+    }
+
+    pub fn consumer(self: &Arc<Self>) -> ArenaConsumer<T> {
+        ArenaConsumer {
+            arena: self.clone(),
+            consumer: self.buffer.consumer(),
+        }
+    }
+
+    fn cell(&self, index: u32) -> *mut MaybeUninit<T> {
+        self.arena[index as usize].get()
+    }
+}
+
+impl<T> fmt::Debug for ArenaBuffer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArenaBuffer")
+            .field("capacity", &self.arena.len())
+            .finish_non_exhaustive()
+    }
+}
+
+pub struct ArenaProducer<T> {
+    arena: Arc<ArenaBuffer<T>>,
+    producer: Producer<u32>,
+}
+
+impl<T> ArenaProducer<T>
+where
+    T: Copy + Send + 'static,
+{
+    /// Write `event` into the claimed slot's arena cell and publish it
+    pub fn push(&self, event: T) -> Result<(), PushError> {
+        let arena = &self.arena;
+        // SAFETY: `new` wrote every slot's index, which is left in place
+        unsafe {
+            self.producer.push_in_place((), |index| {
+                // SAFETY: As above, and the claim gives us the cell
+                (*arena.cell(index.assume_init())).write(event);
+            })
+        }
+    }
+}
+
+pub struct ArenaConsumer<T> {
+    arena: Arc<ArenaBuffer<T>>,
+    consumer: Consumer<u32>,
+}
+
+impl<T> ArenaConsumer<T>
+where
+    T: Copy + Send + 'static,
+{
+    /// Copy out the next sequenced event, if there is one
+    pub fn try_next(&mut self) -> Result<Option<Event<T>>, ConsumerError> {
+        let arena = &self.arena;
+        self.consumer.try_next_with(|event| Self::copy(arena, event))
+    }
+
+    /// Block until the next event is sequenced and copy it out
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Result<Event<T>, ConsumerError> {
+        let arena = &self.arena;
+        self.consumer.next_with(|event| Self::copy(arena, event))
+    }
+
+    /// Run `f` against the next event in its arena cell, without copying it
+    pub fn try_next_with<R>(
+        &mut self,
+        f: impl FnOnce(&Event<&T>) -> R,
+    ) -> Result<Option<R>, ConsumerError> {
+        let arena = &self.arena;
+        self.consumer.try_next_with(|event| {
+            // SAFETY: The event is sequenced, so its producer finished writing the cell,
+            // and our registration keeps the slot from being claimed again meanwhile
+            let payload = unsafe { (*arena.cell(*event.payload)).assume_init_ref() };
+            f(&Event {
+                sequence: event.sequence,
+                generation: event.generation,
+                timestamp: event.timestamp,
+                producer_id: event.producer_id,
+                flags: event.flags,
+                late: event.late,
+                metadata: event.metadata,
+                payload,
+            })
+        })
+    }
+
+    fn copy(arena: &ArenaBuffer<T>, event: &Event<&u32>) -> Event<T> {
+        Event {
+            sequence: event.sequence,
+            generation: event.generation,
+            timestamp: event.timestamp,
+            producer_id: event.producer_id,
+            flags: event.flags,
+            late: event.late,
+            metadata: event.metadata,
+            // SAFETY: As in `try_next_with`
+            payload: unsafe { (*arena.cell(*event.payload)).assume_init_read() },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::slot::Slot;
+
+    type Frame = [u64; 128];
+
+    #[test]
+    fn large_events_round_trip_through_the_arena() {
+        assert!(size_of::<Slot<u32>>() <= crate::pad::CACHE_LINE);
+        let arena = ArenaBuffer::<Frame>::new(4).unwrap();
+        let producer = arena.producer();
+        let mut consumer = arena.consumer();
+
+        // Several laps, so every slot reuses its cell
+        for i in 0..10 {
+            producer.push([i; 128]).unwrap();
+            arena.flush();
+            let event = consumer.try_next().unwrap().unwrap();
+            assert_eq!((event.sequence, event.payload), (i, [i; 128]));
+        }
+        producer.push([42; 128]).unwrap();
+        arena.flush();
+        let sum = consumer.try_next_with(|event| event.payload.iter().sum::<u64>());
+        assert_eq!(sum.unwrap(), Some(42 * 128));
+    }
+
+    #[test]
+    fn a_slow_reader_keeps_its_cells_from_being_overwritten() {
+        let arena = ArenaBuffer::<Frame>::new(4).unwrap();
+        let mut handle = arena.start();
+        let mut consumer = arena.consumer();
+
+        let writer = {
+            let producer = arena.producer();
+            std::thread::spawn(move || {
+                for i in 0..100 {
+                    producer.push([i; 128]).unwrap();
+                }
+            })
+        };
+        for i in 0..100 {
+            let event = consumer.next().unwrap();
+            assert!(event.payload.iter().all(|&word| word == i));
+        }
+        writer.join().unwrap();
+
+        handle.stop();
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,98 @@
+use crate::sync::atomic::{AtomicBool, Ordering};
+
+/// What `Producer::push` does while the buffer is backpressured
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackpressureMode {
+    /// Return `PushError::Backpressure` without claiming a slot
+    FailFast,
+    /// Yield until consumers catch up to the low watermark
+    Wait,
+}
+
+/// Consumer lag watermarks and the flag they drive.
+///
+/// The sequencer raises the flag once the slowest registered consumer is `high`
+/// events behind; it stays raised until the lag drops to `low`.
+#[derive(Debug)]
+pub(crate) struct Backpressure {
+    high: u64,
+    low: u64,
+    pub(crate) mode: BackpressureMode,
+    engaged: AtomicBool,
+}
+
+impl Backpressure {
+    pub(crate) fn new(high: u64, low: u64, mode: BackpressureMode) -> Self {
+        let high = high.max(1);
+        Self {
+            high,
+            low: low.min(high - 1),
+            mode,
+            engaged: AtomicBool::new(false),
+        }
+    }
+
+    pub(crate) fn engaged(&self) -> bool {
+        self.engaged.load(Ordering::Acquire)
+    }
+
+    /// Raise or release the flag for the current `lag` and return whether it is raised
+    pub(crate) fn update(&self, lag: u64) -> bool {
+        if lag >= self.high {
+            self.engaged.store(true, Ordering::Release);
+            true
+        } else if lag <= self.low {
+            self.engaged.store(false, Ordering::Release);
+            false
+        } else {
+            self.engaged()
+        }
+    }
+
+    pub(crate) fn reset(&mut self) {
+        self.engaged.store(false, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::Buffer;
+    use crate::error::PushError;
+
+    #[test]
+    fn flag_has_hysteresis() {
+        let backpressure = Backpressure::new(8, 2, BackpressureMode::FailFast);
+        assert!(!backpressure.update(7));
+        assert!(backpressure.update(8));
+        assert!(backpressure.update(3));
+        assert!(!backpressure.update(2));
+        assert!(!backpressure.update(5));
+    }
+
+    #[test]
+    fn push_fails_fast_until_consumer_catches_up() {
+        let buffer = Buffer::<u64>::builder()
+            .capacity(16)
+            .backpressure(4, 1, BackpressureMode::FailFast)
+            .build()
+            .unwrap();
+        let producer = buffer.producer();
+        let mut consumer = buffer.consumer();
+
+        for i in 0..4 {
+            producer.push(i).unwrap();
+        }
+        buffer.sequence_available();
+        assert!(buffer.is_backpressured());
+        assert_eq!(producer.push(4), Err(PushError::Backpressure));
+
+        // Reading down to a lag of 2 is not enough; 1 releases it
+        consumer.try_next().unwrap();
+        consumer.try_next().unwrap();
+        assert_eq!(producer.push(4), Err(PushError::Backpressure));
+        consumer.try_next().unwrap();
+        producer.push(4).unwrap();
+        assert!(!buffer.is_backpressured());
+    }
+}
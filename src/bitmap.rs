@@ -0,0 +1,128 @@
+use crate::sync::atomic::{AtomicU64, Ordering};
+use crate::sync::ZeroedSlice;
+
+/// One bit per slot, set while the slot is Published.
+///
+/// Lets the sequencer measure a run of published slots 64 at a time instead of
+/// loading every slot's state from its own cache line. A slot's bit is set by the
+/// sequencer when it takes the slot's index off the `PublishQueue`, and cleared
+/// before the slot becomes Sequenced, so a bit is only ever set while its slot is
+/// Published.
+#[derive(Debug)]
+pub(crate) struct PublishedMap {
+    words: ZeroedSlice<AtomicU64>,
+    capacity: usize,
+}
+
+impl PublishedMap {
+    /// Bits for `capacity` slots, on pages of their own with `own_pages`, as for
+    /// `ZeroedSlice::new`
+    pub(crate) fn new(capacity: usize, own_pages: bool) -> Self {
+        let len = capacity.div_ceil(64);
+        // SAFETY: A zero atomic is all zero bytes
+        let words = unsafe { ZeroedSlice::new(len, own_pages, || AtomicU64::new(0)) };
+        Self { words, capacity }
+    }
+
+    /// Mark slot `index` as published. Call only while the slot is Published.
+    pub(crate) fn set(&self, index: usize) {
+        self.words[index / 64].fetch_or(1 << (index % 64), Ordering::Release);
+    }
+
+    pub(crate) fn is_set(&self, index: usize) -> bool {
+        self.words[index / 64].load(Ordering::Acquire) & (1 << (index % 64)) != 0
+    }
+
+    /// Number of consecutive published slots starting at `index`, wrapping around the ring,
+    /// up to `max`. Synchronizes with the producers that set the bits.
+    pub(crate) fn run_from(&self, index: usize, max: usize) -> usize {
+        let mut run = 0;
+        while run < max {
+            let position = (index + run) % self.capacity;
+            let bit = position % 64;
+            let bits = self.words[position / 64].load(Ordering::Acquire) >> bit;
+
+            // Bits left in this word before it or the ring ends
+            let span = (64 - bit).min(self.capacity - position);
+            let ones = (bits.trailing_ones() as usize).min(span);
+            run += ones;
+            if ones < span {
+                break;
+            }
+        }
+        run.min(max)
+    }
+
+    /// Address and length in bytes of the bitmap, for `memory_usage` and `mlock`
+    pub(crate) fn byte_range(&self) -> (*const u8, usize) {
+        (self.words.as_ptr().cast(), size_of_val::<[AtomicU64]>(&self.words))
+    }
+
+    /// Write every word so its pages are faulted in. Only before the sequencer runs.
+    pub(crate) fn prefault(&self) {
+        for word in self.words.iter() {
+            word.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Number of published slots, 64 per load
+    pub(crate) fn count(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.load(Ordering::Relaxed).count_ones() as usize)
+            .sum()
+    }
+
+    /// Clear `len` bits starting at `index`, wrapping around the ring
+    pub(crate) fn clear(&self, index: usize, len: usize) {
+        let mut done = 0;
+        while done < len {
+            let position = (index + done) % self.capacity;
+            let bit = position % 64;
+            let span = (64 - bit).min(self.capacity - position).min(len - done);
+            let mask = if span == 64 {
+                u64::MAX
+            } else {
+                ((1u64 << span) - 1) << bit
+            };
+            self.words[position / 64].fetch_and(!mask, Ordering::Relaxed);
+            done += span;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_crosses_words_and_wraps() {
+        let map = PublishedMap::new(128, false);
+        for index in (60..128).chain(0..3) {
+            map.set(index);
+        }
+
+        assert_eq!(map.run_from(60, 128), 71);
+        assert_eq!(map.run_from(60, 10), 10);
+        assert_eq!(map.run_from(3, 128), 0);
+        assert_eq!(map.count(), 71);
+
+        map.clear(120, 11);
+        assert!(!map.is_set(127) && !map.is_set(2));
+        assert_eq!(map.run_from(60, 128), 60);
+        assert_eq!(map.count(), 60);
+    }
+
+    #[test]
+    fn small_rings_share_one_word() {
+        let map = PublishedMap::new(4, false);
+        for index in [2, 3, 0] {
+            map.set(index);
+        }
+        assert_eq!(map.run_from(2, 4), 3);
+
+        map.clear(2, 3);
+        assert_eq!(map.run_from(2, 4), 0);
+        assert_eq!(map.run_from(0, 4), 0);
+    }
+}
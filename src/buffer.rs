@@ -1,70 +1,703 @@
-use crate::consumer::Consumer;
-use crate::error::BuildError;
-use crate::producer::Producer;
-use crate::sequencer::{start_sequencer, SequencerHandle};
-use crate::slot::Slot;
-use std::sync::atomic::{AtomicU64, AtomicUsize};
-use std::sync::Arc;
+use crate::adapter::{EventSource, Filter};
+use crate::affinity;
+use crate::backpressure::{Backpressure, BackpressureMode};
+use crate::bitmap::PublishedMap;
+use crate::checksum::checksum;
+use crate::config::BufferConfig;
+use crate::consumer::{Consumer, Event};
+use crate::cursor::{CursorRegistry, Registration};
+use crate::error::{BuildError, ConsumerError};
+use crate::group::{ConsumerGroup, DeliveryMode};
+use crate::notify::PublishQueue;
+use crate::pad::CachePadded;
+use crate::policy::{Candidate, PolicyCell, SequencerPolicy, SlotOrder};
+use crate::prefetch::{self, prefetch};
+use crate::producer::{try_claim, Claim, OnFull, Producer};
+use crate::segment::Overflow;
+use crate::sequencer::{
+    drain, spawn_sequencer, start_sequencer, IdleHook, SequencerHandle, StuckClaims, ThreadConfig,
+};
+use crate::slot::{
+    ring_file_len, Slot, SlotReadGuard, SlotState, SlotWriteGuard, Slots, EXPIRED, SKIPPED,
+};
+use crate::subscription::{start_subscription, SubscriptionHandle};
+use crate::sync::atomic::{fence, AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use crate::sync::hint;
+use crate::ttl::Ttl;
+use crate::wait::{Notifier, WaitStrategy, Waiter};
+use crate::weak::WeakConsumer;
+use std::hash::Hash;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
 
-#[cfg(test)]
-use crate::slot::SlotState;
-#[cfg(test)]
-use std::sync::atomic::Ordering;
+/// Default upper bound on slot count; `BufferBuilder::max_capacity` changes it
+pub(crate) const MAX_CAPACITY: usize = 1 << 30; // 1 billion slots max
+
+/// Slots the sequencer marks Sequenced per Release fence; small enough to stay in L1
+const FENCE_BATCH: usize = 64;
+
+/// Release stores are plain moves on x86, where a separate fenced pass over each
+/// batch would only add a second walk over its slots
+const RELEASE_PER_SLOT: bool = cfg!(target_arch = "x86_64");
 
-const MAX_CAPACITY: usize = 1 << 30; // 1 billion slots max
+/// A buffer and the producers preallocated for it by `BufferBuilder::producers`
+type WithProducers<T, M> = (Arc<Buffer<T, M>>, Vec<Producer<T, M>>);
 
+/// A sequenced ring of `T` events. `M` is an optional fixed-size header (correlation id,
+/// source, flags) stored in each slot beside the payload and surfaced as `Event::metadata`.
 #[derive(Debug)]
-pub struct Buffer<T> {
-    pub(crate) slots: Box<[Slot<T>]>,
+pub struct Buffer<T, M = ()> {
+    pub(crate) slots: Slots<T, M>,
     pub(crate) capacity: usize,
     pub(crate) mask: usize,
-    pub(crate) head: AtomicUsize,
-    #[allow(dead_code)]
-    pub(crate) tail: AtomicU64, // TODO: track min consumer position for slot recycling
+    /// Next claim position; bumped by every producer claim
+    pub(crate) head: CachePadded<AtomicUsize>,
+    /// Which slots are Published, packed so the sequencer can scan them a word at a time
+    pub(crate) published: PublishedMap,
+    /// Slot indices producers have published but the sequencer has not yet flagged
+    pub(crate) publish_queue: PublishQueue,
+    /// Next sequence number the sequencer will assign
+    pub(crate) next_seq: CachePadded<AtomicU64>,
+    /// Lowest sequence any registered consumer may still read
+    pub(crate) tail: CachePadded<AtomicU64>,
+    pub(crate) wait_strategy: WaitStrategy,
+    pub(crate) notifier: Notifier,
+    /// How the sequencer waits while nothing is published
+    pub(crate) sequencer_wait_strategy: WaitStrategy,
+    /// Wakes a parked sequencer when a producer publishes
+    pub(crate) publish_notifier: Notifier,
+    pub(crate) delivery: DeliveryMode,
+    pub(crate) consumers: Arc<CursorRegistry>,
+    /// Set when the sequencer stops; nothing further will be sequenced
+    pub(crate) shutdown: AtomicBool,
+    /// Set by `close`; no further pushes are accepted
+    pub(crate) closed: AtomicBool,
+    /// Name, core and priority for the sequencer thread
+    pub(crate) sequencer_thread: ThreadConfig,
+    /// Whether the sequencer thread restarts its loop after a panic
+    pub(crate) restart_sequencer: bool,
+    /// Producers sequence their own events and no sequencer thread runs
+    pub(crate) single_producer: bool,
+    /// Whether producers read the clock for `Event::timestamp`
+    pub(crate) timestamps: bool,
+    /// Payload checksum taken at push and checked by consumers, with `checksums`
+    pub(crate) checksum: Option<fn(&T) -> u32>,
+    /// Whether the ring was mlocked at build time and must be unlocked on drop
+    locked: bool,
+    producer_taken: AtomicBool,
+    /// ID for the next producer handle
+    next_producer_id: AtomicUsize,
+    /// Called by the sequencer when nothing has been sequenced for a while
+    pub(crate) idle_hook: Option<Mutex<IdleHook>>,
+    /// How long a claim may stay unpublished before the sequencer skips it
+    pub(crate) stuck_claims: Option<Mutex<StuckClaims>>,
+    pub(crate) skipped_claims: AtomicU64,
+    /// Raised by the sequencer while consumers lag too far behind
+    pub(crate) backpressure: Option<Backpressure>,
+    /// Expires events older than a fixed age
+    pub(crate) ttl: Option<Ttl>,
+    /// What producers do when the next slot is not reusable
+    pub(crate) on_full: OnFull,
+    /// Events waiting for a ring slot, with `OnFull::Grow`
+    pub(crate) overflow: Option<Overflow<T, M>>,
+    /// Chooses which published event gets the next sequence number
+    pub(crate) policy: PolicyCell<T>,
+    /// Held by whoever is assigning sequence numbers: the sequencer thread or a manual pass
+    pub(crate) sequencing: AtomicBool,
+    /// Cursor shared by `consumer()` handles in work-queue mode
+    work_cursor: OnceLock<Arc<Registration>>,
 }
 
-impl<T> Buffer<T>
+impl<T, M> Buffer<T, M>
 where
     T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
 {
-    pub fn builder() -> BufferBuilder<T> {
+    pub fn builder() -> BufferBuilder<T, M> {
         BufferBuilder::new()
     }
 
+    #[cfg(test)]
     fn new(capacity: usize) -> Result<Self, BuildError> {
+        Self::allocate(capacity, None, false)
+    }
+
+    /// Allocate a ring of `capacity` slots, on `node` if given, and on pages of its own
+    /// with `own_pages`, as for `with_slots`. `build` checks it against the maximum.
+    fn allocate(capacity: usize, node: Option<usize>, own_pages: bool) -> Result<Self, BuildError> {
         if !capacity.is_power_of_two() {
             return Err(BuildError::InvalidCapacity);
         }
 
-        if capacity > MAX_CAPACITY {
+        let slots = Slots::allocate(capacity, node, own_pages)
+            .map_err(|e| BuildError::NumaNode(e.kind()))?;
+        Ok(Self::with_slots(slots, own_pages))
+    }
+
+    /// Map a ring of `capacity` slots from the file at `path`, carrying on after the
+    /// events it held when it was last closed
+    fn map_file(capacity: usize, path: &Path, own_pages: bool) -> Result<Self, BuildError> {
+        if !capacity.is_power_of_two() {
+            return Err(BuildError::InvalidCapacity);
+        }
+        if ring_file_len::<T, M>(capacity).is_none() {
             return Err(BuildError::TooLarge);
         }
 
-        let slots: Vec<Slot<T>> = (0..capacity).map(|_| Slot::new()).collect();
+        let (slots, next_seq) =
+            Slots::map_file(path, capacity).map_err(|e| BuildError::MappedFile(e.kind()))?;
+        let buffer = Self::with_slots(slots, own_pages);
+        buffer.head.store(next_seq as usize, Ordering::Relaxed);
+        buffer.next_seq.store(next_seq, Ordering::Relaxed);
+        buffer.tail.store(next_seq, Ordering::Relaxed);
+        Ok(buffer)
+    }
 
-        Ok(Self {
-            slots: slots.into_boxed_slice(),
+    /// Wrap storage whose length the caller has checked against the maximum capacity.
+    /// With `own_pages` the sequencer's bitmap and queue are kept off the heap, so they
+    /// can be `mlock`ed without pinning, or later unlocking, anyone else's memory.
+    fn with_slots(slots: Slots<T, M>, own_pages: bool) -> Self {
+        let capacity = slots.len();
+        Self {
+            slots,
             capacity,
             mask: capacity - 1,
-            head: AtomicUsize::new(0),
-            tail: AtomicU64::new(0),
-        })
+            head: CachePadded::new(AtomicUsize::new(0)),
+            published: PublishedMap::new(capacity, own_pages),
+            publish_queue: PublishQueue::new(capacity, own_pages),
+            next_seq: CachePadded::new(AtomicU64::new(0)),
+            tail: CachePadded::new(AtomicU64::new(0)),
+            wait_strategy: WaitStrategy::default(),
+            notifier: Notifier::new(),
+            sequencer_wait_strategy: WaitStrategy::BusySpin,
+            publish_notifier: Notifier::new(),
+            delivery: DeliveryMode::default(),
+            consumers: Arc::new(CursorRegistry::new()),
+            shutdown: AtomicBool::new(false),
+            closed: AtomicBool::new(false),
+            sequencing: AtomicBool::new(false),
+            policy: PolicyCell::new(Box::new(SlotOrder)),
+            sequencer_thread: ThreadConfig::default(),
+            restart_sequencer: false,
+            single_producer: false,
+            timestamps: true,
+            checksum: None,
+            locked: false,
+            producer_taken: AtomicBool::new(false),
+            next_producer_id: AtomicUsize::new(0),
+            idle_hook: None,
+            stuck_claims: None,
+            skipped_claims: AtomicU64::new(0),
+            backpressure: None,
+            ttl: None,
+            on_full: OnFull::default(),
+            overflow: None,
+            work_cursor: OnceLock::new(),
+        }
     }
 
-    /// Start the sequencer thread
+    /// Put a buffer back in its just-built state, keeping its settings and its ring.
+    /// The caller has checked that nothing is left unsequenced; holding `&mut` means no
+    /// producer, consumer or sequencer thread is attached.
+    pub(crate) fn reset(&mut self) {
+        for slot in self.slots.iter() {
+            slot.state.store(SlotState::Free as u8, Ordering::Relaxed);
+            slot.flags.store(0, Ordering::Relaxed);
+            slot.generation.store(0, Ordering::Relaxed);
+            slot.sequence.store(0, Ordering::Relaxed);
+        }
+        // The publish queue and bitmap are empty once everything is sequenced
+        self.head.store(0, Ordering::Relaxed);
+        self.next_seq.store(0, Ordering::Relaxed);
+        self.tail.store(0, Ordering::Relaxed);
+        self.shutdown.store(false, Ordering::Relaxed);
+        self.closed.store(false, Ordering::Relaxed);
+        self.producer_taken.store(false, Ordering::Relaxed);
+        self.next_producer_id.store(0, Ordering::Relaxed);
+        self.skipped_claims.store(0, Ordering::Relaxed);
+        self.work_cursor = OnceLock::new();
+        self.consumers = Arc::new(CursorRegistry::new());
+        if let Some(backpressure) = &mut self.backpressure {
+            backpressure.reset();
+        }
+        if let Some(ttl) = &mut self.ttl {
+            ttl.reset();
+        }
+    }
+
+    /// Start the sequencer thread. Core pinning and priority are best effort;
+    /// use `try_start` to find out if they could not be applied.
     pub fn start(self: &Arc<Self>) -> SequencerHandle {
         start_sequencer(self.clone())
     }
 
-    /// Create a new producer handle
-    pub fn producer(self: &Arc<Self>) -> Producer<T> {
-        // TODO: Track producer IDs
-        Producer::new(self.clone(), 0)
+    /// Start the sequencer thread, failing if its core or node pinning or priority could not be applied
+    pub fn try_start(self: &Arc<Self>) -> io::Result<SequencerHandle> {
+        let (handle, placed) = spawn_sequencer(self.clone())?;
+        match placed.recv() {
+            Ok(Err(err)) => Err(err),
+            _ => Ok(handle),
+        }
+    }
+
+    /// Stop accepting pushes and sequence everything already pushed. Producers then get
+    /// `PushError::Shutdown`, and blocking consumer calls return `ConsumerError::Closed`
+    /// once they have read everything. Returns when the last event has been sequenced,
+    /// by the sequencer thread if one is running or else on the calling thread. A claim
+    /// whose producer never publishes, say because it unwound mid-push, is skipped once
+    /// `stuck_claim_timeout` has passed; without one, `close` waits on it for good.
+    pub fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        self.publish_notifier.notify_all();
+
+        // A running sequencer holds `sequencing` until it has drained and exited
+        while self.sequencing.swap(true, Ordering::Acquire) {
+            std::thread::yield_now();
+        }
+        // Pairs with the producer's closed check after claiming; see `Producer::push`
+        fence(Ordering::SeqCst);
+        drain(self);
+        self.sequencing.store(false, Ordering::Release);
+
+        self.update_tail();
+        self.shutdown.store(true, Ordering::Release);
+        self.notifier.notify_all();
+    }
+
+    /// Whether `close` has been called
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::Acquire)
+    }
+
+    /// Closed and fully sequenced: blocking reads past `next_seq` will never complete
+    pub(crate) fn is_drained(&self) -> bool {
+        if !(self.shutdown.load(Ordering::Acquire) && self.closed.load(Ordering::Acquire)) {
+            return false;
+        }
+        // Nothing sequences after close, so readers move spilled events in as they free slots
+        if self.overflowed() > 0 {
+            self.sequence_available();
+        }
+        self.overflowed() == 0
+    }
+
+    /// Run one sequencing pass on the calling thread instead of a sequencer thread,
+    /// returning how many events were sequenced. Returns 0 while a sequencer thread is running.
+    pub fn sequence_available(&self) -> usize {
+        if self.sequencing.swap(true, Ordering::Acquire) {
+            return 0;
+        }
+        let sequenced = self.sequence_run();
+        self.expire_events();
+        self.sequencing.store(false, Ordering::Release);
+        sequenced
+    }
+
+    /// Block until every event claimed before the call has been sequenced. Runs sequencing
+    /// passes on the calling thread when no sequencer thread is running; with a paused or
+    /// stopped sequencer and pending events it waits forever, so prefer `flush_timeout` there.
+    pub fn flush(&self) {
+        self.flush_until(None);
+    }
+
+    /// Like `flush`, but gives up after `timeout`. Returns whether everything was sequenced.
+    pub fn flush_timeout(&self, timeout: Duration) -> bool {
+        self.flush_until(Some(Instant::now() + timeout))
+    }
+
+    fn flush_until(&self, deadline: Option<Instant>) -> bool {
+        let target = self.head.load(Ordering::Acquire) as u64;
+        let flushed = || self.next_seq.load(Ordering::Acquire) >= target;
+        let mut waiter = Waiter::new(self.wait_strategy);
+        loop {
+            if flushed() {
+                return true;
+            }
+            let threaded = self.sequencing.load(Ordering::Relaxed);
+            if !threaded && self.sequence_available() > 0 {
+                waiter.reset();
+                continue;
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                return false;
+            }
+            if threaded {
+                waiter.wait(&self.notifier, deadline, || {
+                    flushed() || !self.sequencing.load(Ordering::Relaxed)
+                });
+            } else {
+                // A producer is still writing a claimed slot; nobody will notify us
+                std::thread::yield_now();
+            }
+        }
+    }
+
+    /// Sequence the contiguous run of published slots at the scan position, at most one lap.
+    /// The caller must hold `sequencing`.
+    pub(crate) fn sequence_run(&self) -> usize {
+        // Sequence n always lives in slot n & mask, so the next sequence is also the scan position
+        let next_seq = self.next_seq.load(Ordering::Relaxed);
+        let mut policy = (self.policy.window > 1)
+            .then(|| self.policy.policy.lock().unwrap_or_else(|e| e.into_inner()));
+        let start = next_seq as usize & self.mask;
+        self.refill();
+        self.take_published();
+        let run = self.published.run_from(start, self.capacity);
+        // Clear before marking Sequenced: after that the next lap may set these bits again
+        self.published.clear(start, run);
+
+        // Write each batch's sequence numbers, then make them visible with one fence
+        // before the state stores, rather than two Release stores per slot
+        for batch in (0..run).step_by(FENCE_BATCH) {
+            let batch = batch..(batch + FENCE_BATCH).min(run);
+            for offset in batch.clone() {
+                let position = next_seq as usize + offset;
+                let slot = &self.slots[position & self.mask];
+                if offset + prefetch::DISTANCE < run {
+                    self.prefetch_slot((position + prefetch::DISTANCE) as u64);
+                }
+
+                if let Some(policy) = policy.as_mut() {
+                    // Rotate the chosen event to the front so the others keep their claim order
+                    let chosen = self.select(policy.as_mut(), position);
+                    for from in (1..=chosen).rev() {
+                        let later = &self.slots[(position + from) & self.mask];
+                        let earlier = &self.slots[(position + from - 1) & self.mask];
+                        // SAFETY: every slot in the window is Published, so only the sequencer touches them
+                        unsafe { earlier.swap_contents(later) };
+                    }
+                }
+
+                slot.sequence
+                    .store(next_seq + offset as u64, Ordering::Relaxed);
+                if RELEASE_PER_SLOT {
+                    slot.state
+                        .store(SlotState::Sequenced as u8, Ordering::Release);
+                }
+            }
+            if RELEASE_PER_SLOT {
+                continue;
+            }
+
+            fence(Ordering::Release);
+            for offset in batch {
+                let slot = &self.slots[(next_seq as usize + offset) & self.mask];
+                slot.state
+                    .store(SlotState::Sequenced as u8, Ordering::Relaxed);
+            }
+        }
+
+        if run > 0 {
+            // Publish the whole run to consumers at once
+            self.advance(next_seq + run as u64);
+        }
+        run
+    }
+
+    /// Sequence a claimed slot in place, skipping Published. Only for single-producer
+    /// buffers, where claim order is sequence order and nothing else sequences; the one
+    /// `Producer` is not `Sync`, so the load and store of `next_seq` cannot interleave.
+    pub(crate) fn sequence_in_place(&self, index: usize) {
+        let sequence = self.next_seq.load(Ordering::Relaxed);
+        let slot = &self.slots[index];
+        slot.sequence.store(sequence, Ordering::Release);
+        slot.state
+            .store(SlotState::Sequenced as u8, Ordering::Release);
+        self.advance(sequence + 1);
+    }
+
+    /// Move spilled events into whatever ring slots are free, publishing them like a
+    /// producer would. The caller must hold `sequencing`.
+    fn refill(&self) {
+        let Some(overflow) = &self.overflow else {
+            return;
+        };
+        overflow.drain_into(|entry| loop {
+            match try_claim(self, None) {
+                Claim::Claimed(slot_ref) => {
+                    // SAFETY: We own exclusive access via Claimed state
+                    let mut contents = unsafe { SlotWriteGuard::new(slot_ref.slot) };
+                    contents.write_payload(entry.payload);
+                    contents.finish(
+                        entry.metadata,
+                        entry.timestamp,
+                        entry.producer_id,
+                        entry.flags,
+                        self.checksum,
+                    );
+                    slot_ref
+                        .slot
+                        .state
+                        .store(SlotState::Published as u8, Ordering::Release);
+                    self.publish_queue.push(slot_ref.index);
+                    return true;
+                }
+                Claim::Contended => hint::spin_loop(),
+                Claim::Full => return false,
+            }
+        });
+    }
+
+    /// Make everything below `next_seq` visible to consumers
+    fn advance(&self, next_seq: u64) {
+        self.next_seq.store(next_seq, Ordering::Release);
+        if let Some(ttl) = &self.ttl {
+            let now = Instant::now();
+            ttl.record(next_seq, now);
+            ttl.expire(now, next_seq.saturating_sub(self.capacity as u64));
+        }
+        let tail = self.update_tail();
+        if let Some(backpressure) = &self.backpressure {
+            backpressure.update(next_seq - tail);
+        }
+        self.notifier.notify_all();
+    }
+
+    /// Give `sequence` to a slot whose producer claimed it but never published, so the
+    /// stream can move on. Fails if the producer published in the meantime, or has not
+    /// finished claiming `sequence` yet. The slot is not reused until the producer lets
+    /// go of it. The caller must hold `sequencing` and `sequence` must be the next to
+    /// assign.
+    pub(crate) fn skip_claim(&self, sequence: u64) -> bool {
+        let slot = &self.slots[(sequence as usize) & self.mask];
+        // The claim for `sequence` moved the generation on before writing, and nothing
+        // moves it again while the slot is Claimed, so this is the claim the CAS takes
+        if slot.generation.load(Ordering::Acquire) != self.generation(sequence) {
+            return false;
+        }
+        // Take the slot from its producer first; its own publish then fails
+        if slot
+            .state
+            .compare_exchange(
+                SlotState::Claimed as u8,
+                SlotState::Published as u8,
+                Ordering::AcqRel,
+                Ordering::Relaxed,
+            )
+            .is_err()
+        {
+            return false;
+        }
+        // Its producer may be letting go of the slot meanwhile, so keep that flag
+        slot.flags.fetch_or(SKIPPED | EXPIRED, Ordering::Relaxed);
+        slot.sequence.store(sequence, Ordering::Relaxed);
+        slot.state
+            .store(SlotState::Sequenced as u8, Ordering::Release);
+
+        self.skipped_claims.fetch_add(1, Ordering::Relaxed);
+        self.advance(sequence + 1);
+        true
+    }
+
+    /// Expire events older than the TTL, returning when the next ones will expire.
+    /// The caller must hold `sequencing`.
+    pub(crate) fn expire_events(&self) -> Option<Instant> {
+        let ttl = self.ttl.as_ref()?;
+        let next = self.next_seq.load(Ordering::Relaxed);
+        ttl.expire(Instant::now(), next.saturating_sub(self.capacity as u64))
+    }
+
+    /// Number of events the TTL removed before they were overwritten
+    pub fn expired(&self) -> u64 {
+        self.ttl.as_ref().map_or(0, Ttl::expired)
+    }
+
+    /// Whether producers are currently held back because consumers lag too far behind
+    pub fn is_backpressured(&self) -> bool {
+        self.backpressure
+            .as_ref()
+            .is_some_and(Backpressure::engaged)
+    }
+
+    /// How far the slowest registered consumer is behind the sequencer
+    pub(crate) fn lag(&self) -> u64 {
+        let tail = self.update_tail();
+        self.next_seq.load(Ordering::Acquire).saturating_sub(tail)
+    }
+
+    /// Number of claims the sequencer skipped because their producer never published
+    pub fn skipped_claims(&self) -> u64 {
+        self.skipped_claims.load(Ordering::Relaxed)
+    }
+
+    /// Flag the slots producers have queued as published since the last pass.
+    /// An index can outlive its publish after `rebuild_published`, so only slots
+    /// that are still Published get flagged.
+    fn take_published(&self) {
+        while let Some(index) = self.publish_queue.pop() {
+            if self.slots[index].state.load(Ordering::Acquire) == SlotState::Published as u8 {
+                self.published.set(index);
+            }
+        }
+    }
+
+    /// Re-flag every Published slot in the bitmap, e.g. after a sequencing pass was
+    /// interrupted between clearing bits and sequencing their slots
+    pub(crate) fn rebuild_published(&self) {
+        for (index, slot) in self.slots.iter().enumerate() {
+            if slot.state.load(Ordering::Acquire) == SlotState::Published as u8 {
+                self.published.set(index);
+            }
+        }
+    }
+
+    /// Ask `policy` which published event, counting from `position`, to sequence there
+    fn select(&self, policy: &mut dyn SequencerPolicy<T>, position: usize) -> usize {
+        let mut candidates = Vec::with_capacity(self.policy.window);
+        for offset in 0..self.policy.window.min(self.capacity) {
+            let slot = &self.slots[(position + offset) & self.mask];
+            if slot.state.load(Ordering::Acquire) != SlotState::Published as u8 {
+                break;
+            }
+            // SAFETY: Published slots are fully written and only the sequencer touches them
+            let contents = unsafe { SlotReadGuard::new(slot) };
+            candidates.push(Candidate {
+                timestamp: contents.timestamp(),
+                producer_id: contents.producer_id(),
+                payload: contents.payload(),
+            });
+        }
+        policy.select(&candidates).min(candidates.len() - 1)
+    }
+
+    /// Create a producer handle. IDs are handed out in creation order and wrap after 255.
+    ///
+    /// # Panics
+    ///
+    /// On a `single_producer` buffer, if a producer was already created.
+    pub fn producer(self: &Arc<Self>) -> Producer<T, M> {
+        if self.single_producer {
+            assert!(
+                !self.producer_taken.swap(true, Ordering::AcqRel),
+                "single-producer buffer already has a producer"
+            );
+        }
+        let id = self.next_producer_id.fetch_add(1, Ordering::Relaxed);
+        Producer::new(self.clone(), id as u8)
+    }
+
+    /// Create a new consumer handle. In work-queue mode all handles share one cursor.
+    pub fn consumer(self: &Arc<Self>) -> Consumer<T, M> {
+        match self.delivery {
+            DeliveryMode::Broadcast => Consumer::new(self.clone()),
+            DeliveryMode::WorkQueue => {
+                let group = self
+                    .work_cursor
+                    .get_or_init(|| Arc::new(self.register_consumer()));
+                Consumer::with_group(self.clone(), group.clone())
+            }
+        }
+    }
+
+    /// Register a new consumer position starting at the oldest resident event. Without
+    /// a consumer the tail is already past it, so it is lowered to the new position;
+    /// producers that checked a slot against the old tail may still reuse it, so reads
+    /// below `next_seq` as of now are checked.
+    pub(crate) fn register_consumer(&self) -> Registration {
+        let registration =
+            self.consumers.register_below(self.resident_range().start, Some(&self.tail));
+        self.consumers.check_below(self.next_seq.load(Ordering::SeqCst));
+        registration
+    }
+
+    /// Number of consumers currently gating slot recycling
+    pub fn registered_consumers(&self) -> usize {
+        self.consumers.len()
+    }
+
+    /// Lowest sequence a registered consumer may still read. Slots holding anything
+    /// below it may be recycled; with no consumer holding history back it is the next
+    /// sequence to be assigned.
+    pub fn tail_sequence(&self) -> u64 {
+        self.update_tail()
+    }
+
+    /// Recompute `tail` from the registered consumers and return it.
+    /// With no registered consumers nothing holds history back, so the tail is `next_seq`.
+    pub(crate) fn update_tail(&self) -> u64 {
+        let next = self.next_seq.load(Ordering::Acquire);
+        // Stored under the registry lock, so a registration lowering it comes after
+        self.consumers.with_min(|min| {
+            let tail = min.map_or(next, |min| min.min(next));
+            self.tail.store(tail, Ordering::Release);
+            tail
+        })
+    }
+
+    /// Whether producers may reuse a slot before every registered consumer has read it
+    pub(crate) fn reclaims_unread(&self) -> bool {
+        self.ttl.is_some() || self.on_full == OnFull::OverwriteOldest
+    }
+
+    /// Whether every registered consumer has moved past `sequence`, so its slot may be reused
+    pub(crate) fn recyclable(&self, sequence: u64) -> bool {
+        sequence < self.tail.load(Ordering::Acquire)
+            || self.ttl.as_ref().is_some_and(|ttl| sequence < ttl.floor())
+            || sequence < self.update_tail()
+    }
+
+    /// Run `handler` on a dedicated thread for every event, starting like `consumer()`
+    pub fn subscribe<F>(self: &Arc<Self>, handler: F) -> SubscriptionHandle
+    where
+        F: FnMut(Event<T, M>) + Send + 'static,
+    {
+        start_subscription(self.clone(), handler)
+    }
+
+    /// Create a consumer that only yields events whose payload matches `predicate`
+    pub fn consumer_filtered<F>(
+        self: &Arc<Self>,
+        mut predicate: F,
+    ) -> Filter<Consumer<T, M>, impl FnMut(&Event<T, M>) -> bool>
+    where
+        F: FnMut(&T) -> bool,
+    {
+        self.consumer().filter(move |event| predicate(&event.payload))
+    }
+
+    /// Create a consumer that reads whatever is resident without ever holding back producers
+    pub fn weak_consumer(self: &Arc<Self>) -> WeakConsumer<T, M> {
+        WeakConsumer::new(self.clone())
+    }
+
+    /// Create a consumer group whose members split the stream between them
+    pub fn consumer_group(self: &Arc<Self>) -> ConsumerGroup<T, M> {
+        ConsumerGroup::new(self.clone())
+    }
+
+    /// Write every slot once so the ring's pages are faulted in now rather than on
+    /// the first lap, and the sequencer's structures beside it likewise. Only sound
+    /// before anything else can reach the slots.
+    fn prefault(&self) {
+        // A ring file's slots hold the events it was closed with
+        if !matches!(self.slots, Slots::File { .. }) {
+            for slot in self.slots.iter() {
+                slot.state.store(SlotState::Free as u8, Ordering::Relaxed);
+                // SAFETY: No producer or consumer exists yet
+                unsafe { slot.prefault() };
+            }
+        }
+        self.published.prefault();
+        self.publish_queue.prefault();
     }
 
-    /// Create a new consumer handle
-    pub fn consumer(self: &Arc<Self>) -> Consumer<T> {
-        Consumer::new(self.clone())
+    /// `mlock` the hot ranges, unlocking any already locked if one is refused
+    fn lock_memory(&mut self) -> Result<(), BuildError> {
+        let ranges = self.hot_ranges();
+        for (index, &(addr, len)) in ranges.iter().enumerate() {
+            if let Err(err) = affinity::lock_memory(addr, len) {
+                for &(addr, len) in &ranges[..index] {
+                    affinity::unlock_memory(addr, len);
+                }
+                let needed = ranges.iter().map(|&(_, len)| len).sum();
+                return Err(lock_error(err, needed, affinity::memlock_limit()));
+            }
+        }
+        self.locked = true;
+        Ok(())
     }
 
     /// Get the buffer capacity
@@ -72,6 +705,289 @@ where
         self.capacity
     }
 
+    /// Events claimed but not yet sequenced: still being written, or published and
+    /// waiting for the sequencer
+    pub fn pending(&self) -> usize {
+        let sequenced = self.next_seq.load(Ordering::Acquire) as usize;
+        self.head.load(Ordering::Acquire).saturating_sub(sequenced)
+    }
+
+    /// Pending events that are published and only wait for the sequencer, read from
+    /// the sequencer's bitmap of published slots 64 at a time plus its queue of ones
+    /// it has not flagged yet. A snapshot while producers and the sequencer run.
+    pub fn ready(&self) -> usize {
+        (self.published.count() + self.publish_queue.len()).min(self.pending())
+    }
+
+    /// Slots that cannot be claimed right now: pending events plus sequenced events
+    /// some registered consumer has not read yet. Walks the consumer registry.
+    pub fn len(&self) -> usize {
+        let mut oldest = self.update_tail();
+        if let Some(ttl) = &self.ttl {
+            oldest = oldest.max(ttl.floor());
+        }
+        let len = self.head.load(Ordering::Acquire).saturating_sub(oldest as usize);
+        len.min(self.capacity)
+    }
+
+    /// Whether every slot can be claimed without waiting
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Events pushed with `OnFull::Grow` that are still waiting for a ring slot.
+    /// They are not counted by `len`, `pending` or `flush`.
+    pub fn overflowed(&self) -> usize {
+        self.overflow.as_ref().map_or(0, Overflow::len)
+    }
+
+    /// How many pushes would succeed right now without waiting on consumers
+    pub fn remaining_capacity(&self) -> usize {
+        self.capacity - self.len()
+    }
+
+    /// Bytes this buffer holds, for sizing many buffers per process. The overflow
+    /// segments and the consumer registry come and go with use, so this is a snapshot;
+    /// consumer handles themselves live wherever their owners keep them.
+    pub fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            slots: self.slots.memory_usage(),
+            side: size_of::<Self>()
+                + self.published.byte_range().1
+                + self.publish_queue.byte_range().1
+                + self.overflow.as_ref().map_or(0, Overflow::memory_usage)
+                + self.ttl.as_ref().map_or(0, Ttl::memory_usage),
+            consumers: self.consumers.memory_usage(),
+        }
+    }
+
+    /// Copy every resident sequenced event, oldest first, without moving any consumer.
+    /// The snapshot ends at the last event sequenced when it was called.
+    ///
+    /// Best effort rather than a point in time: nothing holds producers back while it
+    /// copies, so an event they overwrite before its copy is done is left out, and the
+    /// snapshot may start later than the ring did or miss events in between.
+    pub fn snapshot(&self) -> Vec<Event<T, M>> {
+        self.resident_range()
+            .filter_map(|sequence| self.copy_resident(sequence))
+            .collect()
+    }
+
+    /// Copy the requested events that are still resident, without a consumer, and report
+    /// which part of `range` was recycled and which is not sequenced yet.
+    ///
+    /// Like `snapshot`, best effort: producers may overwrite events while they are being
+    /// copied, and `recycled` then runs up to the last of those, good copies before it
+    /// included.
+    pub fn read_range(&self, range: Range<u64>) -> RangeRead<T, M> {
+        let resident = self.resident_range();
+        let end = range.end.max(range.start);
+        let resident_start = resident.start.clamp(range.start, end);
+        let resident_end = resident.end.clamp(resident_start, end);
+
+        let mut events = Vec::with_capacity((resident_end - resident_start) as usize);
+        let mut recycled_end = resident_start;
+        for sequence in resident_start..resident_end {
+            match self.copy_resident(sequence) {
+                Some(event) => events.push(event),
+                // Overwritten since `resident` was taken; slots are reused oldest first
+                None if self.sequenced_slot(sequence).is_none() => recycled_end = sequence + 1,
+                None => {}
+            }
+        }
+        // Keep `recycled` a prefix of the request, even if that drops copies that were good
+        events.retain(|event| event.sequence >= recycled_end);
+
+        RangeRead {
+            events,
+            recycled: range.start..recycled_end,
+            unsequenced: resident_end..end,
+        }
+    }
+
+    /// Copy out `sequence`, or `None` if it is not resident or was a skipped claim.
+    /// A producer may reuse the slot at any time, so this goes through `copy_slot`.
+    pub(crate) fn copy_resident(&self, sequence: u64) -> Option<Event<T, M>> {
+        if self.sequenced_slot(sequence)?.is_skipped() {
+            return None;
+        }
+        self.copy_slot(sequence).map(|(event, _)| event)
+    }
+
+    /// Copy out the event for `sequence` and the checksum stored with it, for a reader
+    /// that nothing stops a producer from overtaking. Seqlock style: check the slot holds
+    /// `sequence`, copy it with volatile reads, then check its generation again behind an
+    /// Acquire fence. A claim bumps the generation and then fences before it writes, so a
+    /// copy that caught any of a newer event's bytes sees the newer generation and is
+    /// thrown away. `None` if `sequence` is not resident, or stopped being so mid-copy.
+    pub(crate) fn copy_slot(&self, sequence: u64) -> Option<(Event<T, M>, Option<u32>)> {
+        let slot = self.sequenced_slot(sequence)?;
+        let copy = slot.copy_volatile();
+        fence(Ordering::Acquire);
+        if slot.generation.load(Ordering::Relaxed) != self.generation(sequence) {
+            return None;
+        }
+        let event = Event {
+            sequence,
+            generation: self.generation(sequence),
+            timestamp: copy.timestamp,
+            producer_id: copy.producer_id,
+            flags: copy.flags,
+            late: false,
+            // SAFETY: The slot held the sequenced event from before the copy until after
+            // it, so nothing rewrote it in between and the copy is whole
+            metadata: unsafe { copy.metadata.assume_init() },
+            payload: unsafe { copy.payload.assume_init() },
+        };
+        Some((event, copy.checksum))
+    }
+
+    /// The slot holding `sequence`, if it is sequenced and not yet recycled
+    pub(crate) fn sequenced_slot(&self, sequence: u64) -> Option<&Slot<T, M>> {
+        let slot = &self.slots[(sequence as usize) & self.mask];
+
+        // A different generation means the slot holds another lap - reader is too slow.
+        // Read it before the state: the claim for `sequence` bumps it after leaving the
+        // previous lap's Sequenced state, so a state read first could still be the old
+        // lap's while the generation is already ours, mid-write.
+        if slot.generation.load(Ordering::Acquire) != self.generation(sequence) {
+            return None;
+        }
+
+        let state = loop {
+            let state = slot.state.load(Ordering::Acquire);
+            // A claimer working from a stale head holds a sequenced slot as Claimed for a
+            // moment before it sees the generation is taken and puts the state back, as
+            // does a claim of the next lap before it bumps the generation; wait for either
+            if state != SlotState::Claimed as u8
+                || sequence >= self.next_seq.load(Ordering::Acquire)
+            {
+                break state;
+            }
+            if slot.generation.load(Ordering::Acquire) != self.generation(sequence) {
+                return None;
+            }
+            hint::spin_loop();
+        };
+        if state != SlotState::Sequenced as u8 {
+            return None;
+        }
+
+        Some(slot)
+    }
+
+    /// Generation of the slot claim that holds `sequence`: its lap around the ring, plus one
+    pub(crate) fn generation(&self, sequence: u64) -> u32 {
+        ((sequence >> self.capacity.trailing_zeros()) + 1) as u32
+    }
+
+    /// Copy out the event for `sequence` if it is sequenced and resident
+    /// Skipped claims are stepped over. Goes through `copy_slot`, since nothing holds
+    /// the slot back from producers; a copy that loses the slot mid-way looks again.
+    pub(crate) fn read(&self, mut sequence: u64) -> Result<Option<Event<T, M>>, ConsumerError> {
+        loop {
+            match self.locate(sequence)? {
+                Some(slot) if slot.is_skipped() => sequence += 1,
+                Some(_) => {
+                    if let Some((event, _)) = self.copy_slot(sequence) {
+                        return Ok(Some(event));
+                    }
+                }
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// Check `payload`, read from the slot holding `sequence`, against `stored`, the
+    /// checksum read with it
+    pub(crate) fn verify(
+        &self,
+        sequence: u64,
+        payload: &T,
+        stored: Option<u32>,
+    ) -> Result<(), ConsumerError> {
+        match (self.checksum, stored) {
+            (Some(checksum), Some(stored)) if stored != checksum(payload) => {
+                Err(ConsumerError::Corrupted { sequence })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Start loading the slot for `position` into cache; see `prefetch::prefetch`
+    #[inline(always)]
+    pub(crate) fn prefetch_slot(&self, position: u64) {
+        prefetch(&self.slots[position as usize & self.mask]);
+    }
+
+    /// Copy out the event for `sequence` and the checksum stored with it. The caller
+    /// has checked it is sequenced, and holds it back from producers; otherwise see
+    /// `copy_slot`.
+    pub(crate) fn read_slot(&self, sequence: u64) -> (Event<T, M>, Option<u32>) {
+        let slot = &self.slots[(sequence as usize) & self.mask];
+
+        // Read payload and metadata
+        // SAFETY: State is Sequenced, so payload is initialized
+        let contents = unsafe { SlotReadGuard::new(slot) };
+
+        let event = Event {
+            sequence,
+            generation: self.generation(sequence),
+            timestamp: contents.timestamp(),
+            producer_id: contents.producer_id(),
+            flags: contents.flags(),
+            late: false,
+            metadata: contents.metadata(),
+            payload: *contents.payload(),
+        };
+        (event, contents.checksum())
+    }
+
+    /// Find the slot holding `sequence`, distinguishing "not sequenced yet" from "already recycled"
+    pub(crate) fn locate(&self, sequence: u64) -> Result<Option<&Slot<T, M>>, ConsumerError> {
+        if let Some(slot) = self.sequenced_slot(sequence) {
+            return Ok(Some(slot));
+        }
+
+        if sequence < self.next_seq.load(Ordering::Acquire) {
+            // Sequenced at some point - it may have landed after our first look
+            if let Some(slot) = self.sequenced_slot(sequence) {
+                return Ok(Some(slot));
+            }
+            let oldest = self.resident_range().start.max(sequence + 1);
+            return Err(ConsumerError::Lagged {
+                skipped: oldest - sequence,
+            });
+        }
+
+        Ok(None)
+    }
+
+    /// Sequence numbers whose events are still resident in the ring
+    pub(crate) fn resident_range(&self) -> Range<u64> {
+        let next = self.next_seq.load(Ordering::Acquire);
+        let oldest = next.saturating_sub(self.capacity as u64);
+        match &self.ttl {
+            Some(ttl) => oldest.max(ttl.floor()).min(next)..next,
+            None => oldest..next,
+        }
+    }
+
+    /// Get the strategy blocking consumers use to wait for events
+    pub fn wait_strategy(&self) -> WaitStrategy {
+        self.wait_strategy
+    }
+
+    /// Get the strategy the sequencer uses while idle
+    pub fn sequencer_wait_strategy(&self) -> WaitStrategy {
+        self.sequencer_wait_strategy
+    }
+
+    /// Get how `consumer()` handles share events
+    pub fn delivery(&self) -> DeliveryMode {
+        self.delivery
+    }
+
     #[cfg(test)]
     fn slots_are_free(&self) -> bool {
         self.slots.iter().all(|slot| {
@@ -81,37 +997,463 @@ where
     }
 }
 
-pub struct BufferBuilder<T> {
+impl<T, M> Buffer<T, M> {
+    /// The memory every push and sequencing pass touches: the ring, the bitmap of
+    /// published slots and the publish queue
+    fn hot_ranges(&self) -> [(*const u8, usize); 3] {
+        [
+            self.slots.byte_range(),
+            self.published.byte_range(),
+            self.publish_queue.byte_range(),
+        ]
+    }
+}
+
+impl<T, M> Drop for Buffer<T, M> {
+    fn drop(&mut self) {
+        let next_seq = self.next_seq.load(Ordering::Relaxed);
+        // Left marked open if it fails, so the next buffer on the file starts it afresh
+        let _ = self.slots.close_file(next_seq);
+        if self.locked {
+            for (addr, len) in self.hot_ranges() {
+                affinity::unlock_memory(addr, len);
+            }
+        }
+    }
+}
+
+/// Why `mlock` refused `needed` bytes: out of `RLIMIT_MEMLOCK` if it returned ENOMEM
+/// or EPERM with a finite `limit`, else whatever the OS said
+fn lock_error(err: io::Error, needed: usize, limit: Option<usize>) -> BuildError {
+    match (err.kind(), limit) {
+        (io::ErrorKind::OutOfMemory | io::ErrorKind::PermissionDenied, Some(limit)) => {
+            BuildError::MemlockLimit { needed, limit }
+        }
+        (kind, _) => BuildError::LockMemory(kind),
+    }
+}
+
+/// What `Buffer::read_range` found for the requested sequences
+#[derive(Debug, Clone)]
+pub struct RangeRead<T, M = ()> {
+    /// Copies of the requested events still in the ring, in sequence order.
+    /// Claims the sequencer skipped have no event and are absent.
+    pub events: Vec<Event<T, M>>,
+    /// The front of the request that was already recycled or expired
+    pub recycled: Range<u64>,
+    /// The end of the request that is not sequenced yet
+    pub unsequenced: Range<u64>,
+}
+
+/// Bytes held by a buffer, from `Buffer::memory_usage`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// The ring: capacity times the slot size, or the whole mapping for mapped rings
+    pub slots: usize,
+    /// The buffer itself and what the sequencer keeps beside the ring: the bitmap and
+    /// queue of published slots, events spilled with `OnFull::Grow`, and TTL runs
+    pub side: usize,
+    /// A cursor on its own cache line per registered consumer
+    pub consumers: usize,
+}
+
+impl MemoryUsage {
+    pub fn total(&self) -> usize {
+        self.slots + self.side + self.consumers
+    }
+}
+
+pub struct BufferBuilder<T, M = ()> {
     capacity: Option<usize>,
+    capacity_bytes: Option<usize>,
+    max_capacity: usize,
+    prefault: bool,
+    lock_memory: bool,
+    checksum: Option<fn(&T) -> u32>,
+    numa_node: Option<usize>,
+    mapped_file: Option<PathBuf>,
+    wait_strategy: WaitStrategy,
+    sequencer_wait_strategy: WaitStrategy,
+    policy: Option<Box<dyn SequencerPolicy<T>>>,
+    sequencer_thread: ThreadConfig,
+    restart_sequencer: bool,
+    single_producer: bool,
+    timestamps: bool,
+    idle_hook: Option<IdleHook>,
+    claim_timeout: Option<Duration>,
+    on_stuck_claim: Option<Box<dyn FnMut(u64) + Send>>,
+    backpressure: Option<Backpressure>,
+    ttl: Option<Duration>,
+    on_full: OnFull,
+    /// Ring storage from a `StaticBuffer`, instead of allocating it
+    slots: Option<Slots<T, M>>,
+    delivery: DeliveryMode,
     _phantom: std::marker::PhantomData<T>,
 }
 
-impl<T> BufferBuilder<T>
+impl<T, M> BufferBuilder<T, M>
 where
     T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
 {
     pub fn new() -> Self {
         Self {
             capacity: None,
+            capacity_bytes: None,
+            max_capacity: MAX_CAPACITY,
+            prefault: false,
+            lock_memory: false,
+            checksum: None,
+            numa_node: None,
+            mapped_file: None,
+            wait_strategy: WaitStrategy::default(),
+            sequencer_wait_strategy: WaitStrategy::BusySpin,
+            policy: None,
+            sequencer_thread: ThreadConfig::default(),
+            restart_sequencer: false,
+            single_producer: false,
+            timestamps: true,
+            idle_hook: None,
+            claim_timeout: None,
+            on_stuck_claim: None,
+            backpressure: None,
+            ttl: None,
+            on_full: OnFull::default(),
+            slots: None,
+            delivery: DeliveryMode::default(),
             _phantom: std::marker::PhantomData,
         }
     }
 
+    /// Start from settings loaded at runtime, e.g. with `BufferConfig::from_env`.
+    /// Later builder calls still override them.
+    pub fn from_config(config: BufferConfig) -> Self {
+        let mut builder = Self::new();
+        builder.capacity = config.capacity;
+        if let Some(strategy) = config.wait_strategy {
+            builder = builder.wait_strategy(strategy);
+        }
+        if let Some(strategy) = config.sequencer_wait_strategy {
+            builder = builder.sequencer_wait_strategy(strategy);
+        }
+        if let Some(on_full) = config.on_full {
+            builder = builder.on_full(on_full);
+        }
+        if let Some(core) = config.sequencer_core {
+            builder = builder.sequencer_core(core);
+        }
+        builder
+    }
+
     pub fn capacity(mut self, capacity: usize) -> Self {
         self.capacity = Some(capacity);
+        self.capacity_bytes = None;
+        self
+    }
+
+    /// Size the ring by memory instead of slot count: the largest power-of-two number
+    /// of slots whose storage fits in `bytes`, capped at `max_capacity`. `build` fails
+    /// with `InvalidCapacity` if not even one slot fits.
+    pub fn capacity_bytes(mut self, bytes: usize) -> Self {
+        self.capacity_bytes = Some(bytes);
+        self.capacity = None;
+        self
+    }
+
+    /// Raise or lower the largest slot count `build` accepts (default 2^30).
+    /// Larger requests fail with `TooLarge`.
+    pub fn max_capacity(mut self, slots: usize) -> Self {
+        self.max_capacity = slots;
+        self
+    }
+
+    /// Set how blocking consumer calls wait for new events
+    pub fn wait_strategy(mut self, strategy: WaitStrategy) -> Self {
+        self.wait_strategy = strategy;
+        self
+    }
+
+    /// Set how the sequencer thread waits while nothing is published.
+    /// Defaults to `BusySpin`; `Blocking` parks it until a producer publishes.
+    pub fn sequencer_wait_strategy(mut self, strategy: WaitStrategy) -> Self {
+        self.sequencer_wait_strategy = strategy;
+        self
+    }
+
+    /// Name the sequencer thread, as shown by `top` and debuggers. Defaults to `lftes-sequencer`.
+    pub fn sequencer_thread_name(mut self, name: impl Into<String>) -> Self {
+        self.sequencer_thread.name = Some(name.into());
+        self
+    }
+
+    /// Pin the sequencer thread to one CPU core (Linux only)
+    pub fn sequencer_core(mut self, core: usize) -> Self {
+        self.sequencer_thread.core = Some(core);
+        self
+    }
+
+    /// Allocate the ring on NUMA node `node` and run the sequencer thread on that node's
+    /// CPUs, so neither reaches across sockets (Linux only). An explicit
+    /// `sequencer_core` still wins. `build` fails with `NumaNode` if the ring cannot
+    /// be bound to the node; see `numa::nodes` for the nodes there are.
+    pub fn numa_node(mut self, node: usize) -> Self {
+        self.numa_node = Some(node);
+        self.sequencer_thread.node = Some(node);
         self
     }
 
-    pub fn build(self) -> Result<Arc<Buffer<T>>, BuildError> {
-        let capacity = self.capacity.unwrap_or(1024);
-        let buffer = Buffer::new(capacity)?;
+    /// Keep the ring in a shared mapping of the file at `path` instead of anonymous
+    /// memory (Linux only), so its pages are page cache the OS can write back and evict.
+    /// Dropping the buffer writes the ring out and marks the file closed cleanly; a
+    /// buffer built later on the same file, with the same capacity and types, starts
+    /// with the events sequenced by then, so consumers resuming from a cursor store
+    /// carry on where they were and the next push takes the next sequence number.
+    /// Claims that were never sequenced are lost, and a file whose buffer was never
+    /// dropped (the process died) starts out empty.
+    ///
+    /// `build` fails with `MappedFile` if the file cannot be opened or mapped, if it is
+    /// laid out for another capacity or slot type (`InvalidData`), or if another buffer
+    /// has it open (`WouldBlock`). A file ring cannot also be placed with `numa_node`,
+    /// and `prefault` leaves its slots alone.
+    ///
+    /// # Safety
+    ///
+    /// Events are read back from the file as raw bytes, so `T` and `M` must be plain
+    /// data, with no references, pointers or handles into the process that wrote them,
+    /// and nothing but buffers built this way with these types may write the file.
+    pub unsafe fn mapped_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.mapped_file = Some(path.into());
+        self
+    }
+
+    /// Run the sequencer thread under `SCHED_FIFO` at `priority`, 1-99 (Linux only)
+    pub fn sequencer_priority(mut self, priority: i32) -> Self {
+        self.sequencer_thread.priority = Some(priority);
+        self
+    }
+
+    /// Restart the sequencer loop if it panics, instead of leaving the buffer unsequenced.
+    /// Either way `SequencerHandle` reports what happened.
+    pub fn restart_sequencer_on_panic(mut self, restart: bool) -> Self {
+        self.restart_sequencer = restart;
+        self
+    }
+
+    /// Promise that only one producer will ever push. Its `push` then assigns the
+    /// sequence number itself and `start` runs no sequencer thread, saving a thread
+    /// and a state transition per event. Sequencer policies, idle hooks and the
+    /// stuck-claim timeout have no effect; `producer()` panics on a second call.
+    pub fn single_producer(mut self) -> Self {
+        self.single_producer = true;
+        self
+    }
+
+    /// Whether producers capture a timestamp per event (the default). Without it every
+    /// `Event::timestamp` is 0 and `push` skips the cycle-counter read, which is a
+    /// measurable share of a push; `MergeConsumer` needs timestamps to order events.
+    pub fn timestamps(mut self, capture: bool) -> Self {
+        self.timestamps = capture;
+        self
+    }
+
+    /// Touch every slot, and the sequencer's structures beside the ring, at build
+    /// time, so the first lap around a fresh ring doesn't take a page fault per page.
+    /// Worth it for large rings, and for `StaticBuffer` rings, which start out as
+    /// untouched zero pages.
+    pub fn prefault(mut self, prefault: bool) -> Self {
+        self.prefault = prefault;
+        self
+    }
+
+    /// Also `mlock` the ring, and the sequencer's bitmap and queue beside it, so none
+    /// of them is ever paged out and a run takes no major faults on them (Linux only).
+    /// Unless the ring is static, they get pages of their own, so locking them pins,
+    /// and dropping the buffer unlocks, no other allocation's memory.
+    /// `build` fails with `MemlockLimit` if that is more than `RLIMIT_MEMLOCK` allows,
+    /// or `LockMemory` if the OS refuses for another reason. Implies `prefault`.
+    pub fn lock_memory(mut self, lock: bool) -> Self {
+        self.lock_memory = lock;
+        self
+    }
+
+    /// Checksum every payload (CRC-32 over its `Hash` impl) when it is pushed, and have
+    /// consumers check it when they read the event, returning `ConsumerError::Corrupted`
+    /// on a mismatch. Catches memory corruption from bad RAM or stray writes by unsafe
+    /// code elsewhere in the process, at the cost of hashing each payload twice.
+    /// Unavailable with the `compact-slots` feature, whose slots have no room for it.
+    pub fn checksums(mut self, enabled: bool) -> Self
+    where
+        T: Hash,
+    {
+        self.checksum = enabled.then_some(checksum::<T> as fn(&T) -> u32);
+        self
+    }
+
+    /// Have the sequencer call `hook` after every `interval` in which nothing was sequenced,
+    /// passing how long it has been quiet. Lets a quiet source be told apart from a stalled one.
+    pub fn on_sequencer_idle<F>(mut self, interval: Duration, hook: F) -> Self
+    where
+        F: FnMut(Duration) + Send + 'static,
+    {
+        self.idle_hook = Some(IdleHook {
+            interval,
+            hook: Box::new(hook),
+        });
+        self
+    }
+
+    /// Skip a slot whose producer claimed it but has not published within `timeout`,
+    /// e.g. because the producer crashed. The skipped sequence number carries no event
+    /// and the late producer's push fails with `ClaimExpired`. Off by default.
+    ///
+    /// The slot itself stays the producer's until its push returns or unwinds, so
+    /// producers that come round to it again wait for that, and a producer stalled
+    /// for good holds back the ring one lap on.
+    pub fn stuck_claim_timeout(mut self, timeout: Duration) -> Self {
+        self.claim_timeout = Some(timeout);
+        self
+    }
+
+    /// Call `hook` with the sequence number of every claim skipped by `stuck_claim_timeout`
+    pub fn on_stuck_claim<F>(mut self, hook: F) -> Self
+    where
+        F: FnMut(u64) + Send + 'static,
+    {
+        self.on_stuck_claim = Some(Box::new(hook));
+        self
+    }
+
+    /// Hold producers back once the slowest registered consumer is `high` events behind,
+    /// until it is back within `low`. `mode` picks whether `push` fails or waits meanwhile.
+    /// Off by default, leaving a full ring as the only backpressure.
+    pub fn backpressure(mut self, high: usize, low: usize, mode: BackpressureMode) -> Self {
+        self.backpressure = Some(Backpressure::new(high as u64, low as u64, mode));
+        self
+    }
+
+    /// Expire events `ttl` after they were sequenced. Expired events are skipped by
+    /// readers as a lag, and their slots can be reused even if a registered consumer
+    /// has not read them yet. `Buffer::expired` counts them. Off by default.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Choose what `push` does when the ring is full. Defaults to `OnFull::Block`.
+    pub fn on_full(mut self, on_full: OnFull) -> Self {
+        self.on_full = on_full;
+        self
+    }
+
+    /// Decide which published event gets each sequence number. Defaults to `SlotOrder`.
+    pub fn sequencer_policy<P>(mut self, policy: P) -> Self
+    where
+        P: SequencerPolicy<T> + 'static,
+    {
+        self.policy = Some(Box::new(policy));
+        self
+    }
+
+    /// Choose between broadcast and work-queue semantics for `consumer()` handles
+    pub fn delivery(mut self, mode: DeliveryMode) -> Self {
+        self.delivery = mode;
+        self
+    }
+
+    /// Use `slots` as the ring. Only `StaticBuffer::builder` sets this.
+    #[cfg(not(loom))]
+    pub(crate) fn static_slots(mut self, slots: &'static [Slot<T, M>]) -> Self {
+        self.capacity = Some(slots.len());
+        self.slots = Some(Slots::Static(std::ptr::NonNull::from(slots)));
+        self
+    }
+
+    /// Build the buffer along with `n` producers holding IDs `0..n`, so each producer's
+    /// ID is fixed by its position rather than by which thread asked first.
+    /// Producers created later with `Buffer::producer` continue from `n`.
+    pub fn producers(self, n: usize) -> Result<WithProducers<T, M>, BuildError> {
+        let limit = if self.single_producer { 1 } else { u8::MAX as usize + 1 };
+        if n > limit {
+            return Err(BuildError::TooManyProducers);
+        }
+        let buffer = self.build()?;
+        let producers = (0..n).map(|_| buffer.producer()).collect();
+        Ok((buffer, producers))
+    }
+
+    pub fn build(self) -> Result<Arc<Buffer<T, M>>, BuildError> {
+        let capacity = match self.capacity_bytes {
+            Some(bytes) => {
+                let slots = (bytes / size_of::<Slot<T, M>>()).min(self.max_capacity);
+                if slots == 0 {
+                    return Err(BuildError::InvalidCapacity);
+                }
+                1 << slots.ilog2()
+            }
+            None => self.capacity.unwrap_or(1024),
+        };
+        // A 32-bit slot sequence is only unambiguous within 2^31 of a position on its lap
+        let sequence_limit = cfg!(feature = "sequence-32") && capacity > MAX_CAPACITY;
+        if capacity > self.max_capacity || sequence_limit {
+            return Err(BuildError::TooLarge);
+        }
+        if cfg!(feature = "compact-slots") && self.checksum.is_some() {
+            return Err(BuildError::InvalidConfig("compact slots cannot hold checksums".into()));
+        }
+        if self.mapped_file.is_some() && (self.slots.is_some() || self.numa_node.is_some()) {
+            let msg = "a mapped file ring cannot be static or placed on a NUMA node";
+            return Err(BuildError::InvalidConfig(msg.into()));
+        }
+        let mut buffer = match (self.slots, &self.mapped_file) {
+            // The static ring's size is fixed by its type
+            (Some(slots), _) if slots.len() != capacity => {
+                return Err(BuildError::InvalidCapacity);
+            }
+            (Some(slots), _) => Buffer::with_slots(slots, self.lock_memory),
+            (None, Some(path)) => Buffer::map_file(capacity, path, self.lock_memory)?,
+            (None, None) => Buffer::allocate(capacity, self.numa_node, self.lock_memory)?,
+        };
+        buffer.wait_strategy = self.wait_strategy;
+        buffer.sequencer_wait_strategy = self.sequencer_wait_strategy;
+        buffer.sequencer_thread = self.sequencer_thread;
+        buffer.restart_sequencer = self.restart_sequencer;
+        buffer.single_producer = self.single_producer;
+        buffer.timestamps = self.timestamps;
+        buffer.idle_hook = self.idle_hook.map(Mutex::new);
+        buffer.backpressure = self.backpressure;
+        buffer.ttl = self.ttl.map(Ttl::new);
+        buffer.on_full = self.on_full;
+        match self.on_full {
+            // Nothing would move spilled events into the ring
+            OnFull::Grow if self.single_producer => buffer.on_full = OnFull::Block,
+            OnFull::Grow => buffer.overflow = Some(Overflow::new(capacity)),
+            _ => {}
+        }
+        buffer.stuck_claims = self.claim_timeout.map(|timeout| {
+            Mutex::new(StuckClaims {
+                timeout,
+                hook: self.on_stuck_claim,
+            })
+        });
+        if let Some(policy) = self.policy {
+            buffer.policy = PolicyCell::new(policy);
+        }
+        buffer.delivery = self.delivery;
+        buffer.checksum = self.checksum;
+        if self.prefault || self.lock_memory {
+            buffer.prefault();
+        }
+        if self.lock_memory {
+            buffer.lock_memory()?;
+        }
         Ok(Arc::new(buffer))
     }
 }
 
-impl<T> Default for BufferBuilder<T>
+impl<T, M> Default for BufferBuilder<T, M>
 where
     T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
 {
     fn default() -> Self {
         Self::new()
@@ -148,6 +1490,45 @@ mod tests {
         assert_eq!(buffer.mask, 1023);
     }
 
+    #[test]
+    fn simd_payloads_round_trip_aligned() {
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        #[repr(align(32))]
+        struct Simd([f32; 8]);
+
+        let buffer = Buffer::<Simd>::builder().capacity(4).build().unwrap();
+        let producer = buffer.producer();
+        let mut consumer = buffer.consumer();
+        for lap in 0..3 {
+            for i in 0..4 {
+                producer.push(Simd([(lap * 4 + i) as f32; 8])).unwrap();
+            }
+            buffer.sequence_available();
+            for i in 0..4 {
+                let event = consumer.try_next_ref().unwrap().unwrap();
+                let payload: *const Simd = event.payload();
+                assert!(payload.is_aligned());
+                assert_eq!(*event.payload(), Simd([(lap * 4 + i) as f32; 8]));
+            }
+        }
+    }
+
+    #[test]
+    fn hot_counters_do_not_share_cache_lines() {
+        use crate::pad::CACHE_LINE;
+        use std::mem::offset_of;
+        let lines = [
+            offset_of!(Buffer<u64>, head),
+            offset_of!(Buffer<u64>, next_seq),
+            offset_of!(Buffer<u64>, tail),
+        ];
+        for offset in lines {
+            assert_eq!(offset % CACHE_LINE, 0);
+        }
+        assert!(lines[0] != lines[1] && lines[1] != lines[2] && lines[0] != lines[2]);
+        assert_eq!(size_of::<CachePadded<AtomicU64>>(), CACHE_LINE);
+    }
+
     #[test]
     fn slots_initialized_to_free() {
         let buffer = Buffer::<u64>::new(256).unwrap();
@@ -165,4 +1546,601 @@ mod tests {
         let buffer = Buffer::<u64>::builder().capacity(512).build().unwrap();
         assert_eq!(buffer.capacity, 512);
     }
+
+    #[test]
+    fn capacity_bytes_rounds_down_to_a_power_of_two() {
+        let slot = size_of::<Slot<u64>>();
+        let build = |bytes| Buffer::<u64>::builder().capacity_bytes(bytes).build();
+        assert_eq!(build(1024 * slot).unwrap().capacity(), 1024);
+        assert_eq!(build(100 * slot + slot / 2).unwrap().capacity(), 64);
+        assert_eq!(build(slot - 1).unwrap_err(), BuildError::InvalidCapacity);
+
+        let capped = Buffer::<u64>::builder()
+            .max_capacity(16)
+            .capacity_bytes(1 << 20)
+            .build()
+            .unwrap();
+        assert_eq!(capped.capacity(), 16);
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn prefaulted_and_locked_rings_work_as_usual() {
+        let buffer = Buffer::<[u64; 8]>::builder()
+            .capacity(4)
+            .prefault(true)
+            .build()
+            .unwrap();
+        assert!(buffer.slots_are_free());
+
+        // Without CAP_IPC_LOCK or room under RLIMIT_MEMLOCK there is nothing to check
+        let buffer = match Buffer::<u64>::builder().capacity(4).lock_memory(true).build() {
+            Ok(buffer) => buffer,
+            Err(
+                BuildError::MemlockLimit { .. }
+                | BuildError::LockMemory(io::ErrorKind::PermissionDenied)
+                | BuildError::LockMemory(io::ErrorKind::OutOfMemory),
+            ) => return,
+            Err(err) => panic!("{}", err),
+        };
+        assert!(buffer.locked);
+        let producer = buffer.producer();
+        let mut consumer = buffer.consumer();
+        for i in 0..6 {
+            producer.push(i).unwrap();
+            buffer.flush();
+            assert_eq!(consumer.try_next().unwrap().unwrap().payload, i);
+        }
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn rings_to_lock_share_no_pages_with_the_heap() {
+        let buffer = Buffer::<u64>::allocate(4, None, true).unwrap();
+        for (addr, _) in buffer.hot_ranges() {
+            assert_eq!(addr as usize % affinity::PAGE, 0);
+        }
+    }
+
+    #[test]
+    fn memlock_refusals_under_a_limit_report_it() {
+        let refused = |kind: io::ErrorKind| io::Error::from(kind);
+        let err = lock_error(refused(io::ErrorKind::OutOfMemory), 1 << 20, Some(64 << 10));
+        assert_eq!(err, BuildError::MemlockLimit { needed: 1 << 20, limit: 64 << 10 });
+        assert!(err.to_string().contains("1048576 bytes but RLIMIT_MEMLOCK allows 65536"));
+
+        // Unlimited, so something else refused
+        let err = lock_error(refused(io::ErrorKind::PermissionDenied), 1 << 20, None);
+        assert_eq!(err, BuildError::LockMemory(io::ErrorKind::PermissionDenied));
+        let err = lock_error(refused(io::ErrorKind::Unsupported), 1 << 20, Some(64 << 10));
+        assert_eq!(err, BuildError::LockMemory(io::ErrorKind::Unsupported));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn large_rings_start_as_untouched_zero_pages() {
+        // 2 MB of slots or more, mapped so that building the ring writes none of them
+        let buffer = Buffer::<u64>::builder().capacity(1 << 16).build().unwrap();
+        assert!(matches!(buffer.slots, Slots::Mapped { .. }));
+        assert!(buffer.slots_are_free());
+
+        let producer = buffer.producer();
+        let mut consumer = buffer.consumer();
+        producer.push(7).unwrap();
+        buffer.sequence_available();
+        assert_eq!(consumer.try_next().unwrap().unwrap().payload, 7);
+    }
+
+    #[cfg(target_os = "linux")]
+    fn ring_file(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("lftes-{}-{}.ring", name, std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn ring_files_keep_sequenced_events_across_a_restart() {
+        let path = ring_file("restart");
+        // SAFETY: u64 is plain data
+        let build = || unsafe { Buffer::<u64>::builder().capacity(8).mapped_file(&path) }.build();
+
+        let buffer = build().unwrap();
+        assert!(matches!(buffer.slots, Slots::File { .. }));
+        let producer = buffer.producer();
+        for i in 0..10 {
+            producer.push(i).unwrap();
+            buffer.sequence_available();
+        }
+        // Claimed but never sequenced, so lost on restart, along with sequence 2 whose
+        // slot it took
+        producer.push(10).unwrap();
+        drop(producer);
+        drop(buffer);
+
+        let buffer = build().unwrap();
+        let mut consumer = buffer.consumer();
+        let producer = buffer.producer();
+        producer.push(11).unwrap();
+        buffer.flush();
+        assert_eq!(consumer.try_next().unwrap_err(), ConsumerError::Lagged { skipped: 1 });
+        let events: Vec<_> = std::iter::from_fn(|| consumer.try_next().unwrap())
+            .map(|event| (event.sequence, event.payload))
+            .collect();
+        assert_eq!(events, [(3, 3), (4, 4), (5, 5), (6, 6), (7, 7), (8, 8), (9, 9), (10, 11)]);
+        drop((consumer, producer, buffer));
+
+        // A file still marked open was left by a process that died, and starts over
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[44..48].copy_from_slice(&0u32.to_le_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+        let buffer = build().unwrap();
+        assert!(buffer.snapshot().is_empty());
+        assert!(buffer.slots_are_free());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn ring_files_must_match_and_are_held_by_one_buffer() {
+        let path = ring_file("match");
+        // SAFETY: u64 and u32 are plain data
+        let buffer = unsafe { Buffer::<u64>::builder().capacity(8).mapped_file(&path) }.build();
+        let buffer = buffer.unwrap();
+        let again = unsafe { Buffer::<u64>::builder().capacity(8).mapped_file(&path) }.build();
+        assert_eq!(again.unwrap_err(), BuildError::MappedFile(io::ErrorKind::WouldBlock));
+        drop(buffer);
+
+        let larger = unsafe { Buffer::<u64>::builder().capacity(16).mapped_file(&path) }.build();
+        assert_eq!(larger.unwrap_err(), BuildError::MappedFile(io::ErrorKind::InvalidData));
+        let other = unsafe { Buffer::<u32, u32>::builder().capacity(8).mapped_file(&path) }.build();
+        assert_eq!(other.unwrap_err(), BuildError::MappedFile(io::ErrorKind::InvalidData));
+
+        // A closed ring file cut short is refused rather than mapped past its end
+        let file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(4096).unwrap();
+        drop(file);
+        let short = unsafe { Buffer::<u64>::builder().capacity(8).mapped_file(&path) }.build();
+        assert_eq!(short.unwrap_err(), BuildError::MappedFile(io::ErrorKind::InvalidData));
+
+        // Nor is any other file overwritten, even one that starts with zeros
+        for bytes in [&b"not a ring file"[..], &[0; 8192][..]] {
+            std::fs::write(&path, bytes).unwrap();
+            let built = unsafe { Buffer::<u64>::builder().capacity(8).mapped_file(&path) };
+            let built = built.build();
+            assert_eq!(built.unwrap_err(), BuildError::MappedFile(io::ErrorKind::InvalidData));
+            assert_eq!(std::fs::read(&path).unwrap(), bytes);
+        }
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    #[cfg(all(feature = "huge-pages", target_os = "linux"))]
+    fn large_rings_are_mapped_on_huge_pages() {
+        let small = Buffer::<u64>::builder().capacity(1024).build().unwrap();
+        assert!(matches!(small.slots, Slots::Heap(_)));
+
+        // 2 MB of slots
+        let buffer = Buffer::<u64>::builder().capacity(1 << 15).build().unwrap();
+        assert!(matches!(buffer.slots, Slots::Mapped { .. }));
+        assert!(buffer.slots_are_free());
+        let producer = buffer.producer();
+        let mut consumer = buffer.consumer();
+        producer.push(7).unwrap();
+        buffer.flush();
+        assert_eq!(consumer.try_next().unwrap().unwrap().payload, 7);
+    }
+
+    #[test]
+    fn rings_placed_on_a_numa_node_work_as_usual() {
+        let buffer = match Buffer::<u64>::builder()
+            .capacity(64)
+            .numa_node(0)
+            .sequencer_wait_strategy(WaitStrategy::Blocking)
+            .build()
+        {
+            Ok(buffer) => buffer,
+            // Kernels without NUMA support reject mbind
+            Err(BuildError::NumaNode(_)) => return,
+            Err(e) => panic!("{}", e),
+        };
+        assert!(matches!(buffer.slots, Slots::Mapped { .. }));
+        assert!(buffer.slots_are_free());
+        let mut handle = buffer.start();
+        let producer = buffer.producer();
+        let mut consumer = buffer.consumer();
+        producer.push(7).unwrap();
+        assert_eq!(consumer.next().unwrap().payload, 7);
+        handle.stop();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn max_capacity_bounds_explicit_capacity() {
+        let builder = || Buffer::<u64>::builder().max_capacity(256);
+        assert_eq!(builder().capacity(512).build().unwrap_err(), BuildError::TooLarge);
+        assert_eq!(builder().capacity(256).build().unwrap().capacity(), 256);
+    }
+
+    #[test]
+    fn buffer_builder_sets_wait_strategy() {
+        let buffer = Buffer::<u64>::builder()
+            .wait_strategy(WaitStrategy::Blocking)
+            .build()
+            .unwrap();
+        assert_eq!(buffer.wait_strategy(), WaitStrategy::Blocking);
+    }
+
+    #[test]
+    fn sequence_available_pumps_without_a_thread() {
+        let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        let producer = buffer.producer();
+        for i in 0..3 {
+            producer.push(i).unwrap();
+        }
+        assert_eq!(buffer.sequence_available(), 3);
+        assert_eq!(buffer.sequence_available(), 0);
+
+        let mut consumer = buffer.consumer();
+        let payloads: Vec<u64> = consumer.iter().map(|event| event.payload).collect();
+        assert_eq!(payloads, vec![0, 1, 2]);
+
+        // A running sequencer thread owns sequencing
+        let mut handle = buffer.start();
+        while !buffer.sequencing.load(Ordering::Acquire) {
+            std::thread::yield_now();
+        }
+        producer.push(3).unwrap();
+        assert_eq!(buffer.sequence_available(), 0);
+        handle.stop();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn preallocated_producers_have_fixed_ids() {
+        let (buffer, producers) = Buffer::<u64>::builder().capacity(16).producers(3).unwrap();
+        for producer in producers.iter().rev() {
+            producer.push(0).unwrap();
+        }
+        buffer.producer().push(0).unwrap();
+        buffer.flush();
+        let ids: Vec<u8> = buffer.consumer().iter().map(|event| event.producer_id).collect();
+        assert_eq!(ids, vec![2, 1, 0, 3]);
+
+        let too_many = Buffer::<u64>::builder().single_producer().producers(2);
+        assert!(matches!(too_many, Err(BuildError::TooManyProducers)));
+    }
+
+    #[test]
+    fn ready_counts_published_events_but_not_claims() {
+        let buffer = Buffer::<u64>::builder().capacity(8).build().unwrap();
+        let producer = buffer.producer();
+        for i in 0..3 {
+            producer.push(i).unwrap();
+        }
+        // A claim still being written is pending but not ready
+        assert!(matches!(try_claim(&buffer, None), Claim::Claimed(_)));
+        assert_eq!((buffer.pending(), buffer.ready()), (4, 3));
+
+        // Same count once the sequencer moved them from its queue to the bitmap
+        buffer.take_published();
+        assert_eq!(buffer.published.count(), 3);
+        assert_eq!(buffer.ready(), 3);
+
+        buffer.sequence_available();
+        assert_eq!((buffer.pending(), buffer.ready()), (1, 0));
+    }
+
+    #[test]
+    fn memory_usage_counts_ring_side_structures_and_consumers() {
+        let buffer = Buffer::<u64>::builder()
+            .capacity(1024)
+            .on_full(OnFull::Grow)
+            .build()
+            .unwrap();
+        let usage = buffer.memory_usage();
+        assert_eq!(usage.slots, 1024 * size_of::<Slot<u64>>());
+        // One bit and one queue cell per slot
+        assert!(usage.side >= 1024 / 8 + 1024 * 2 * size_of::<usize>());
+        assert_eq!(usage.consumers, 0);
+
+        let consumers: Vec<_> = (0..4).map(|_| buffer.consumer()).collect();
+        let cell = buffer.memory_usage().consumers;
+        assert!(cell >= 4 * size_of::<crate::cursor::CursorCell>());
+        drop(consumers);
+        assert!(buffer.memory_usage().consumers < cell);
+
+        // Events spilled past a full ring are held beside it
+        let _reader = buffer.consumer();
+        let producer = buffer.producer();
+        for i in 0..1025 {
+            producer.push(i).unwrap();
+        }
+        let grown = buffer.memory_usage();
+        assert!(grown.side > usage.side);
+        assert_eq!(grown.total(), grown.slots + grown.side + grown.consumers);
+    }
+
+    #[test]
+    fn occupancy_tracks_pending_and_unread_events() {
+        let buffer = Buffer::<u64>::builder().capacity(8).build().unwrap();
+        let producer = buffer.producer();
+        let mut consumer = buffer.consumer();
+        assert!(buffer.is_empty());
+
+        for i in 0..3 {
+            producer.push(i).unwrap();
+        }
+        assert_eq!(buffer.pending(), 3);
+        assert_eq!(buffer.len(), 3);
+
+        buffer.sequence_available();
+        assert_eq!(buffer.pending(), 0);
+        assert_eq!(buffer.len(), 3);
+        assert_eq!(buffer.remaining_capacity(), 5);
+
+        consumer.try_next().unwrap();
+        assert_eq!(buffer.len(), 2);
+        consumer.iter().count();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.remaining_capacity(), 8);
+    }
+
+    #[test]
+    fn snapshot_copies_resident_events_without_reading_them() {
+        let buffer = Buffer::<u64>::builder()
+            .capacity(4)
+            .on_full(OnFull::OverwriteOldest)
+            .build()
+            .unwrap();
+        let producer = buffer.producer();
+        let mut consumer = buffer.consumer();
+        assert!(buffer.snapshot().is_empty());
+
+        for i in 0..6 {
+            producer.push(i * 10).unwrap();
+            buffer.flush();
+        }
+        let sequences = |events: Vec<Event<u64>>| -> Vec<(u64, u64)> {
+            events.iter().map(|event| (event.sequence, event.payload)).collect()
+        };
+        assert_eq!(sequences(buffer.snapshot()), vec![(2, 20), (3, 30), (4, 40), (5, 50)]);
+
+        // An unsequenced push is not part of it, but the slot it took is gone
+        producer.push(60).unwrap();
+        assert_eq!(sequences(buffer.snapshot()), vec![(3, 30), (4, 40), (5, 50)]);
+
+        // The consumer still starts where it was and sees the lap
+        assert_eq!(consumer.position(), 0);
+        assert!(matches!(consumer.try_next(), Err(ConsumerError::Lagged { .. })));
+    }
+
+    #[test]
+    fn tail_follows_the_slowest_consumer() {
+        let buffer = Buffer::<u64>::builder().capacity(8).build().unwrap();
+        let producer = buffer.producer();
+        for i in 0..4 {
+            producer.push(i).unwrap();
+        }
+        buffer.flush();
+        assert_eq!(buffer.tail_sequence(), 4);
+
+        let mut fast = buffer.consumer();
+        let mut slow = buffer.consumer();
+        assert_eq!(buffer.tail_sequence(), 0);
+        fast.iter().count();
+        slow.try_next().unwrap();
+        assert_eq!(buffer.tail_sequence(), 1);
+        drop(slow);
+        assert_eq!(buffer.tail_sequence(), 4);
+    }
+
+    #[test]
+    fn a_new_consumer_lowers_the_tail_producers_check_against() {
+        let buffer = Buffer::<u64>::builder().capacity(8).build().unwrap();
+        let producer = buffer.producer();
+        for i in 0..4 {
+            producer.push(i).unwrap();
+        }
+        buffer.flush();
+        assert_eq!(buffer.tail_sequence(), 4);
+
+        let mut consumer = buffer.consumer();
+        assert_eq!(buffer.tail.load(Ordering::Acquire), 0);
+        assert!(!buffer.recyclable(0));
+        // Read back through the checked copy, as a producer might have passed the old tail
+        assert_eq!(consumer.try_next().unwrap().unwrap().payload, 0);
+    }
+
+    #[test]
+    fn read_range_splits_recycled_resident_and_unsequenced() {
+        let buffer = Buffer::<u64>::builder().capacity(4).build().unwrap();
+        let producer = buffer.producer();
+        let mut consumer = buffer.consumer();
+        for i in 0..6 {
+            producer.push(i).unwrap();
+            buffer.flush();
+            consumer.try_next().unwrap();
+        }
+
+        let read = buffer.read_range(1..9);
+        assert_eq!(read.recycled, 1..2);
+        assert_eq!(read.unsequenced, 6..9);
+        let payloads: Vec<u64> = read.events.iter().map(|event| event.payload).collect();
+        assert_eq!(payloads, vec![2, 3, 4, 5]);
+
+        let read = buffer.read_range(3..5);
+        assert_eq!((read.recycled, read.events.len(), read.unsequenced), (3..3, 2, 5..5));
+        let read = buffer.read_range(0..2);
+        assert_eq!((read.recycled, read.events.len(), read.unsequenced), (0..2, 0, 2..2));
+    }
+
+    #[test]
+    fn flush_waits_for_the_sequencer_or_sequences_itself() {
+        let buffer = Buffer::<u64>::builder().capacity(64).build().unwrap();
+        let producer = buffer.producer();
+        for i in 0..10 {
+            producer.push(i).unwrap();
+        }
+        buffer.flush();
+        assert_eq!(buffer.pending(), 0);
+
+        let mut handle = buffer.start();
+        for i in 10..40 {
+            producer.push(i).unwrap();
+        }
+        assert!(buffer.flush_timeout(Duration::from_secs(5)));
+        assert_eq!(buffer.consumer().iter().count(), 40);
+
+        handle.pause();
+        producer.push(40).unwrap();
+        assert!(!buffer.flush_timeout(Duration::from_millis(20)));
+        handle.resume();
+        buffer.flush();
+
+        handle.stop();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn close_drains_then_ends_consumers() {
+        let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        let producer = buffer.producer();
+        let mut consumer = buffer.consumer();
+        for i in 0..3 {
+            producer.push(i).unwrap();
+        }
+
+        // No sequencer thread: close drains on the calling thread
+        buffer.close();
+        assert!(buffer.is_closed());
+        assert_eq!(producer.push(3), Err(crate::error::PushError::Shutdown));
+        for i in 0..3 {
+            assert_eq!(consumer.next().unwrap().payload, i);
+        }
+        assert_eq!(consumer.next().unwrap_err(), ConsumerError::Closed);
+        assert_eq!(
+            consumer.next_timeout(Duration::from_secs(5)).unwrap_err(),
+            ConsumerError::Closed
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "compact-slots"))]
+    fn close_gets_past_claims_abandoned_by_unwinding_producers() {
+        use std::hash::{Hash, Hasher};
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        /// Hashing 0 panics, as a payload's `Hash` might
+        #[derive(Debug, Clone, Copy)]
+        struct Fragile(u64);
+
+        impl Hash for Fragile {
+            fn hash<H: Hasher>(&self, state: &mut H) {
+                assert_ne!(self.0, 0, "unhashable");
+                self.0.hash(state);
+            }
+        }
+
+        let buffer = Buffer::<Fragile>::builder()
+            .capacity(16)
+            .checksums(true)
+            .stuck_claim_timeout(Duration::from_millis(5))
+            .build()
+            .unwrap();
+        let mut handle = buffer.start();
+        let producer = buffer.producer();
+        producer.push(Fragile(1)).unwrap();
+        // Each push unwinds out of its checksum, leaving its claim behind
+        for _ in 0..2 {
+            assert!(catch_unwind(AssertUnwindSafe(|| producer.push(Fragile(0)))).is_err());
+        }
+        producer.push(Fragile(2)).unwrap();
+
+        buffer.close();
+        handle.join().unwrap();
+        assert_eq!(buffer.next_seq.load(Ordering::Acquire), 4);
+        assert_eq!(buffer.skipped_claims(), 2);
+    }
+
+    #[test]
+    fn close_racing_pushes_delivers_every_accepted_event() {
+        let buffer = Buffer::<u64>::builder().capacity(64).build().unwrap();
+        let mut handle = buffer.start();
+        let mut consumer = buffer.consumer();
+        let reader = std::thread::spawn(move || {
+            let mut read = 0;
+            while consumer.next().is_ok() {
+                read += 1;
+            }
+            read
+        });
+
+        let pushers: Vec<_> = (0..4)
+            .map(|_| {
+                let producer = buffer.producer();
+                std::thread::spawn(move || {
+                    let mut accepted = 0;
+                    while producer.push(accepted).is_ok() {
+                        accepted += 1;
+                    }
+                    accepted
+                })
+            })
+            .collect();
+        std::thread::sleep(Duration::from_millis(5));
+        buffer.close();
+
+        let accepted: u64 = pushers.into_iter().map(|p| p.join().unwrap()).sum();
+        assert_eq!(reader.join().unwrap(), accepted);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn generation_rejects_a_stale_lap() {
+        let buffer = Buffer::<u64>::builder().capacity(4).build().unwrap();
+        let producer = buffer.producer();
+        for i in 0..6 {
+            producer.push(i).unwrap();
+            buffer.sequence_available();
+        }
+        let generations: Vec<u32> = buffer
+            .slots
+            .iter()
+            .map(|slot| slot.generation.load(Ordering::Relaxed))
+            .collect();
+        assert_eq!(generations, vec![2, 2, 1, 1]);
+
+        // Even if slot 0 still claimed to hold sequence 0, its generation says lap 1
+        buffer.slots[0].sequence.store(0, Ordering::Release);
+        assert!(buffer.sequenced_slot(0).is_none());
+        let event = buffer.read(4).unwrap().unwrap();
+        assert_eq!((event.payload, event.generation), (4, 2));
+    }
+
+    #[test]
+    fn sequenced_slots_are_reclaimed_by_producers() {
+        let buffer = Buffer::<u64>::builder().capacity(4).build().unwrap();
+        let producer = buffer.producer();
+        for i in 0..12 {
+            producer.push(i).unwrap();
+            buffer.sequence_available();
+        }
+
+        // Nothing went back to Free, and the last lap is still readable
+        assert!(buffer
+            .slots
+            .iter()
+            .all(|slot| slot.state.load(Ordering::Relaxed) == SlotState::Sequenced as u8));
+        assert_eq!(buffer.resident_range(), 8..12);
+        assert_eq!(buffer.read(8).unwrap().unwrap().payload, 8);
+    }
+
+    #[test]
+    fn sequencer_busy_spins_by_default() {
+        let buffer = Buffer::<u64>::builder().build().unwrap();
+        assert_eq!(buffer.sequencer_wait_strategy(), WaitStrategy::BusySpin);
+    }
 }
@@ -0,0 +1,288 @@
+use crate::buffer::Buffer;
+use crate::consumer::{Consumer, Event};
+use crate::error::{BuildError, ConsumerError, PushError};
+use crate::producer::Producer;
+use crate::sequencer::SequencerHandle;
+use crate::slot::SlotReadGuard;
+use std::cell::UnsafeCell;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// Where an event's bytes live in the arena
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Span {
+    /// Position in the arena's unbounded address space; the byte index is `offset % arena.len()`
+    offset: u64,
+    len: u32,
+}
+
+/// A buffer of variable-length byte messages.
+///
+/// Each slot holds a span into a shared byte arena instead of the bytes themselves, so
+/// frames of any size up to the arena length fit without a fixed `[u8; N]` payload.
+/// Producers reserve arena space in the same order they claim slots, so arena offsets
+/// rise with sequence numbers and the space behind the oldest event a registered
+/// consumer can still read is free. A frame never wraps around the end of the arena.
+pub struct BytesBuffer {
+    buffer: Arc<Buffer<Span>>,
+    arena: Box<[UnsafeCell<u8>]>,
+    /// Next free arena position. Held from reserving a frame's bytes until its span is
+    /// pushed, which keeps arena order and claim order the same.
+    head: Mutex<u64>,
+}
+
+// SAFETY: Arena bytes are written only by the producer holding `head`, into space no
+// readable span covers, and read only by consumers whose registration pins their span
+unsafe impl Sync for BytesBuffer {}
+unsafe impl Send for BytesBuffer {}
+
+impl BytesBuffer {
+    /// `capacity` slots, as for `Buffer`, sharing `arena_bytes` bytes of message storage.
+    /// An empty arena is `BuildError::EmptyArena`.
+    pub fn new(capacity: usize, arena_bytes: usize) -> Result<Arc<Self>, BuildError> {
+        if arena_bytes == 0 {
+            return Err(BuildError::EmptyArena);
+        }
+        let buffer = Buffer::builder().capacity(capacity).build()?;
+        Ok(Arc::new(Self {
+            buffer,
+            arena: (0..arena_bytes).map(|_| UnsafeCell::new(0)).collect(),
+            head: Mutex::new(0),
+        }))
+    }
+
+    /// Start the sequencer thread
+    pub fn start(&self) -> SequencerHandle {
+        self.buffer.start()
+    }
+
+    /// Run one sequencing pass on the calling thread; see `Buffer::sequence_available`
+    pub fn sequence_available(&self) -> usize {
+        self.buffer.sequence_available()
+    }
+
+    /// Block until every frame pushed before the call has been sequenced
+    pub fn flush(&self) {
+        self.buffer.flush();
+    }
+
+    /// Stop accepting frames and sequence everything already pushed; see `Buffer::close`
+    pub fn close(&self) {
+        self.buffer.close();
+    }
+
+    /// Bytes of message storage
+    pub fn arena_len(&self) -> usize {
+        self.arena.len()
+    }
+
+    pub fn producer(self: &Arc<Self>) -> BytesProducer {
+        BytesProducer {
+            bytes: self.clone(),
+            producer: self.buffer.producer(),
+        }
+    }
+
+    pub fn consumer(self: &Arc<Self>) -> BytesConsumer {
+        BytesConsumer {
+            bytes: self.clone(),
+            consumer: self.buffer.consumer(),
+        }
+    }
+
+    /// Oldest arena position still referenced by an event some reader may need.
+    /// The caller holds `head`, so every claimed slot has its span written.
+    fn oldest_live(&self, head: u64) -> u64 {
+        let buffer = &self.buffer;
+        let oldest = buffer.update_tail();
+        if oldest >= buffer.head.load(std::sync::atomic::Ordering::Acquire) as u64 {
+            return head;
+        }
+        let slot = &buffer.slots[oldest as usize & buffer.mask];
+        // SAFETY: The slot holds event `oldest`, which is pinned or not yet sequenced,
+        // and only producers holding `head` write spans
+        let span = *unsafe { SlotReadGuard::new(slot) }.payload();
+        span.offset
+    }
+
+    /// Where a frame of `len` bytes would go, if the arena has room for it now
+    fn reserve(&self, head: u64, len: usize) -> Option<u64> {
+        let arena = self.arena.len() as u64;
+        let mut offset = head;
+        // Skip the arena's tail end rather than split the frame
+        if offset % arena + len as u64 > arena {
+            offset = offset.next_multiple_of(arena);
+        }
+        // With nothing live the skipped tail end does not matter
+        let oldest = self.oldest_live(head);
+        (oldest == head || offset + len as u64 - oldest <= arena).then_some(offset)
+    }
+
+    fn bytes(&self, span: &Span) -> &[u8] {
+        let start = (span.offset % self.arena.len() as u64) as usize;
+        let cells = &self.arena[start..start + span.len as usize];
+        // SAFETY: UnsafeCell<u8> has the layout of u8, and the caller's registration
+        // keeps producers from rewriting this span while the slice is borrowed
+        unsafe { std::slice::from_raw_parts(UnsafeCell::raw_get(cells.as_ptr()), cells.len()) }
+    }
+}
+
+impl fmt::Debug for BytesBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BytesBuffer")
+            .field("capacity", &self.buffer.capacity())
+            .field("arena_len", &self.arena.len())
+            .finish_non_exhaustive()
+    }
+}
+
+pub struct BytesProducer {
+    bytes: Arc<BytesBuffer>,
+    producer: Producer<Span>,
+}
+
+impl BytesProducer {
+    /// Copy `frame` into the arena and publish it, waiting for consumers to free space
+    pub fn push(&self, frame: &[u8]) -> Result<(), PushError> {
+        let bytes = &self.bytes;
+        if frame.len() > bytes.arena.len() || frame.len() > u32::MAX as usize {
+            return Err(PushError::TooLarge);
+        }
+        let mut head = bytes.head.lock().unwrap_or_else(|e| e.into_inner());
+        let offset = loop {
+            if bytes.buffer.is_closed() {
+                return Err(PushError::Shutdown);
+            }
+            if let Some(offset) = bytes.reserve(*head, frame.len()) {
+                break offset;
+            }
+            std::thread::yield_now();
+        };
+
+        let start = (offset % bytes.arena.len() as u64) as usize;
+        let cells = &bytes.arena[start..start + frame.len()];
+        // SAFETY: `reserve` checked no readable span covers these bytes, and holding
+        // `head` makes us their only writer
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                frame.as_ptr(),
+                UnsafeCell::raw_get(cells.as_ptr()),
+                frame.len(),
+            );
+        }
+        self.producer.push(Span {
+            offset,
+            len: frame.len() as u32,
+        })?;
+        *head = offset + frame.len() as u64;
+        Ok(())
+    }
+}
+
+pub struct BytesConsumer {
+    bytes: Arc<BytesBuffer>,
+    consumer: Consumer<Span>,
+}
+
+impl BytesConsumer {
+    /// Copy out the next sequenced frame, if there is one
+    pub fn try_next(&mut self) -> Result<Option<Event<Vec<u8>>>, ConsumerError> {
+        let bytes = &self.bytes;
+        self.consumer
+            .try_next_with(|event| Self::copy(bytes, event))
+    }
+
+    /// Block until the next frame is sequenced and copy it out
+    #[allow(clippy::should_implement_trait)]
+    pub fn next(&mut self) -> Result<Event<Vec<u8>>, ConsumerError> {
+        let bytes = &self.bytes;
+        self.consumer.next_with(|event| Self::copy(bytes, event))
+    }
+
+    /// Run `f` against the next frame's bytes in the arena, without copying
+    pub fn try_next_with<R>(
+        &mut self,
+        f: impl FnOnce(&Event<&[u8]>) -> R,
+    ) -> Result<Option<R>, ConsumerError> {
+        let bytes = &self.bytes;
+        self.consumer.try_next_with(|event| {
+            f(&Event {
+                sequence: event.sequence,
+                generation: event.generation,
+                timestamp: event.timestamp,
+                producer_id: event.producer_id,
+                flags: event.flags,
+                late: event.late,
+                metadata: event.metadata,
+                payload: bytes.bytes(event.payload),
+            })
+        })
+    }
+
+    fn copy(bytes: &BytesBuffer, event: &Event<&Span>) -> Event<Vec<u8>> {
+        Event {
+            sequence: event.sequence,
+            generation: event.generation,
+            timestamp: event.timestamp,
+            producer_id: event.producer_id,
+            flags: event.flags,
+            late: event.late,
+            metadata: event.metadata,
+            payload: bytes.bytes(event.payload).to_vec(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_of_any_size_round_trip() {
+        let bytes = BytesBuffer::new(16, 64).unwrap();
+        let producer = bytes.producer();
+        let mut consumer = bytes.consumer();
+
+        let frames: Vec<Vec<u8>> = (0..40u8).map(|i| vec![i; (i as usize * 7) % 50]).collect();
+        for frame in &frames {
+            producer.push(frame).unwrap();
+            bytes.flush();
+            let len = consumer.try_next_with(|event| event.payload.len()).unwrap();
+            assert_eq!(len, Some(frame.len()));
+        }
+
+        producer.push(b"hello").unwrap();
+        bytes.flush();
+        assert_eq!(consumer.try_next().unwrap().unwrap().payload, b"hello");
+        assert_eq!(producer.push(&[0; 65]), Err(PushError::TooLarge));
+    }
+
+    #[test]
+    fn an_empty_arena_is_rejected() {
+        assert_eq!(BytesBuffer::new(16, 0).unwrap_err(), BuildError::EmptyArena);
+    }
+
+    #[test]
+    fn unread_frames_hold_their_arena_space() {
+        let bytes = BytesBuffer::new(16, 32).unwrap();
+        let mut handle = bytes.start();
+        let mut consumer = bytes.consumer();
+
+        let writer = {
+            let producer = bytes.producer();
+            std::thread::spawn(move || {
+                for i in 0..100u8 {
+                    producer.push(&[i; 12]).unwrap();
+                }
+            })
+        };
+        // Two frames fill the arena, so the writer keeps waiting on this reader
+        for i in 0..100u8 {
+            assert_eq!(consumer.next().unwrap().payload, vec![i; 12]);
+        }
+        writer.join().unwrap();
+
+        handle.stop();
+        handle.join().unwrap();
+    }
+}
@@ -0,0 +1,60 @@
+use std::hash::{Hash, Hasher};
+
+/// Lookup table for the reflected IEEE polynomial, one entry per byte value
+const TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut byte = 0;
+    while byte < 256 {
+        let mut crc = byte as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+            bit += 1;
+        }
+        table[byte] = crc;
+        byte += 1;
+    }
+    table
+};
+
+/// CRC-32 (IEEE) as a `Hasher`. Feeding the payload through its `Hash` impl covers
+/// its fields without reading padding bytes, which may be uninitialized.
+pub(crate) struct Crc32(u32);
+
+impl Crc32 {
+    pub(crate) fn new() -> Self {
+        Self(!0)
+    }
+}
+
+impl Hasher for Crc32 {
+    fn write(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.0 = TABLE[((self.0 ^ byte as u32) & 0xFF) as usize] ^ (self.0 >> 8);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        u64::from(!self.0)
+    }
+}
+
+/// Checksum of `event`, as stored per slot by `BufferBuilder::checksums`
+pub(crate) fn checksum<T: Hash>(event: &T) -> u32 {
+    let mut crc = Crc32::new();
+    event.hash(&mut crc);
+    crc.finish() as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_the_standard_check_value() {
+        let mut crc = Crc32::new();
+        crc.write(b"123456789");
+        assert_eq!(crc.finish(), 0xCBF4_3926);
+        assert_ne!(checksum(&1u64), checksum(&2u64));
+    }
+}
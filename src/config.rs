@@ -0,0 +1,137 @@
+use crate::error::BuildError;
+use crate::producer::OnFull;
+use crate::wait::WaitStrategy;
+use std::str::FromStr;
+
+/// Buffer settings that can come from a config file or the environment instead of code.
+/// Unset fields keep the builder's defaults. Apply with `BufferBuilder::from_config`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BufferConfig {
+    pub capacity: Option<usize>,
+    pub wait_strategy: Option<WaitStrategy>,
+    pub sequencer_wait_strategy: Option<WaitStrategy>,
+    pub on_full: Option<OnFull>,
+    pub sequencer_core: Option<usize>,
+}
+
+impl BufferConfig {
+    /// Read `<PREFIX>_CAPACITY`, `<PREFIX>_WAIT_STRATEGY`, `<PREFIX>_SEQUENCER_WAIT_STRATEGY`,
+    /// `<PREFIX>_ON_FULL` and `<PREFIX>_SEQUENCER_CORE`. Strategy and policy names are
+    /// matched ignoring case, `_` and `-`, e.g. `busy_spin` or `OverwriteOldest`.
+    pub fn from_env(prefix: &str) -> Result<Self, BuildError> {
+        Self::from_vars(prefix, |name| std::env::var(name).ok())
+    }
+
+    fn from_vars(prefix: &str, var: impl Fn(&str) -> Option<String>) -> Result<Self, BuildError> {
+        Ok(Self {
+            capacity: parse_var(&var, prefix, "CAPACITY")?,
+            wait_strategy: parse_var(&var, prefix, "WAIT_STRATEGY")?,
+            sequencer_wait_strategy: parse_var(&var, prefix, "SEQUENCER_WAIT_STRATEGY")?,
+            on_full: parse_var(&var, prefix, "ON_FULL")?,
+            sequencer_core: parse_var(&var, prefix, "SEQUENCER_CORE")?,
+        })
+    }
+}
+
+/// Parse `<prefix>_<suffix>` if it is set
+fn parse_var<V: FromStr>(
+    var: &impl Fn(&str) -> Option<String>,
+    prefix: &str,
+    suffix: &str,
+) -> Result<Option<V>, BuildError> {
+    let name = format!("{}_{}", prefix, suffix);
+    var(&name)
+        .map(|value| {
+            value
+                .trim()
+                .parse()
+                .map_err(|_| BuildError::InvalidConfig(format!("{}={:?}", name, value)))
+        })
+        .transpose()
+}
+
+/// Lowercase with `_` and `-` removed, so `busy_spin`, `BusySpin` and `busy-spin` agree
+fn normalize(name: &str) -> String {
+    name.trim()
+        .chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+impl FromStr for WaitStrategy {
+    type Err = BuildError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match normalize(s).as_str() {
+            "busyspin" => Ok(WaitStrategy::BusySpin),
+            "yielding" => Ok(WaitStrategy::Yielding),
+            "blocking" => Ok(WaitStrategy::Blocking),
+            "backoff" => Ok(WaitStrategy::Backoff),
+            _ => Err(BuildError::InvalidConfig(format!("unknown wait strategy {:?}", s))),
+        }
+    }
+}
+
+impl FromStr for OnFull {
+    type Err = BuildError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match normalize(s).as_str() {
+            "fail" => Ok(OnFull::Fail),
+            "block" => Ok(OnFull::Block),
+            "overwriteoldest" => Ok(OnFull::OverwriteOldest),
+            "grow" => Ok(OnFull::Grow),
+            _ => Err(BuildError::InvalidConfig(format!("unknown on_full policy {:?}", s))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::{Buffer, BufferBuilder};
+    use std::collections::HashMap;
+
+    fn vars(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = pairs
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    #[test]
+    fn reads_prefixed_variables() {
+        let config = BufferConfig::from_vars(
+            "MD",
+            vars(&[
+                ("MD_CAPACITY", "256"),
+                ("MD_WAIT_STRATEGY", "busy_spin"),
+                ("MD_ON_FULL", "OverwriteOldest"),
+                ("OTHER_CAPACITY", "8"),
+            ]),
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            BufferConfig {
+                capacity: Some(256),
+                wait_strategy: Some(WaitStrategy::BusySpin),
+                on_full: Some(OnFull::OverwriteOldest),
+                ..BufferConfig::default()
+            }
+        );
+
+        let buffer: std::sync::Arc<Buffer<u64>> =
+            BufferBuilder::from_config(config).build().unwrap();
+        assert_eq!(buffer.capacity(), 256);
+        assert_eq!(buffer.wait_strategy(), WaitStrategy::BusySpin);
+    }
+
+    #[test]
+    fn bad_values_name_the_variable() {
+        let err = BufferConfig::from_vars("MD", vars(&[("MD_ON_FULL", "drop")])).unwrap_err();
+        assert_eq!(err, BuildError::InvalidConfig("MD_ON_FULL=\"drop\"".to_string()));
+    }
+}
@@ -0,0 +1,213 @@
+use crate::adapter::EventSource;
+use crate::consumer::{Consumer, Event};
+use crate::error::ConsumerError;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+/// A consumer that jumps to the newest event whenever it has fallen behind.
+/// Overruns are absorbed rather than reported as `Lagged`.
+pub struct Conflate<T, M = ()> {
+    consumer: Consumer<T, M>,
+}
+
+impl<T, M> Conflate<T, M>
+where
+    T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
+{
+    pub(crate) fn new(consumer: Consumer<T, M>) -> Self {
+        Self { consumer }
+    }
+
+    /// Get the wrapped consumer back
+    pub fn into_inner(self) -> Consumer<T, M> {
+        self.consumer
+    }
+}
+
+impl<T, M> EventSource for Conflate<T, M>
+where
+    T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
+{
+    type Item = Event<T, M>;
+
+    fn try_next(&mut self) -> Result<Option<Event<T, M>>, ConsumerError> {
+        loop {
+            self.consumer.skip_to_latest();
+            match self.consumer.try_next() {
+                Err(ConsumerError::Lagged { .. }) => continue,
+                result => return result,
+            }
+        }
+    }
+
+    fn next(&mut self) -> Result<Event<T, M>, ConsumerError> {
+        loop {
+            self.consumer.skip_to_latest();
+            match self.consumer.next() {
+                Err(ConsumerError::Lagged { .. }) => continue,
+                result => return result,
+            }
+        }
+    }
+
+    fn next_timeout(&mut self, timeout: Duration) -> Result<Event<T, M>, ConsumerError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            self.consumer.skip_to_latest();
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.consumer.next_timeout(remaining) {
+                Err(ConsumerError::Lagged { .. }) => continue,
+                result => return result,
+            }
+        }
+    }
+}
+
+/// A consumer that collapses its backlog to the newest event per key.
+/// Events are delivered in sequence order of the surviving (latest) events.
+pub struct ConflateByKey<T, K, F, M = ()> {
+    consumer: Consumer<T, M>,
+    key: F,
+    pending: VecDeque<Event<T, M>>,
+    latest: HashMap<K, Event<T, M>>,
+}
+
+impl<T, K, F, M> ConflateByKey<T, K, F, M>
+where
+    T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
+    K: Eq + Hash,
+    F: FnMut(&T) -> K,
+{
+    pub(crate) fn new(consumer: Consumer<T, M>, key: F) -> Self {
+        Self {
+            consumer,
+            key,
+            pending: VecDeque::new(),
+            latest: HashMap::new(),
+        }
+    }
+
+    /// Get the wrapped consumer back. Conflated events not yet returned are dropped.
+    pub fn into_inner(self) -> Consumer<T, M> {
+        self.consumer
+    }
+
+    /// Drain everything currently sequenced, keeping only the newest event per key
+    fn refill(&mut self, first: Option<Event<T, M>>) {
+        if let Some(event) = first {
+            self.latest.insert((self.key)(&event.payload), event);
+        }
+        loop {
+            match self.consumer.try_next() {
+                Ok(Some(event)) => {
+                    self.latest.insert((self.key)(&event.payload), event);
+                }
+                Err(ConsumerError::Lagged { .. }) => continue,
+                _ => break,
+            }
+        }
+
+        let mut events: Vec<Event<T, M>> = self.latest.drain().map(|(_, event)| event).collect();
+        events.sort_unstable_by_key(|event| event.sequence);
+        self.pending.extend(events);
+    }
+}
+
+impl<T, K, F, M> EventSource for ConflateByKey<T, K, F, M>
+where
+    T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
+    K: Eq + Hash,
+    F: FnMut(&T) -> K,
+{
+    type Item = Event<T, M>;
+
+    fn try_next(&mut self) -> Result<Option<Event<T, M>>, ConsumerError> {
+        if self.pending.is_empty() {
+            self.refill(None);
+        }
+        Ok(self.pending.pop_front())
+    }
+
+    fn next(&mut self) -> Result<Event<T, M>, ConsumerError> {
+        while self.pending.is_empty() {
+            match self.consumer.next() {
+                Ok(event) => self.refill(Some(event)),
+                Err(ConsumerError::Lagged { .. }) => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(self.pending.pop_front().expect("refilled above"))
+    }
+
+    fn next_timeout(&mut self, timeout: Duration) -> Result<Event<T, M>, ConsumerError> {
+        let deadline = Instant::now() + timeout;
+        while self.pending.is_empty() {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.consumer.next_timeout(remaining) {
+                Ok(event) => self.refill(Some(event)),
+                Err(ConsumerError::Lagged { .. }) => continue,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(self.pending.pop_front().expect("refilled above"))
+    }
+}
+
+/// A 16-slot buffer holding `payloads`, all sequenced, with its sequencer stopped
+#[cfg(test)]
+pub(crate) fn sequenced<T>(payloads: &[T]) -> std::sync::Arc<crate::buffer::Buffer<T>>
+where
+    T: Copy + Send + 'static,
+{
+    use std::sync::atomic::Ordering;
+
+    let buffer = crate::buffer::Buffer::<T>::builder().capacity(16).build().unwrap();
+    let mut handle = buffer.start();
+    let producer = buffer.producer();
+    for &payload in payloads {
+        producer.push(payload).unwrap();
+    }
+    while buffer.next_seq.load(Ordering::Acquire) < payloads.len() as u64 {
+        std::thread::yield_now();
+    }
+    handle.stop();
+    handle.join().unwrap();
+    buffer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conflate_yields_only_latest_backlog_event() {
+        let buffer = sequenced(&[1u64, 2, 3, 4]);
+        let mut latest = buffer.consumer().conflate();
+
+        let event = latest.try_next().unwrap().unwrap();
+        assert_eq!(event.sequence, 3);
+        assert_eq!(event.payload, 4);
+        assert!(latest.try_next().unwrap().is_none());
+    }
+
+    #[test]
+    fn conflate_by_key_keeps_latest_per_key_in_sequence_order() {
+        // Key is the tens digit: 10, 11 share a key, as do 20, 21
+        let buffer = sequenced(&[10u64, 20, 11, 30, 21]);
+        let mut by_key = buffer.consumer().conflate_by_key(|payload| payload / 10);
+
+        let payloads: Vec<u64> = by_key
+            .try_next_batch(10)
+            .unwrap()
+            .iter()
+            .map(|event| event.payload)
+            .collect();
+        assert_eq!(payloads, vec![11, 30, 21]);
+        assert!(by_key.try_next().unwrap().is_none());
+    }
+}
@@ -1,80 +1,797 @@
 use crate::buffer::Buffer;
-use crate::slot::SlotState;
-use std::sync::atomic::Ordering;
+use crate::conflate::{Conflate, ConflateByKey};
+use crate::cursor::{Registration, RELEASED};
+use crate::error::ConsumerError;
+use crate::persist::LogWriter;
+use crate::prefetch;
+use crate::sink::{Sink, SinkFormat, SinkPayload};
+use crate::slot::SlotReadGuard;
+use crate::store::CursorStore;
+use crate::sync::atomic::{AtomicBool, Ordering};
+use crate::wait::Waiter;
+use std::fmt;
+use std::hash::Hash;
+use std::io::{self, Write};
+use std::mem::MaybeUninit;
+use std::num::ParseIntError;
+use std::path::PathBuf;
+use std::str::FromStr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-pub struct Consumer<T> {
-    buffer: Arc<Buffer<T>>,
+/// Slots per chunk of `Consumer::take_run`: headers for 4 KB of 64-byte slots, so
+/// their lines are still in L1 when the payloads are copied
+const RUN_CHUNK: u64 = 64;
+
+pub struct Consumer<T, M = ()> {
+    buffer: Arc<Buffer<T, M>>,
     cursor: u64,
+    /// Oldest sequence this consumer may still read, gating slot recycling
+    registration: Registration,
+    /// Cursor shared with the other members of a work-queue group
+    group: Option<Arc<Registration>>,
+    /// Offset store and the name this consumer commits under
+    store: Option<(String, Box<dyn CursorStore>)>,
+    /// Last committed position; recycling is held back here so uncommitted events can be re-read
+    committed: Option<u64>,
+    /// Corrupted event found after the first of a batch, left for the next call to report
+    deferred: Option<ConsumerError>,
 }
 
-impl<T> Consumer<T>
+impl<T, M> Consumer<T, M>
 where
     T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
 {
-    pub(crate) fn new(buffer: Arc<Buffer<T>>) -> Self {
-        Self { buffer, cursor: 0 }
+    pub(crate) fn new(buffer: Arc<Buffer<T, M>>) -> Self {
+        let registration = buffer.register_consumer();
+        let cursor = registration.position().load(Ordering::Acquire);
+        Self {
+            buffer,
+            cursor,
+            registration,
+            group: None,
+            store: None,
+            committed: None,
+            deferred: None,
+        }
+    }
+
+    /// A consumer that takes events from a cursor shared with other group members.
+    /// Members only hold back recycling while they are reading an event; the shared
+    /// cursor gates the rest.
+    pub(crate) fn with_group(buffer: Arc<Buffer<T, M>>, group: Arc<Registration>) -> Self {
+        let registration = buffer.consumers.register(RELEASED);
+        let cursor = group.position().load(Ordering::Acquire);
+        Self {
+            buffer,
+            cursor,
+            registration,
+            group: Some(group),
+            store: None,
+            committed: None,
+            deferred: None,
+        }
     }
 
-    pub fn try_next(&mut self) -> Option<Event<T>> {
-        // Calculate slot index from cursor
-        let slot_idx = (self.cursor as usize) & self.buffer.mask;
-        let slot = &self.buffer.slots[slot_idx];
+    /// Read the next sequenced event if there is one.
+    /// Returns `Lagged` if events were recycled before this consumer read them;
+    /// the cursor is moved to the oldest resident event so the next call resumes from there.
+    pub fn try_next(&mut self) -> Result<Option<Event<T, M>>, ConsumerError> {
+        self.take(true)
+    }
 
-        // Check if slot is sequenced
-        let state = slot.state.load(Ordering::Acquire);
-        if state != SlotState::Sequenced as u8 {
-            return None;
+    /// Return the next sequenced event without advancing the cursor
+    pub fn peek(&self) -> Result<Option<Event<T, M>>, ConsumerError> {
+        self.buffer.read(self.position())
+    }
+
+    /// Reposition the cursor so the next read returns `sequence`.
+    /// Seeking to the end of the resident range waits for the next event.
+    pub fn seek(&mut self, sequence: u64) -> Result<(), ConsumerError> {
+        let available = self.buffer.resident_range();
+        if sequence < available.start || sequence > available.end {
+            return Err(ConsumerError::SeekOutOfRange {
+                requested: sequence,
+                available,
+            });
         }
+        self.move_to(sequence);
+        Ok(())
+    }
 
-        // Verify sequence number matches (defensive check)
-        let seq = slot.sequence.load(Ordering::Acquire);
-        if seq != self.cursor {
-            return None; // Slot was recycled - we're too slow
+    /// Jump to the most recently sequenced event, returning how many events were skipped
+    pub fn skip_to_latest(&mut self) -> u64 {
+        let next = self.buffer.next_seq.load(Ordering::Acquire);
+        let latest = next.saturating_sub(1);
+        let position = self.position();
+        if next == 0 || position >= latest {
+            return 0;
+        }
+        self.move_to(latest);
+        latest - position
+    }
+
+    /// Read up to `max` currently sequenced events in one pass.
+    /// A lag or corrupted event detected after the first event ends the batch and is
+    /// reported by the next call.
+    pub fn try_next_batch(&mut self, max: usize) -> Result<Vec<Event<T, M>>, ConsumerError> {
+        let mut events = Vec::with_capacity(max.min(self.buffer.capacity));
+        if self.take_run(max, |event| events.push(event)) > 0 {
+            return Ok(events);
+        }
+        while events.len() < max {
+            if events.len() + prefetch::DISTANCE < max {
+                self.buffer.prefetch_slot(self.cursor + prefetch::DISTANCE as u64);
+            }
+            match self.take(events.is_empty()) {
+                Ok(Some(event)) => events.push(event),
+                Ok(None) => break,
+                Err(_) if !events.is_empty() => break,
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(events)
+    }
+
+    /// Fill `out` with currently sequenced events, returning how many were written.
+    /// Only the first `n` elements of `out` are initialized afterwards.
+    pub fn try_next_batch_into(
+        &mut self,
+        out: &mut [MaybeUninit<Event<T, M>>],
+    ) -> Result<usize, ConsumerError> {
+        let mut slots = out.iter_mut();
+        let run = self.take_run(slots.len(), |event| {
+            slots.next().expect("run fits in out").write(event);
+        });
+        if run > 0 {
+            return Ok(run);
+        }
+        let mut n = 0;
+        while n < out.len() {
+            if n + prefetch::DISTANCE < out.len() {
+                self.buffer.prefetch_slot(self.cursor + prefetch::DISTANCE as u64);
+            }
+            match self.take(n == 0) {
+                Ok(Some(event)) => {
+                    out[n].write(event);
+                    n += 1;
+                }
+                Ok(None) => break,
+                Err(_) if n > 0 => break,
+                Err(err) => return Err(err),
+            }
         }
+        Ok(n)
+    }
+
+    /// Copy out the run of sequenced events at the cursor, at most `max`, and publish
+    /// the cursor once for all of them. Each chunk's slot headers are checked before
+    /// any of its payloads is copied, so the copies read lines the checks just loaded
+    /// and a slot holding another lap ends the run up front. Returns how many events it
+    /// passed to `emit`.
+    ///
+    /// Only for a lone consumer on a buffer that never reclaims unread slots, where the
+    /// registration keeps every slot from the cursor up to `next_seq` in place; others
+    /// return 0, as does a cursor where reads are still checked, and a run that starts at
+    /// a lag or a corrupted event, which the per-event path then reports.
+    fn take_run(&mut self, max: usize, mut emit: impl FnMut(Event<T, M>)) -> usize {
+        let buffer = &*self.buffer;
+        if self.group.is_some() || self.checks_reads(self.cursor) {
+            return 0;
+        }
+        let start = self.cursor;
+        let end = buffer
+            .next_seq
+            .load(Ordering::Acquire)
+            .min(start.saturating_add(max as u64));
+        let mut emitted = 0;
+        let mut sequence = start;
+        'chunks: while sequence < end {
+            let chunk = sequence..(sequence + RUN_CHUNK).min(end);
+            // Everything below `next_seq` was sequenced, so only the lap needs checking
+            let valid = chunk
+                .clone()
+                .find(|&seq| {
+                    let slot = &buffer.slots[seq as usize & buffer.mask];
+                    slot.generation.load(Ordering::Acquire) != buffer.generation(seq)
+                })
+                .unwrap_or(chunk.end);
+            for seq in sequence..valid {
+                if buffer.slots[seq as usize & buffer.mask].is_skipped() {
+                    continue;
+                }
+                let (event, stored) = buffer.read_slot(seq);
+                if buffer.verify(seq, &event.payload, stored).is_err() {
+                    // Leave it for `take` to report
+                    sequence = seq;
+                    break 'chunks;
+                }
+                emit(event);
+                emitted += 1;
+            }
+            sequence = valid;
+            if valid < chunk.end {
+                break;
+            }
+        }
+        if sequence > start {
+            self.cursor = sequence;
+            self.publish();
+        }
+        emitted
+    }
+
+    /// Copy out the next event and advance past it
+    fn take(&mut self, resync: bool) -> Result<Option<Event<T, M>>, ConsumerError> {
+        let Some(sequence) = self.claim(resync)? else {
+            return Ok(None);
+        };
+        // An expired or overwritten slot can be reclaimed under us, so only a copy that
+        // was still there once it was done counts
+        let read = if self.checks_reads(sequence) {
+            self.buffer.copy_slot(sequence)
+        } else {
+            Some(self.buffer.read_slot(sequence))
+        };
+        let Some((event, stored)) = read else {
+            if resync || self.group.is_some() {
+                self.cursor = sequence + 1;
+                self.publish();
+            }
+            return Err(ConsumerError::Lagged { skipped: 1 });
+        };
+        self.cursor = sequence + 1;
+        self.publish();
+        if let Err(err) = self.buffer.verify(sequence, &event.payload, stored) {
+            // The cursor is past it either way, so one the caller can't take now is kept
+            if !resync {
+                self.deferred = Some(err);
+                return Ok(None);
+            }
+            return Err(err);
+        }
+        Ok(Some(event))
+    }
+
+    /// Whether a producer may reuse the slot holding `sequence` while it is read: always
+    /// on a buffer that reclaims unread slots, and on any where a consumer registered or
+    /// moved back since producers may have checked it against the tail
+    fn checks_reads(&self, sequence: u64) -> bool {
+        self.buffer.reclaims_unread() || sequence < self.buffer.consumers.checked_below()
+    }
+
+    /// Find the next sequence this consumer should deliver. In a group the sequence is
+    /// taken from the shared cursor so no other member can deliver it.
+    /// With `resync`, a lag moves the cursor to the oldest resident event.
+    fn claim(&mut self, resync: bool) -> Result<Option<u64>, ConsumerError> {
+        if self.deferred.is_some() {
+            if resync {
+                return Err(self.deferred.take().expect("checked above"));
+            }
+            return Ok(None);
+        }
+        let Some(group) = &self.group else {
+            loop {
+                return match self.buffer.locate(self.cursor) {
+                    // A claim the sequencer gave up on carries no event
+                    Ok(Some(slot)) if slot.is_skipped() => {
+                        self.cursor += 1;
+                        self.publish();
+                        continue;
+                    }
+                    Ok(found) => Ok(found.map(|_| self.cursor)),
+                    Err(ConsumerError::Lagged { skipped }) if resync => {
+                        self.cursor += skipped;
+                        self.publish();
+                        Err(ConsumerError::Lagged { skipped })
+                    }
+                    Err(err) => Err(err),
+                };
+            }
+        };
+
+        loop {
+            let cursor = group.position().load(Ordering::SeqCst);
+            // Hold the slot before taking it from the shared cursor
+            self.registration.set(cursor);
+            let (next, result) = match self.buffer.locate(cursor) {
+                Ok(Some(slot)) if slot.is_skipped() => (cursor + 1, Ok(None)),
+                Ok(Some(_)) => (cursor + 1, Ok(Some(cursor))),
+                Ok(None) => {
+                    self.registration.set(RELEASED);
+                    return Ok(None);
+                }
+                // One member reports the lag; the others retry from the new position
+                Err(ConsumerError::Lagged { skipped }) if resync => {
+                    (cursor + skipped, Err(ConsumerError::Lagged { skipped }))
+                }
+                Err(err) => {
+                    self.registration.set(RELEASED);
+                    return Err(err);
+                }
+            };
+            if group
+                .position()
+                .compare_exchange(cursor, next, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+            {
+                match result {
+                    // Stepped over a skipped claim; look at the next one
+                    Ok(None) => continue,
+                    Err(_) => self.registration.set(RELEASED),
+                    Ok(Some(_)) => {}
+                }
+                return result;
+            }
+        }
+    }
+
+    /// Next sequence this consumer (or its group) will read
+    pub fn position(&self) -> u64 {
+        match &self.group {
+            Some(group) => group.position().load(Ordering::Acquire),
+            None => self.cursor,
+        }
+    }
+
+    /// Resume from `sequence`, e.g. a position saved before a restart. Same rules as `seek`.
+    pub fn set_position(&mut self, sequence: u64) -> Result<(), ConsumerError> {
+        self.seek(sequence)
+    }
+
+    /// Capture the current position for external storage
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint {
+            sequence: self.position(),
+        }
+    }
+
+    /// Resume from a saved checkpoint
+    pub fn restore(&mut self, checkpoint: &Checkpoint) -> Result<(), ConsumerError> {
+        self.seek(checkpoint.sequence)
+    }
+
+    fn move_to(&mut self, sequence: u64) {
+        let from = match &self.group {
+            Some(group) => group.position().load(Ordering::Acquire),
+            None => self.cursor,
+        };
+        // Producers may have checked slots below `from` against a tail up to it, and
+        // may yet reuse them
+        let back = sequence < from;
+        if back {
+            self.buffer.consumers.check_below(from);
+        }
+        self.deferred = None;
+        if let Some(group) = &self.group {
+            group.set(sequence);
+        }
+        self.cursor = sequence;
+        self.publish();
+        if back {
+            self.buffer.consumers.lower(sequence, &self.buffer.tail);
+        }
+    }
+
+    /// Attach an offset store, resuming from the position last committed under `name`
+    pub fn with_store<S>(mut self, name: impl Into<String>, store: S) -> Result<Self, ConsumerError>
+    where
+        S: CursorStore + 'static,
+    {
+        let name = name.into();
+        let saved = store
+            .load(&name)
+            .map_err(|e| ConsumerError::Store(e.to_string()))?;
+        if let Some(checkpoint) = saved {
+            // Not `restore`: after a restart the checkpoint may be past everything
+            // sequenced so far, or before what is still resident
+            let start = self.buffer.resident_range().start;
+            self.move_to(checkpoint.sequence.max(start));
+            self.committed = Some(checkpoint.sequence);
+        }
+        self.store = Some((name, Box::new(store)));
+        self.publish();
+        Ok(self)
+    }
+
+    /// Record everything read so far as processed. Without a store this only
+    /// moves the in-memory rollback point.
+    pub fn commit(&mut self) -> Result<(), ConsumerError> {
+        let checkpoint = self.checkpoint();
+        if let Some((name, store)) = &mut self.store {
+            store
+                .save(name, checkpoint)
+                .map_err(|e| ConsumerError::Store(e.to_string()))?;
+        }
+        self.committed = Some(checkpoint.sequence);
+        self.publish();
+        Ok(())
+    }
+
+    pub(crate) fn has_store(&self) -> bool {
+        self.store.is_some()
+    }
+
+    /// Last committed position, if anything has been committed
+    pub fn committed(&self) -> Option<u64> {
+        self.committed
+    }
+
+    /// Move back to the last commit so uncommitted events are delivered again
+    pub fn rollback(&mut self) -> Result<(), ConsumerError> {
+        match self.committed {
+            Some(sequence) => self.seek(sequence),
+            None => Ok(()),
+        }
+    }
+
+    /// Registered value once the current read is finished
+    fn released_position(&self) -> u64 {
+        match (&self.group, self.committed) {
+            (Some(_), _) => RELEASED,
+            (None, Some(committed)) => committed.min(self.cursor),
+            (None, None) => self.cursor,
+        }
+    }
+
+    /// Let the reclaimer know how far back this consumer may still read
+    fn publish(&self) {
+        self.registration.set(self.released_position());
+    }
 
-        // Read payload and metadata
-        // SAFETY: State is Sequenced, so payload is initialized
-        let payload = unsafe { (*slot.payload.get()).assume_init_read() };
-        let timestamp = unsafe { *slot.timestamp.get() };
-        let producer_id = unsafe { *slot.producer_id.get() };
+    /// Run `f` against the next sequenced event in place, advancing the cursor afterwards
+    pub fn try_next_with<R>(
+        &mut self,
+        f: impl FnOnce(&Event<&T, M>) -> R,
+    ) -> Result<Option<R>, ConsumerError> {
+        let Some(event) = self.try_next_ref()? else {
+            return Ok(None);
+        };
+        let view = Event {
+            sequence: event.sequence,
+            generation: event.generation,
+            timestamp: event.timestamp,
+            producer_id: event.producer_id,
+            flags: event.flags,
+            late: false,
+            metadata: event.metadata,
+            payload: event.payload(),
+        };
+        Ok(Some(f(&view)))
+    }
 
-        let event = Event {
-            sequence: seq,
-            timestamp,
-            producer_id,
-            payload,
+    /// Block like `next`, then run `f` against the event in place like `try_next_with`
+    pub fn next_with<R>(
+        &mut self,
+        f: impl FnOnce(&Event<&T, M>) -> R,
+    ) -> Result<R, ConsumerError> {
+        let mut f = Some(f);
+        let mut waiter = Waiter::new(self.buffer.wait_strategy);
+        loop {
+            let drained = self.buffer.is_drained();
+            let found = self.try_next_with(|event| f.take().expect("called once")(event))?;
+            if let Some(result) = found {
+                return Ok(result);
+            }
+            if drained {
+                return Err(ConsumerError::Closed);
+            }
+            waiter.wait(&self.buffer.notifier, None, || {
+                self.is_ready() || self.buffer.is_drained()
+            });
+        }
+    }
+
+    /// Borrow the next sequenced event in place instead of copying the payload.
+    /// The cursor advances when the returned `EventRef` is dropped.
+    pub fn try_next_ref(&mut self) -> Result<Option<EventRef<'_, T, M>>, ConsumerError> {
+        let Some(sequence) = self.claim(true)? else {
+            return Ok(None);
+        };
+        let slot = &self.buffer.slots[(sequence as usize) & self.buffer.mask];
+        let release_to = match (&self.group, self.committed) {
+            (Some(_), _) => RELEASED,
+            (None, Some(committed)) => committed.min(sequence + 1),
+            (None, None) => sequence + 1,
         };
 
-        self.cursor += 1;
-        Some(event)
+        // Producers may reclaim this slot regardless of the registration, so lend out a
+        // copy that was checked to still be there instead of the slot itself
+        if self.checks_reads(sequence) {
+            let Some((event, stored)) = self.buffer.copy_slot(sequence) else {
+                self.cursor = sequence + 1;
+                self.publish();
+                return Err(ConsumerError::Lagged { skipped: 1 });
+            };
+            if let Err(err) = self.buffer.verify(sequence, &event.payload, stored) {
+                self.cursor = sequence + 1;
+                self.publish();
+                return Err(err);
+            }
+            return Ok(Some(EventRef {
+                sequence,
+                generation: event.generation,
+                timestamp: event.timestamp,
+                producer_id: event.producer_id,
+                flags: event.flags,
+                metadata: event.metadata,
+                payload: Payload::Copied(event.payload),
+                cursor: &mut self.cursor,
+                registration: &self.registration,
+                release_to,
+            }));
+        }
+
+        // SAFETY: State is Sequenced, so payload is initialized and read-only.
+        // The registration keeps the slot from being recycled until the EventRef drops.
+        let contents = unsafe { SlotReadGuard::new(slot) };
+        let payload = contents.payload();
+        if let Err(err) = self.buffer.verify(sequence, payload, contents.checksum()) {
+            self.cursor = sequence + 1;
+            self.publish();
+            return Err(err);
+        }
+        Ok(Some(EventRef {
+            sequence,
+            generation: self.buffer.generation(sequence),
+            timestamp: contents.timestamp(),
+            producer_id: contents.producer_id(),
+            flags: contents.flags(),
+            metadata: contents.metadata(),
+            payload: Payload::Borrowed(payload),
+            cursor: &mut self.cursor,
+            registration: &self.registration,
+            release_to,
+        }))
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    /// Block until the next event is sequenced, waiting with the buffer's `WaitStrategy`
+    /// Returns `Closed` once the buffer is closed and everything has been read.
+    pub fn next(&mut self) -> Result<Event<T, M>, ConsumerError> {
+        let mut waiter = Waiter::new(self.buffer.wait_strategy);
+        loop {
+            let drained = self.buffer.is_drained();
+            if let Some(event) = self.try_next()? {
+                return Ok(event);
+            }
+            if drained {
+                return Err(ConsumerError::Closed);
+            }
+            waiter.wait(&self.buffer.notifier, None, || {
+                self.is_ready() || self.buffer.is_drained()
+            });
+        }
     }
 
-    pub fn iter(&mut self) -> ConsumerIter<'_, T> {
+    /// Block until the next event is sequenced or `timeout` elapses
+    pub fn next_timeout(&mut self, timeout: Duration) -> Result<Event<T, M>, ConsumerError> {
+        let deadline = Instant::now() + timeout;
+        let mut waiter = Waiter::new(self.buffer.wait_strategy);
+        loop {
+            let drained = self.buffer.is_drained();
+            if let Some(event) = self.try_next()? {
+                return Ok(event);
+            }
+            if drained {
+                return Err(ConsumerError::Closed);
+            }
+            if Instant::now() >= deadline {
+                return Err(ConsumerError::Timeout);
+            }
+            waiter.wait(&self.buffer.notifier, Some(deadline), || {
+                self.is_ready() || self.buffer.is_drained()
+            });
+        }
+    }
+
+    /// Block until the next event is sequenced or `stop` is set, returning `None` on stop.
+    /// Whoever sets `stop` must call `notify_all` on the buffer's notifier afterwards.
+    pub(crate) fn next_until(
+        &mut self,
+        stop: &AtomicBool,
+    ) -> Result<Option<Event<T, M>>, ConsumerError> {
+        let mut waiter = Waiter::new(self.buffer.wait_strategy);
+        loop {
+            if let Some(event) = self.try_next()? {
+                return Ok(Some(event));
+            }
+            if stop.load(Ordering::Acquire) {
+                return Ok(None);
+            }
+            waiter.wait(&self.buffer.notifier, None, || {
+                stop.load(Ordering::Acquire) || self.is_ready()
+            });
+        }
+    }
+
+    /// Whether the sequencer has reached the cursor (an event or a lag is waiting)
+    fn is_ready(&self) -> bool {
+        self.position() < self.buffer.next_seq.load(Ordering::Acquire)
+    }
+
+    /// Only ever see the newest event, skipping any backlog
+    pub fn conflate(self) -> Conflate<T, M> {
+        Conflate::new(self)
+    }
+
+    /// Collapse any backlog to the newest event per key
+    pub fn conflate_by_key<K, F>(self, key: F) -> ConflateByKey<T, K, F, M>
+    where
+        K: Eq + Hash,
+        F: FnMut(&T) -> K,
+    {
+        ConflateByKey::new(self, key)
+    }
+
+    /// Write every event to `writer` in the given format
+    pub fn sink<W: Write>(self, writer: W, format: SinkFormat) -> Sink<T, W, M>
+    where
+        T: SinkPayload,
+    {
+        Sink::new(self, writer, format)
+    }
+
+    /// Append every event to segment files in `dir`, creating it if needed
+    pub fn log_writer(self, dir: impl Into<PathBuf>) -> io::Result<LogWriter<T, M>>
+    where
+        T: SinkPayload,
+    {
+        LogWriter::new(self, dir.into())
+    }
+
+    pub fn iter(&mut self) -> ConsumerIter<'_, T, M> {
         ConsumerIter { consumer: self }
     }
+
+    /// Iterate over a live stream, waiting for new events per the wait strategy.
+    /// Ends once the sequencer has stopped and everything it sequenced has been read.
+    pub fn blocking_iter(&mut self) -> BlockingIter<'_, T, M> {
+        BlockingIter { consumer: self }
+    }
+}
+
+/// A saved consumer position. Encodes to 8 little-endian bytes or a decimal string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Checkpoint {
+    /// Next sequence to read when resuming
+    pub sequence: u64,
+}
+
+impl Checkpoint {
+    pub fn new(sequence: u64) -> Self {
+        Self { sequence }
+    }
+
+    pub fn to_bytes(&self) -> [u8; 8] {
+        self.sequence.to_le_bytes()
+    }
+
+    pub fn from_bytes(bytes: [u8; 8]) -> Self {
+        Self {
+            sequence: u64::from_le_bytes(bytes),
+        }
+    }
+}
+
+impl fmt::Display for Checkpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.sequence)
+    }
+}
+
+impl FromStr for Checkpoint {
+    type Err = ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self {
+            sequence: s.trim().parse()?,
+        })
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
-pub struct Event<T> {
+pub struct Event<T, M = ()> {
     pub sequence: u64,
+    /// Generation of the slot claim that held the event: its lap around the ring, plus
+    /// one. Reads check it against `sequence`, so a recycled slot is never mistaken for it.
+    pub generation: u32,
     pub timestamp: u64,
     pub producer_id: u8,
+    /// Tags the producer pushed the event with, such as "replayed" or "end of batch";
+    /// see `Producer::push_with_flags`. Zero if it set none. With `sequence-32`, and
+    /// so compact slots, only the low 8 bits are kept.
+    pub flags: u32,
+    /// Set on an event a `MergeConsumer` delivered behind a newer one it had already
+    /// released; see `LatePolicy::SequenceAnyway`. False everywhere else.
+    pub late: bool,
+    /// The buffer's user header, `()` unless it was built with one
+    pub metadata: M,
     pub payload: T,
 }
 
-pub struct ConsumerIter<'a, T> {
-    consumer: &'a mut Consumer<T>,
+/// An event borrowed from its slot. The consumer stays on this event until it is dropped.
+#[derive(Debug)]
+pub struct EventRef<'a, T, M = ()> {
+    pub sequence: u64,
+    /// As `Event::generation`
+    pub generation: u32,
+    pub timestamp: u64,
+    pub producer_id: u8,
+    /// As `Event::flags`
+    pub flags: u32,
+    pub metadata: M,
+    payload: Payload<'a, T>,
+    cursor: &'a mut u64,
+    registration: &'a Registration,
+    release_to: u64,
+}
+
+/// The slot's payload, or a copy of it when producers may reclaim the slot early
+#[derive(Debug)]
+enum Payload<'a, T> {
+    Borrowed(&'a T),
+    Copied(T),
+}
+
+impl<T, M> EventRef<'_, T, M> {
+    pub fn payload(&self) -> &T {
+        match &self.payload {
+            Payload::Borrowed(payload) => payload,
+            Payload::Copied(payload) => payload,
+        }
+    }
+}
+
+impl<T, M> std::ops::Deref for EventRef<'_, T, M> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.payload()
+    }
+}
+
+impl<T, M> Drop for EventRef<'_, T, M> {
+    fn drop(&mut self) {
+        *self.cursor = self.sequence + 1;
+        self.registration.set(self.release_to);
+    }
 }
 
-impl<'a, T> Iterator for ConsumerIter<'a, T>
+pub struct ConsumerIter<'a, T, M = ()> {
+    consumer: &'a mut Consumer<T, M>,
+}
+
+impl<'a, T, M> Iterator for ConsumerIter<'a, T, M>
 where
     T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
 {
-    type Item = Event<T>;
+    type Item = Event<T, M>;
 
+    /// Ends at the first unsequenced slot. A lag or corrupted event also ends
+    /// iteration and is left for the next `try_next` to report.
     fn next(&mut self) -> Option<Self::Item> {
-        self.consumer.try_next()
+        self.consumer.take(false).ok()?
+    }
+}
+
+pub struct BlockingIter<'a, T, M = ()> {
+    consumer: &'a mut Consumer<T, M>,
+}
+
+impl<'a, T, M> Iterator for BlockingIter<'a, T, M>
+where
+    T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
+{
+    type Item = Result<Event<T, M>, ConsumerError>;
+
+    /// A lag is yielded as an error and iteration carries on from the oldest resident event
+    fn next(&mut self) -> Option<Self::Item> {
+        let buffer = self.consumer.buffer.clone();
+        self.consumer.next_until(&buffer.shutdown).transpose()
     }
 }
 
@@ -82,6 +799,7 @@ where
 mod tests {
     use super::*;
     use crate::buffer::Buffer;
+    use crate::slot::SlotState;
 
     #[test]
     fn consumer_reads_sequenced_slots() {
@@ -89,17 +807,15 @@ mod tests {
 
         // Manually sequence a slot for testing
         let slot = &buffer.slots[0];
-        unsafe {
-            (*slot.payload.get()).write(42);
-            *slot.timestamp.get() = 1000;
-            *slot.producer_id.get() = 0;
-        }
+        slot.stage(42, 1000);
         slot.sequence.store(0, Ordering::Release);
+        slot.generation
+            .store(buffer.generation(0), Ordering::Release);
         slot.state
             .store(SlotState::Sequenced as u8, Ordering::Release);
 
         let mut consumer = Consumer::new(buffer);
-        let event = consumer.try_next();
+        let event = consumer.try_next().unwrap();
 
         assert!(event.is_some());
         let event = event.unwrap();
@@ -114,7 +830,7 @@ mod tests {
         let mut consumer = Consumer::new(buffer);
 
         // No slots are sequenced yet
-        let event = consumer.try_next();
+        let event = consumer.try_next().unwrap();
         assert!(event.is_none());
     }
 
@@ -125,12 +841,10 @@ mod tests {
         // Sequence two slots
         for i in 0..2 {
             let slot = &buffer.slots[i];
-            unsafe {
-                (*slot.payload.get()).write(100 + i as u64);
-                *slot.timestamp.get() = 1000 + i as u64;
-                *slot.producer_id.get() = 0;
-            }
+            slot.stage(100 + i as u64, 1000 + i as u64);
             slot.sequence.store(i as u64, Ordering::Release);
+            slot.generation
+                .store(buffer.generation(i as u64), Ordering::Release);
             slot.state
                 .store(SlotState::Sequenced as u8, Ordering::Release);
         }
@@ -138,16 +852,465 @@ mod tests {
         let mut consumer = Consumer::new(buffer);
 
         // Read first event
-        let event1 = consumer.try_next().unwrap();
+        let event1 = consumer.try_next().unwrap().unwrap();
         assert_eq!(event1.sequence, 0);
         assert_eq!(event1.payload, 100);
 
         // Read second event
-        let event2 = consumer.try_next().unwrap();
+        let event2 = consumer.try_next().unwrap().unwrap();
         assert_eq!(event2.sequence, 1);
         assert_eq!(event2.payload, 101);
 
         // No more events
-        assert!(consumer.try_next().is_none());
+        assert!(consumer.try_next().unwrap().is_none());
+    }
+
+    /// Write `count` payloads into slots 0.. as already-sequenced events
+    fn sequence_slots(buffer: &Buffer<u64>, count: usize) {
+        for i in 0..count {
+            let slot = &buffer.slots[i];
+            slot.stage(100 + i as u64, 1000 + i as u64);
+            slot.sequence.store(i as u64, Ordering::Release);
+            slot.generation
+                .store(buffer.generation(i as u64), Ordering::Release);
+            slot.state
+                .store(SlotState::Sequenced as u8, Ordering::Release);
+        }
+    }
+
+    #[test]
+    fn batch_reads_all_sequenced_up_to_max() {
+        let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        sequence_slots(&buffer, 5);
+
+        let mut consumer = Consumer::new(buffer);
+        let batch = consumer.try_next_batch(3).unwrap();
+        let payloads: Vec<u64> = batch.iter().map(|e| e.payload).collect();
+        assert_eq!(payloads, vec![100, 101, 102]);
+
+        // Remaining events, stopping at the first unsequenced slot
+        let batch = consumer.try_next_batch(10).unwrap();
+        assert_eq!(batch.len(), 2);
+        assert_eq!(batch[0].sequence, 3);
+        assert!(consumer.try_next_batch(10).unwrap().is_empty());
+    }
+
+    #[test]
+    #[cfg(not(feature = "compact-slots"))]
+    fn batches_step_over_skipped_claims_and_stop_before_corruption() {
+        use crate::slot::{SlotWriteGuard, SKIPPED};
+
+        let buffer = Buffer::<u64>::builder()
+            .capacity(8)
+            .checksums(true)
+            .build()
+            .unwrap();
+        let producer = buffer.producer();
+        let mut consumer = buffer.consumer();
+        for i in 0..6 {
+            producer.push(i).unwrap();
+        }
+        buffer.flush();
+        // As if the sequencer had given up on the claim for 1
+        buffer.slots[1].flags.fetch_or(SKIPPED, Ordering::Relaxed);
+        let mut contents = unsafe { SlotWriteGuard::new(&buffer.slots[4]) };
+        unsafe { *contents.payload_mut().assume_init_mut() ^= 1 };
+
+        let batch = consumer.try_next_batch(8).unwrap();
+        let payloads: Vec<u64> = batch.iter().map(|event| event.payload).collect();
+        assert_eq!(payloads, vec![0, 2, 3]);
+        assert_eq!(consumer.registration.position().load(Ordering::Relaxed), 4);
+
+        let corrupted = ConsumerError::Corrupted { sequence: 4 };
+        assert_eq!(consumer.try_next_batch(8).unwrap_err(), corrupted);
+        assert_eq!(consumer.try_next_batch(8).unwrap()[0].payload, 5);
+    }
+
+    #[test]
+    #[cfg(not(feature = "compact-slots"))]
+    fn corruption_mid_batch_is_reported_by_the_next_call() {
+        use crate::slot::SlotWriteGuard;
+
+        let buffer = Buffer::<u64>::builder()
+            .capacity(8)
+            .checksums(true)
+            .build()
+            .unwrap();
+        let producer = buffer.producer();
+        let group = buffer.consumer_group();
+        let mut member = group.consumer();
+        let mut consumer = buffer.consumer();
+        for i in 0..4 {
+            producer.push(i).unwrap();
+        }
+        buffer.flush();
+        let mut contents = unsafe { SlotWriteGuard::new(&buffer.slots[2]) };
+        unsafe { *contents.payload_mut().assume_init_mut() ^= 1 };
+        let corrupted = ConsumerError::Corrupted { sequence: 2 };
+
+        // A group member takes events one at a time from the shared cursor
+        let batch = member.try_next_batch(8).unwrap();
+        let payloads: Vec<u64> = batch.iter().map(|event| event.payload).collect();
+        assert_eq!(payloads, vec![0, 1]);
+        assert_eq!(member.try_next_batch(8).unwrap_err(), corrupted);
+        assert_eq!(member.try_next_batch(8).unwrap()[0].payload, 3);
+
+        let payloads: Vec<u64> = consumer.iter().map(|event| event.payload).collect();
+        assert_eq!(payloads, vec![0, 1]);
+        assert!(consumer.iter().next().is_none());
+        assert_eq!(consumer.try_next().unwrap_err(), corrupted);
+        assert_eq!(consumer.try_next().unwrap().unwrap().payload, 3);
+    }
+
+    #[test]
+    fn batch_into_fills_caller_slice() {
+        let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        sequence_slots(&buffer, 4);
+
+        let mut consumer = Consumer::new(buffer);
+        let mut out = [MaybeUninit::<Event<u64>>::uninit(); 8];
+        let n = consumer.try_next_batch_into(&mut out).unwrap();
+        assert_eq!(n, 4);
+
+        let last = unsafe { out[3].assume_init() };
+        assert_eq!(last.sequence, 3);
+        assert_eq!(last.payload, 103);
+        assert!(consumer.try_next().unwrap().is_none());
+    }
+
+    #[test]
+    fn peek_does_not_advance_cursor() {
+        let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        sequence_slots(&buffer, 2);
+
+        let mut consumer = Consumer::new(buffer);
+        assert_eq!(consumer.peek().unwrap().unwrap().sequence, 0);
+        assert_eq!(consumer.peek().unwrap().unwrap().sequence, 0);
+
+        assert_eq!(consumer.try_next().unwrap().unwrap().sequence, 0);
+        assert_eq!(consumer.peek().unwrap().unwrap().payload, 101);
+        consumer.try_next().unwrap();
+        assert!(consumer.peek().unwrap().is_none());
+    }
+
+    #[test]
+    fn seek_repositions_within_resident_range() {
+        let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        sequence_slots(&buffer, 4);
+        buffer.next_seq.store(4, Ordering::Release);
+
+        let mut consumer = Consumer::new(buffer);
+        consumer.seek(2).unwrap();
+        assert_eq!(consumer.try_next().unwrap().unwrap().payload, 102);
+
+        consumer.seek(0).unwrap();
+        assert_eq!(consumer.try_next().unwrap().unwrap().sequence, 0);
+
+        // The end of the range is a valid position with nothing to read yet
+        consumer.seek(4).unwrap();
+        assert!(consumer.try_next().unwrap().is_none());
+    }
+
+    #[test]
+    fn seek_past_sequenced_reports_available_range() {
+        let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        sequence_slots(&buffer, 4);
+        buffer.next_seq.store(4, Ordering::Release);
+
+        let mut consumer = Consumer::new(buffer);
+        let err = consumer.seek(10).unwrap_err();
+        assert_eq!(
+            err,
+            ConsumerError::SeekOutOfRange {
+                requested: 10,
+                available: 0..4,
+            }
+        );
+    }
+
+    #[test]
+    fn skip_to_latest_jumps_to_newest_event() {
+        let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        let mut consumer = Consumer::new(buffer.clone());
+        assert_eq!(consumer.skip_to_latest(), 0);
+
+        sequence_slots(&buffer, 5);
+        buffer.next_seq.store(5, Ordering::Release);
+
+        assert_eq!(consumer.skip_to_latest(), 4);
+        assert_eq!(consumer.try_next().unwrap().unwrap().sequence, 4);
+        assert!(consumer.try_next().unwrap().is_none());
+        assert_eq!(consumer.skip_to_latest(), 0);
+    }
+
+    #[test]
+    fn event_ref_borrows_payload_and_advances_on_drop() {
+        let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        sequence_slots(&buffer, 2);
+
+        let mut consumer = Consumer::new(buffer.clone());
+        {
+            let event = consumer.try_next_ref().unwrap().unwrap();
+            assert_eq!(event.sequence, 0);
+            assert_eq!(event.timestamp, 1000);
+            assert_eq!(*event, 100);
+            assert!(std::ptr::eq(event.payload(), unsafe {
+                SlotReadGuard::new(&buffer.slots[0]).payload()
+            }));
+        }
+
+        assert_eq!(consumer.try_next_ref().unwrap().unwrap().payload(), &101);
+        assert!(consumer.try_next_ref().unwrap().is_none());
+    }
+
+    #[test]
+    #[cfg(not(feature = "compact-slots"))]
+    fn checksums_catch_corrupted_payloads() {
+        use crate::slot::SlotWriteGuard;
+
+        let buffer = Buffer::<u64>::builder()
+            .capacity(8)
+            .checksums(true)
+            .build()
+            .unwrap();
+        let producer = buffer.producer();
+        let mut consumer = buffer.consumer();
+        for i in 0..4 {
+            producer.push(i).unwrap();
+        }
+        buffer.flush();
+        // Flip a bit in two payloads, as bad RAM would
+        for index in [1, 2] {
+            let mut contents = unsafe { SlotWriteGuard::new(&buffer.slots[index]) };
+            unsafe { *contents.payload_mut().assume_init_mut() ^= 1 << 7 };
+        }
+
+        assert_eq!(consumer.try_next().unwrap().unwrap().payload, 0);
+        let corrupted = ConsumerError::Corrupted { sequence: 1 };
+        assert_eq!(consumer.try_next().unwrap_err(), corrupted);
+        let corrupted = ConsumerError::Corrupted { sequence: 2 };
+        assert_eq!(consumer.try_next_with(|event| *event.payload).unwrap_err(), corrupted);
+        assert_eq!(consumer.try_next().unwrap().unwrap().payload, 3);
+    }
+
+    #[test]
+    fn try_next_with_runs_closure_then_advances() {
+        let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        sequence_slots(&buffer, 2);
+
+        let mut consumer = Consumer::new(buffer);
+        let doubled = consumer.try_next_with(|event| {
+            assert_eq!(event.sequence, 0);
+            *event.payload * 2
+        });
+        assert_eq!(doubled, Ok(Some(200)));
+
+        let seq = consumer.try_next_with(|event| event.sequence);
+        assert_eq!(seq, Ok(Some(1)));
+        assert_eq!(consumer.try_next_with(|_| ()), Ok(None));
+    }
+
+    #[test]
+    fn overwritten_slot_reports_lagged_and_resyncs() {
+        let buffer = Buffer::<u64>::builder().capacity(4).build().unwrap();
+        let mut consumer = Consumer::new(buffer.clone());
+
+        // Sequences 0..6 went through a 4-slot ring: 0 and 1 were overwritten by 4 and 5
+        for seq in 2..6u64 {
+            let slot = &buffer.slots[(seq as usize) & buffer.mask];
+            slot.stage(seq, 0);
+            slot.sequence.store(seq, Ordering::Release);
+            slot.generation
+                .store(buffer.generation(seq), Ordering::Release);
+            slot.state
+                .store(SlotState::Sequenced as u8, Ordering::Release);
+        }
+        buffer.next_seq.store(6, Ordering::Release);
+
+        assert_eq!(
+            consumer.try_next().unwrap_err(),
+            ConsumerError::Lagged { skipped: 2 }
+        );
+        assert_eq!(consumer.try_next().unwrap().unwrap().sequence, 2);
+    }
+
+    #[test]
+    fn iterator_stops_at_lag_without_swallowing_it() {
+        let buffer = Buffer::<u64>::builder().capacity(4).build().unwrap();
+        let slot = &buffer.slots[0];
+        slot.sequence.store(4, Ordering::Release);
+        slot.generation
+            .store(buffer.generation(4), Ordering::Release);
+        slot.state
+            .store(SlotState::Sequenced as u8, Ordering::Release);
+        buffer.next_seq.store(5, Ordering::Release);
+
+        let mut consumer = Consumer::new(buffer);
+        assert_eq!(consumer.iter().count(), 0);
+        assert!(matches!(
+            consumer.try_next(),
+            Err(ConsumerError::Lagged { .. })
+        ));
+    }
+
+    #[test]
+    fn checkpoint_round_trips_position() {
+        let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        sequence_slots(&buffer, 4);
+        buffer.next_seq.store(4, Ordering::Release);
+
+        let mut consumer = Consumer::new(buffer.clone());
+        consumer.try_next().unwrap();
+        consumer.try_next().unwrap();
+        assert_eq!(consumer.position(), 2);
+
+        let saved = consumer.checkpoint();
+        assert_eq!(Checkpoint::from_bytes(saved.to_bytes()), saved);
+        assert_eq!(saved.to_string().parse::<Checkpoint>().unwrap(), saved);
+
+        let mut resumed = Consumer::new(buffer);
+        resumed.restore(&saved).unwrap();
+        assert_eq!(resumed.try_next().unwrap().unwrap().payload, 102);
+
+        assert!(resumed.set_position(99).is_err());
+        resumed.set_position(0).unwrap();
+        assert_eq!(resumed.position(), 0);
+    }
+
+    #[test]
+    fn uncommitted_events_are_redelivered() {
+        use crate::store::MemoryCursorStore;
+
+        let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        sequence_slots(&buffer, 4);
+        buffer.next_seq.store(4, Ordering::Release);
+        let store = MemoryCursorStore::new();
+
+        let mut consumer = Consumer::new(buffer.clone())
+            .with_store("audit", store.clone())
+            .unwrap();
+        consumer.try_next().unwrap();
+        consumer.commit().unwrap();
+        consumer.try_next().unwrap();
+        assert_eq!(consumer.committed(), Some(1));
+
+        // Uncommitted events are held back from recycling
+        assert_eq!(buffer.update_tail(), 1);
+
+        consumer.rollback().unwrap();
+        assert_eq!(consumer.try_next().unwrap().unwrap().sequence, 1);
+        drop(consumer);
+
+        // A new consumer under the same name resumes from the commit
+        let mut resumed = Consumer::new(buffer).with_store("audit", store).unwrap();
+        assert_eq!(resumed.try_next().unwrap().unwrap().sequence, 1);
+    }
+
+    #[test]
+    fn stored_positions_outside_the_ring_resume_without_error() {
+        use crate::store::{CursorStore, MemoryCursorStore};
+
+        // After a restart the ring starts empty, behind the stored position
+        let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        let mut store = MemoryCursorStore::new();
+        store.save("audit", Checkpoint::new(40)).unwrap();
+        let consumer = Consumer::new(buffer.clone()).with_store("audit", store).unwrap();
+        assert_eq!(consumer.position(), 40);
+        assert_eq!(consumer.committed(), Some(40));
+
+        // A position that has since been overwritten resumes at the oldest resident event
+        sequence_slots(&buffer, 16);
+        buffer.next_seq.store(20, Ordering::Release);
+        let mut store = MemoryCursorStore::new();
+        store.save("audit", Checkpoint::new(1)).unwrap();
+        let consumer = Consumer::new(buffer).with_store("audit", store).unwrap();
+        assert_eq!(consumer.position(), 4);
+    }
+
+    #[test]
+    fn next_blocks_until_event_sequenced() {
+        use crate::wait::WaitStrategy;
+        use std::thread;
+
+        let buffer = Buffer::<u64>::builder()
+            .capacity(16)
+            .wait_strategy(WaitStrategy::Blocking)
+            .build()
+            .unwrap();
+        let mut handle = buffer.start();
+
+        let producer = buffer.producer();
+        let pusher = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            producer.push(7).unwrap();
+        });
+
+        let mut consumer = buffer.consumer();
+        let event = consumer.next().unwrap();
+        assert_eq!(event.sequence, 0);
+        assert_eq!(event.payload, 7);
+
+        pusher.join().unwrap();
+        handle.stop();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn blocking_iter_waits_until_sequencer_stops() {
+        use crate::wait::WaitStrategy;
+        use std::thread;
+
+        let buffer = Buffer::<u64>::builder()
+            .capacity(16)
+            .wait_strategy(WaitStrategy::Blocking)
+            .build()
+            .unwrap();
+        let mut handle = buffer.start();
+
+        let mut consumer = buffer.consumer();
+        let reader = thread::spawn(move || {
+            consumer
+                .blocking_iter()
+                .map(|event| event.unwrap().payload)
+                .collect::<Vec<u64>>()
+        });
+
+        let producer = buffer.producer();
+        for i in 0..5 {
+            thread::sleep(Duration::from_millis(5));
+            producer.push(i).unwrap();
+        }
+        while buffer.next_seq.load(Ordering::Acquire) < 5 {
+            thread::yield_now();
+        }
+        handle.stop();
+        handle.join().unwrap();
+
+        assert_eq!(reader.join().unwrap(), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn next_timeout_expires_without_events() {
+        let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        let mut consumer = Consumer::new(buffer);
+
+        let start = Instant::now();
+        let result = consumer.next_timeout(Duration::from_millis(20));
+        assert_eq!(result.unwrap_err(), ConsumerError::Timeout);
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[test]
+    fn next_timeout_returns_sequenced_event() {
+        let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        let mut handle = buffer.start();
+        buffer.producer().push(9).unwrap();
+
+        let mut consumer = buffer.consumer();
+        let event = consumer.next_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(event.payload, 9);
+
+        handle.stop();
+        handle.join().unwrap();
     }
 }
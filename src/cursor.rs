@@ -0,0 +1,179 @@
+use crate::sync::atomic::{AtomicU64, Ordering};
+use std::alloc::Layout;
+use std::sync::{Arc, RwLock};
+
+/// Registered value for a consumer that holds nothing back
+pub(crate) const RELEASED: u64 = u64::MAX;
+
+/// A consumer position on its own cache line, so consumers advancing don't false-share
+#[derive(Debug, Default)]
+#[cfg_attr(not(feature = "align-128"), repr(align(64)))]
+#[cfg_attr(feature = "align-128", repr(align(128)))]
+pub(crate) struct CursorCell {
+    position: AtomicU64,
+}
+
+/// Positions of every registered consumer. The minimum gates slot recycling.
+#[derive(Debug, Default)]
+pub(crate) struct CursorRegistry {
+    cells: RwLock<Vec<Arc<CursorCell>>>,
+    /// Bumped each time the tail is lowered, so producers can tell a tail they cached
+    /// from before is stale
+    epoch: AtomicU64,
+    /// Producers may have decided to reuse slots below this before a consumer registered
+    /// or moved back to them, so reads below it are checked
+    checked_below: AtomicU64,
+}
+
+impl CursorRegistry {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn register(self: &Arc<Self>, position: u64) -> Registration {
+        self.register_below(position, None)
+    }
+
+    /// Register `position` and lower `tail` to it if it is above. Both happen under the
+    /// lock `with_min` holds, so a tail worked out without the new cell is stored before
+    /// the registration lowers it, never after.
+    pub(crate) fn register_below(
+        self: &Arc<Self>,
+        position: u64,
+        tail: Option<&AtomicU64>,
+    ) -> Registration {
+        let cell = Arc::new(CursorCell {
+            position: AtomicU64::new(position),
+        });
+        let mut cells = self.cells.write().unwrap_or_else(|e| e.into_inner());
+        cells.push(cell.clone());
+        if let Some(tail) = tail {
+            self.lower_locked(position, tail);
+        }
+        drop(cells);
+        Registration {
+            registry: self.clone(),
+            cell,
+        }
+    }
+
+    /// Lower `tail` to `position` for a registered consumer that moved back there, like
+    /// `register_below` does for a new one
+    pub(crate) fn lower(&self, position: u64, tail: &AtomicU64) {
+        let _cells = self.cells.write().unwrap_or_else(|e| e.into_inner());
+        self.lower_locked(position, tail);
+    }
+
+    fn lower_locked(&self, position: u64, tail: &AtomicU64) {
+        tail.fetch_min(position, Ordering::SeqCst);
+        self.epoch.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Count of the times the tail was lowered
+    pub(crate) fn epoch(&self) -> u64 {
+        self.epoch.load(Ordering::SeqCst)
+    }
+
+    /// Have reads below `sequence` checked from now on
+    pub(crate) fn check_below(&self, sequence: u64) {
+        self.checked_below.fetch_max(sequence, Ordering::SeqCst);
+    }
+
+    /// Sequences whose reads must be checked are below this
+    pub(crate) fn checked_below(&self) -> u64 {
+        self.checked_below.load(Ordering::Acquire)
+    }
+
+    /// Lowest registered position, or `None` if no consumer holds anything back.
+    /// Cells are read in registration order.
+    pub(crate) fn min(&self) -> Option<u64> {
+        self.with_min(|min| min)
+    }
+
+    /// `f` of the lowest registered position, with registrations held off until it returns
+    pub(crate) fn with_min<R>(&self, f: impl FnOnce(Option<u64>) -> R) -> R {
+        let cells = self.cells.read().unwrap_or_else(|e| e.into_inner());
+        f(cells
+            .iter()
+            .map(|cell| cell.position.load(Ordering::SeqCst))
+            .filter(|&position| position != RELEASED)
+            .min())
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.cells.read().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// Bytes held for registered consumers: each cell with its `Arc` counts, and the
+    /// list of them
+    pub(crate) fn memory_usage(&self) -> usize {
+        let cells = self.cells.read().unwrap_or_else(|e| e.into_inner());
+        let (cell, _) = Layout::new::<[usize; 2]>()
+            .extend(Layout::new::<CursorCell>())
+            .expect("small layout");
+        cells.len() * cell.pad_to_align().size() + cells.capacity() * size_of::<Arc<CursorCell>>()
+    }
+}
+
+/// A registered position. Unregisters itself on drop.
+#[derive(Debug)]
+pub(crate) struct Registration {
+    registry: Arc<CursorRegistry>,
+    cell: Arc<CursorCell>,
+}
+
+impl Registration {
+    pub(crate) fn position(&self) -> &AtomicU64 {
+        &self.cell.position
+    }
+
+    pub(crate) fn set(&self, position: u64) {
+        self.cell.position.store(position, Ordering::SeqCst);
+    }
+}
+
+impl Drop for Registration {
+    fn drop(&mut self) {
+        self.registry
+            .cells
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|cell| !Arc::ptr_eq(cell, &self.cell));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn min_tracks_registered_positions() {
+        let registry = Arc::new(CursorRegistry::new());
+        assert_eq!(registry.min(), None);
+
+        let a = registry.register(5);
+        let b = registry.register(3);
+        assert_eq!(registry.min(), Some(3));
+
+        b.set(9);
+        assert_eq!(registry.min(), Some(5));
+
+        a.set(RELEASED);
+        assert_eq!(registry.min(), Some(9));
+    }
+
+    #[test]
+    fn drop_unregisters() {
+        let registry = Arc::new(CursorRegistry::new());
+        let a = registry.register(1);
+        let _b = registry.register(2);
+        drop(a);
+        assert_eq!(registry.len(), 1);
+        assert_eq!(registry.min(), Some(2));
+    }
+
+    #[test]
+    fn cells_are_cache_line_aligned() {
+        assert_eq!(std::mem::align_of::<CursorCell>(), crate::pad::CACHE_LINE);
+    }
+}
@@ -0,0 +1,180 @@
+use crate::buffer::Buffer;
+use crate::slot::SlotState;
+use crate::sync::atomic::Ordering;
+use std::fmt;
+
+/// Claimed slots listed by the `Display` form before it summarises the rest
+const SHOWN_CLAIMS: usize = 8;
+
+/// Point-in-time report on a ring, from `Buffer::debug_dump`, for working out why it
+/// stalled. Fields are read one at a time while producers and the sequencer keep
+/// running, so on a busy ring they can disagree slightly with each other.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DebugDump {
+    pub capacity: usize,
+    /// Claims made so far; the next producer claims position `head`
+    pub head: u64,
+    /// Next sequence number the sequencer will assign
+    pub next_sequence: u64,
+    /// Lowest sequence any registered consumer may still read
+    pub tail: u64,
+    /// Oldest sequenced event some registered consumer has not read yet
+    pub oldest_unconsumed: Option<u64>,
+    pub free: usize,
+    pub claimed: usize,
+    pub published: usize,
+    pub sequenced: usize,
+    /// Slots a producer has claimed but not published, oldest claim first
+    pub claims: Vec<ClaimedSlot>,
+    pub consumers: usize,
+    /// Events waiting for a ring slot, with `OnFull::Grow`
+    pub overflowed: usize,
+    pub skipped_claims: u64,
+    pub closed: bool,
+    /// The sequencer has stopped
+    pub shutdown: bool,
+}
+
+/// A slot held in Claimed state, as listed by `DebugDump::claims`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClaimedSlot {
+    pub index: usize,
+    /// Claim position, which is the slot's sequence number under slot order
+    pub position: u64,
+    /// Claims made since this one. A producer is mid-push for a few claims at most;
+    /// one that is far behind `head` stalled or died while holding the slot.
+    pub age: u64,
+}
+
+impl<T, M> Buffer<T, M>
+where
+    T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
+{
+    /// Walk the ring and collect its state for diagnostics. Costs a pass over every
+    /// slot, so it is meant for incidents and debug endpoints rather than the hot path.
+    pub fn debug_dump(&self) -> DebugDump {
+        let head = self.head.load(Ordering::Acquire) as u64;
+        let next_sequence = self.next_seq.load(Ordering::Acquire);
+        let mut counts = [0; 4];
+        let mut claims = Vec::new();
+        for (index, slot) in self.slots.iter().enumerate() {
+            let state = slot.state.load(Ordering::Acquire);
+            counts[state as usize] += 1;
+            if state == SlotState::Claimed as u8 {
+                // Every claim bumps the generation, so it gives the lap of this claim
+                let lap = u64::from(slot.generation.load(Ordering::Relaxed)).saturating_sub(1);
+                let position = lap * self.capacity as u64 + index as u64;
+                claims.push(ClaimedSlot {
+                    index,
+                    position,
+                    age: head.saturating_sub(position + 1),
+                });
+            }
+        }
+        claims.sort_by_key(|claim| claim.position);
+
+        DebugDump {
+            capacity: self.capacity,
+            head,
+            next_sequence,
+            tail: self.update_tail(),
+            oldest_unconsumed: self.consumers.min().filter(|&min| min < next_sequence),
+            free: counts[SlotState::Free as usize],
+            claimed: counts[SlotState::Claimed as usize],
+            published: counts[SlotState::Published as usize],
+            sequenced: counts[SlotState::Sequenced as usize],
+            claims,
+            consumers: self.registered_consumers(),
+            overflowed: self.overflowed(),
+            skipped_claims: self.skipped_claims(),
+            closed: self.is_closed(),
+            shutdown: self.shutdown.load(Ordering::Acquire),
+        }
+    }
+}
+
+/// One line, e.g. `ring 8: head 12 next 9 tail 4 oldest 4 | free 0 claimed 1 ...`
+impl fmt::Display for DebugDump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "ring {}: head {} next {} tail {}",
+            self.capacity, self.head, self.next_sequence, self.tail
+        )?;
+        if let Some(oldest) = self.oldest_unconsumed {
+            write!(f, " oldest {}", oldest)?;
+        }
+        write!(
+            f,
+            " | free {} claimed {} published {} sequenced {} | consumers {}",
+            self.free, self.claimed, self.published, self.sequenced, self.consumers
+        )?;
+        if self.overflowed > 0 {
+            write!(f, " overflowed {}", self.overflowed)?;
+        }
+        if self.skipped_claims > 0 {
+            write!(f, " skipped {}", self.skipped_claims)?;
+        }
+        if self.closed {
+            write!(f, " closed")?;
+        }
+        if self.shutdown {
+            write!(f, " shutdown")?;
+        }
+        if !self.claims.is_empty() {
+            write!(f, " | claims")?;
+            for claim in self.claims.iter().take(SHOWN_CLAIMS) {
+                write!(f, " #{}@{} age {}", claim.index, claim.position, claim.age)?;
+            }
+            if self.claims.len() > SHOWN_CLAIMS {
+                write!(f, " +{} more", self.claims.len() - SHOWN_CLAIMS)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::producer::{try_claim, Claim};
+
+    #[test]
+    fn dump_reports_a_stalled_claim() {
+        let buffer = Buffer::<u64>::builder().capacity(8).build().unwrap();
+        let producer = buffer.producer();
+        let mut consumer = buffer.consumer();
+        producer.push(0).unwrap();
+        buffer.flush();
+        consumer.try_next().unwrap().unwrap();
+
+        // A producer that claims position 1 and never publishes
+        assert!(matches!(try_claim(&buffer, None), Claim::Claimed(_)));
+        for i in 2..5 {
+            producer.push(i).unwrap();
+        }
+        buffer.sequence_available();
+
+        let dump = buffer.debug_dump();
+        assert_eq!((dump.head, dump.next_sequence, dump.tail), (5, 1, 1));
+        assert_eq!(dump.oldest_unconsumed, None);
+        assert_eq!(
+            (dump.free, dump.claimed, dump.published, dump.sequenced),
+            (3, 1, 3, 1)
+        );
+        assert_eq!(
+            dump.claims,
+            vec![ClaimedSlot {
+                index: 1,
+                position: 1,
+                age: 3
+            }]
+        );
+        assert_eq!(
+            dump.to_string(),
+            "ring 8: head 5 next 1 tail 1 | free 3 claimed 1 published 3 sequenced 1 \
+             | consumers 1 | claims #1@1 age 3"
+        );
+    }
+}
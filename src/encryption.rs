@@ -0,0 +1,275 @@
+#[cfg(feature = "encryption")]
+use chacha20poly1305::aead::{AeadCore, AeadInPlace, KeyInit, OsRng};
+#[cfg(feature = "encryption")]
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use std::fmt;
+use std::io;
+#[cfg(feature = "encryption")]
+use std::sync::Arc;
+
+/// Bytes an encrypted payload adds to a record: the random nonce, then the tag
+pub(crate) const SEAL_OVERHEAD: usize = 24 + 16;
+
+/// Supplies the keys a `LogWriter` encrypts segments with and a `LogReader` decrypts
+/// them with, for logs of sensitive events on disks that are not encrypted themselves.
+///
+/// Each encrypted segment records the id of the key it was written with. A writer asks
+/// for the current key whenever it starts a segment, so keys rotate at segment
+/// boundaries; a reader asks for each segment's key by id, so old keys must stay
+/// available for as long as segments written with them are read. A `[u8; 32]` is a
+/// provider of a single key with id 0.
+#[cfg(feature = "encryption")]
+pub trait KeyProvider: Send + Sync {
+    /// The id and key new segments are encrypted with
+    fn current_key(&self) -> io::Result<(u32, [u8; 32])>;
+
+    /// The key with id `id`
+    fn key(&self, id: u32) -> io::Result<[u8; 32]>;
+}
+
+#[cfg(feature = "encryption")]
+impl KeyProvider for [u8; 32] {
+    fn current_key(&self) -> io::Result<(u32, [u8; 32])> {
+        Ok((0, *self))
+    }
+
+    fn key(&self, id: u32) -> io::Result<[u8; 32]> {
+        match id {
+            0 => Ok(*self),
+            _ => Err(io::Error::new(io::ErrorKind::NotFound, format!("no key with id {}", id))),
+        }
+    }
+}
+
+/// The key provider a log writer or reader was given, if any
+#[derive(Clone, Default)]
+pub(crate) struct Keys(#[cfg(feature = "encryption")] Option<Arc<dyn KeyProvider>>);
+
+impl Keys {
+    #[cfg(feature = "encryption")]
+    pub(crate) fn new(keys: impl KeyProvider + 'static) -> Self {
+        Self(Some(Arc::new(keys)))
+    }
+
+    /// Whether new segments are encrypted
+    pub(crate) fn encrypts(&self) -> bool {
+        #[cfg(feature = "encryption")]
+        return self.0.is_some();
+        #[cfg(not(feature = "encryption"))]
+        false
+    }
+
+    /// The id of the key to encrypt a new segment with and a sealer for it, or `None`
+    /// if segments are not encrypted
+    pub(crate) fn current(&self) -> io::Result<Option<(u32, Sealer)>> {
+        #[cfg(feature = "encryption")]
+        if let Some(keys) = &self.0 {
+            let (id, key) = keys.current_key()?;
+            return Ok(Some((id, Sealer::new(&key))));
+        }
+        Ok(None)
+    }
+
+    /// A sealer for a segment encrypted with the key `id`
+    pub(crate) fn sealer(&self, id: u32) -> io::Result<Sealer> {
+        #[cfg(feature = "encryption")]
+        return match &self.0 {
+            Some(keys) => Ok(Sealer::new(&keys.key(id)?)),
+            None => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("log segment is encrypted with key {}, but no key provider is set", id),
+            )),
+        };
+        #[cfg(not(feature = "encryption"))]
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("log segment is encrypted with key {}; enable the `encryption` feature", id),
+        ))
+    }
+}
+
+impl fmt::Debug for Keys {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Keys").field("encrypts", &self.encrypts()).finish()
+    }
+}
+
+/// Encrypts and decrypts the payloads of one segment with XChaCha20-Poly1305. Each
+/// payload gets a random nonce, stored in front of the ciphertext, and the record's
+/// sequence, timestamp and producer id are authenticated along with it.
+#[cfg(feature = "encryption")]
+pub(crate) struct Sealer {
+    cipher: XChaCha20Poly1305,
+}
+
+/// Never constructed without the `encryption` feature
+#[cfg(not(feature = "encryption"))]
+pub(crate) enum Sealer {}
+
+#[cfg(feature = "encryption")]
+impl Sealer {
+    fn new(key: &[u8; 32]) -> Self {
+        Self {
+            cipher: XChaCha20Poly1305::new(key.into()),
+        }
+    }
+
+    /// Append the nonce, ciphertext and tag of `payload` to `out`
+    pub(crate) fn seal(&self, header: &[u8], payload: &[u8], out: &mut Vec<u8>) -> io::Result<()> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        out.extend_from_slice(&nonce);
+        let start = out.len();
+        out.extend_from_slice(payload);
+        let tag = self
+            .cipher
+            .encrypt_in_place_detached(&nonce, header, &mut out[start..])
+            .map_err(|_| io::Error::other("encrypting a log payload failed"))?;
+        out.extend_from_slice(&tag);
+        Ok(())
+    }
+
+    /// Decrypt `sealed` into `payload`, returning false if it fails authentication: the
+    /// wrong key, or a record altered since it was written
+    pub(crate) fn open(&self, header: &[u8], sealed: &[u8], payload: &mut Vec<u8>) -> bool {
+        if sealed.len() < SEAL_OVERHEAD {
+            return false;
+        }
+        let (nonce, sealed) = sealed.split_at(24);
+        payload.clear();
+        payload.extend_from_slice(sealed);
+        self.cipher.decrypt_in_place(XNonce::from_slice(nonce), header, payload).is_ok()
+    }
+}
+
+#[cfg(not(feature = "encryption"))]
+impl Sealer {
+    pub(crate) fn seal(&self, _: &[u8], _: &[u8], _: &mut Vec<u8>) -> io::Result<()> {
+        match *self {}
+    }
+
+    pub(crate) fn open(&self, _: &[u8], _: &[u8], _: &mut Vec<u8>) -> bool {
+        match *self {}
+    }
+}
+
+#[cfg(all(test, feature = "encryption"))]
+mod tests {
+    use super::*;
+    use crate::adapter::EventSource;
+    use crate::buffer::Buffer;
+    use crate::persist::{temp_dir, LogReader, LogWriter};
+    use std::fs;
+    use std::path::Path;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Keys 1 and 2, rotated by setting `current`
+    struct Rotating {
+        current: AtomicU32,
+    }
+
+    impl KeyProvider for Arc<Rotating> {
+        fn current_key(&self) -> io::Result<(u32, [u8; 32])> {
+            let id = self.current.load(Ordering::Relaxed);
+            Ok((id, self.key(id)?))
+        }
+
+        fn key(&self, id: u32) -> io::Result<[u8; 32]> {
+            Ok([id as u8; 32])
+        }
+    }
+
+    /// A writer of 2 records to a segment, and a buffer with `count` events for it,
+    /// stamped with their payloads
+    fn writer(
+        dir: &Path,
+        count: u32,
+        keys: impl KeyProvider + 'static,
+    ) -> (Arc<Buffer<u32>>, LogWriter<u32>) {
+        let buffer = Buffer::<u32>::builder().capacity(64).build().unwrap();
+        let producer = buffer.producer();
+        for payload in 0..count {
+            producer.push_replayed(payload, (), payload as u64).unwrap();
+        }
+        buffer.flush();
+        let writer = buffer
+            .consumer()
+            .log_writer(dir)
+            .unwrap()
+            .key_provider(keys)
+            .segment_bytes(16 + 2 * (29 + SEAL_OVERHEAD as u64));
+        (buffer, writer)
+    }
+
+    #[test]
+    fn encrypted_segments_read_back_only_with_the_key() {
+        let dir = temp_dir("encrypted");
+        let key = [7; 32];
+        let (_buffer, mut writer) = writer(&dir, 5, key);
+        writer.drain().unwrap();
+        writer.sync().unwrap();
+
+        let path = dir.join(format!("{:020}.log", 0));
+        let bytes = fs::read(&path).unwrap();
+        assert_eq!(bytes[..12], *b"LFTESENC\x02\0\0\0");
+        assert_eq!(bytes.len(), 16 + 2 * (29 + SEAL_OVERHEAD) + 36);
+        // Payload 1 would sit right after the first record's header in the clear
+        assert_ne!(bytes[16 + 21..16 + 25], 1u32.to_le_bytes());
+
+        let recovered = LogReader::new(&dir).key_provider(key).recover::<u32>().unwrap();
+        let payloads: Vec<_> = recovered.events.iter().map(|event| event.payload).collect();
+        assert_eq!(payloads, [0, 1, 2, 3, 4]);
+        let mut replay = LogReader::new(&dir).key_provider(key).replay::<u32, ()>().unwrap();
+        replay.seek(3).unwrap();
+        assert_eq!(replay.next().unwrap().payload, 3);
+
+        let err = LogReader::new(&dir).recover::<u32>().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = LogReader::new(&dir).key_provider([8; 32]).recover::<u32>().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        // Timestamps and sequences stay in the clear
+        let found = LogReader::new(&dir).seek_to_timestamp(3).unwrap().unwrap();
+        assert_eq!(found.sequence, 3);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn keys_rotate_at_segment_boundaries() {
+        let dir = temp_dir("encrypted-rotate");
+        let keys = Arc::new(Rotating {
+            current: AtomicU32::new(1),
+        });
+        let (buffer, mut writer) = writer(&dir, 2, keys.clone());
+        writer.drain().unwrap();
+        keys.current.store(2, Ordering::Relaxed);
+        buffer.producer().push_replayed(2, (), 2).unwrap();
+        buffer.producer().push_replayed(3, (), 3).unwrap();
+        buffer.flush();
+        writer.drain().unwrap();
+        writer.sync().unwrap();
+
+        for (first, id) in [(0, 1), (2, 2)] {
+            let bytes = fs::read(dir.join(format!("{:020}.log", first))).unwrap();
+            assert_eq!(bytes[12..16], (id as u32).to_le_bytes());
+        }
+        let recovered = LogReader::new(&dir).key_provider(keys).recover::<u32>().unwrap();
+        assert_eq!(recovered.events.len(), 4);
+
+        // A segment written in the clear is not appended to with encryption
+        let plain = temp_dir("encrypted-plain");
+        let buffer = Buffer::<u32>::builder().capacity(8).build().unwrap();
+        buffer.producer().push(1).unwrap();
+        buffer.flush();
+        let mut writer = buffer.consumer().log_writer(&plain).unwrap();
+        writer.drain().unwrap();
+        buffer.producer().push(2).unwrap();
+        buffer.flush();
+        let mut consumer = buffer.consumer();
+        consumer.seek(0).unwrap();
+        let mut writer = consumer.log_writer(&plain).unwrap().key_provider([7; 32]);
+        assert_eq!(writer.drain().unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+        fs::remove_dir_all(&dir).unwrap();
+        fs::remove_dir_all(&plain).unwrap();
+    }
+}
@@ -1,9 +1,25 @@
 use std::fmt;
+use std::ops::Range;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum BuildError {
     InvalidCapacity,
     TooLarge,
+    /// More producers were requested than 8-bit producer IDs can tell apart
+    TooManyProducers,
+    /// A `BufferConfig` value could not be parsed
+    InvalidConfig(String),
+    /// `BufferBuilder::lock_memory` was refused for a reason other than the limit below
+    LockMemory(std::io::ErrorKind),
+    /// `BufferBuilder::lock_memory` needs more than `RLIMIT_MEMLOCK` allows, counting
+    /// whatever else the process has locked
+    MemlockLimit { needed: usize, limit: usize },
+    /// The ring could not be placed on the node given to `BufferBuilder::numa_node`
+    NumaNode(std::io::ErrorKind),
+    /// The file given to `BufferBuilder::mapped_file` could not be opened or mapped
+    MappedFile(std::io::ErrorKind),
+    /// `BytesBuffer::new` was given an arena with no room for any bytes
+    EmptyArena,
 }
 
 impl fmt::Display for BuildError {
@@ -11,16 +27,58 @@ impl fmt::Display for BuildError {
         match self {
             BuildError::InvalidCapacity => write!(f, "Capacity must be a power of two"),
             BuildError::TooLarge => write!(f, "Capacity exceeds maximum size"),
+            BuildError::TooManyProducers => write!(f, "Too many producers for 8-bit producer IDs"),
+            BuildError::InvalidConfig(msg) => write!(f, "Invalid buffer config: {}", msg),
+            BuildError::LockMemory(kind) => write!(f, "Could not lock ring memory: {}", kind),
+            BuildError::MemlockLimit { needed, limit } => write!(
+                f,
+                "Locking the ring needs {} bytes but RLIMIT_MEMLOCK allows {}; raise it (ulimit -l) or grant CAP_IPC_LOCK",
+                needed, limit
+            ),
+            BuildError::NumaNode(kind) => write!(f, "Could not place ring on NUMA node: {}", kind),
+            BuildError::MappedFile(kind) => write!(f, "Could not map ring file: {}", kind),
+            BuildError::EmptyArena => write!(f, "Arena must hold at least one byte"),
         }
     }
 }
 
 impl std::error::Error for BuildError {}
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RegistryError {
+    NameTaken(String),
+    NotFound(String),
+    /// The buffer under this name holds a different event type
+    WrongType(String),
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::NameTaken(name) => write!(f, "A buffer is already registered as {}", name),
+            RegistryError::NotFound(name) => write!(f, "No buffer is registered as {}", name),
+            RegistryError::WrongType(name) => {
+                write!(f, "Buffer {} holds a different event type", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum PushError {
     BufferFull,
     Shutdown,
+    /// The sequencer skipped this push's slot after the claim timeout
+    ClaimExpired,
+    /// Consumers are too far behind; see `BufferBuilder::backpressure`
+    Backpressure,
+    /// The frame is larger than the `BytesBuffer` arena, or than an `InlineBytes`
+    TooLarge,
+    /// A replayed log holds events from more than one producer, and the buffer was
+    /// built `single_producer`
+    TooManyProducers,
 }
 
 impl fmt::Display for PushError {
@@ -28,8 +86,65 @@ impl fmt::Display for PushError {
         match self {
             PushError::BufferFull => write!(f, "Buffer is full"),
             PushError::Shutdown => write!(f, "Buffer is shutting down"),
+            PushError::ClaimExpired => write!(f, "Slot claim expired before publish"),
+            PushError::Backpressure => write!(f, "Consumers are lagging too far behind"),
+            PushError::TooLarge => write!(f, "Payload is larger than the buffer can hold"),
+            PushError::TooManyProducers => write!(f, "Log has more producers than the buffer"),
         }
     }
 }
 
 impl std::error::Error for PushError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConsumerError {
+    Timeout,
+    Lagged {
+        skipped: u64,
+    },
+    SeekOutOfRange {
+        requested: u64,
+        available: Range<u64>,
+    },
+    Store(String),
+    /// The buffer was closed and every sequenced event has been read
+    Closed,
+    /// The event's payload no longer matches the checksum taken when it was pushed;
+    /// see `BufferBuilder::checksums`. The consumer has moved past it.
+    Corrupted {
+        sequence: u64,
+    },
+    /// A `ReplayConsumer` could not read its log, or an event in it does not decode
+    Log(String),
+}
+
+impl fmt::Display for ConsumerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConsumerError::Timeout => write!(f, "Timed out waiting for an event"),
+            ConsumerError::Lagged { skipped } => {
+                write!(
+                    f,
+                    "Consumer lagged behind, {} events were recycled",
+                    skipped
+                )
+            }
+            ConsumerError::SeekOutOfRange {
+                requested,
+                available,
+            } => write!(
+                f,
+                "Sequence {} is not resident (available: {}..{})",
+                requested, available.start, available.end
+            ),
+            ConsumerError::Store(msg) => write!(f, "Cursor store failed: {}", msg),
+            ConsumerError::Closed => write!(f, "Buffer is closed"),
+            ConsumerError::Corrupted { sequence } => {
+                write!(f, "Event {} does not match its checksum", sequence)
+            }
+            ConsumerError::Log(msg) => write!(f, "Reading the log failed: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ConsumerError {}
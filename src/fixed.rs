@@ -0,0 +1,97 @@
+use crate::buffer::{Buffer, BufferBuilder, MAX_CAPACITY};
+use crate::error::BuildError;
+use crate::slot::Slot;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// A ring of `N` slots stored inline, so it can live in a `static` instead of on the heap.
+///
+/// `N` is checked at compile time. The ring backs exactly one `Buffer`, which shares
+/// all producer, sequencer and consumer code with heap-allocated buffers. Only the
+/// slots live in the static: building the buffer still allocates, once, the `Buffer`
+/// itself behind its `Arc` and the sequencer's structures beside the ring, a bit per
+/// slot of published bitmap and a 16-byte publish queue cell per slot (8 bytes on
+/// 32-bit targets). None of it is allocated again afterwards.
+pub struct StaticBuffer<T, const N: usize> {
+    slots: [Slot<T>; N],
+    taken: AtomicBool,
+}
+
+impl<T, const N: usize> StaticBuffer<T, N> {
+    pub const fn new() -> Self {
+        const { assert!(N.is_power_of_two(), "StaticBuffer capacity must be a power of two") };
+        const { assert!(N <= MAX_CAPACITY, "StaticBuffer capacity exceeds maximum size") };
+        Self {
+            slots: [const { Slot::new() }; N],
+            taken: AtomicBool::new(false),
+        }
+    }
+}
+
+impl<T, const N: usize> StaticBuffer<T, N>
+where
+    T: Copy + Send + 'static,
+{
+    /// Configure the buffer this ring backs. Its capacity is `N`.
+    ///
+    /// Panics if the ring already backs a buffer: its slots cannot be shared or reset.
+    pub fn builder(&'static self) -> BufferBuilder<T> {
+        assert!(
+            !self.taken.swap(true, Ordering::AcqRel),
+            "static buffer already backs a Buffer"
+        );
+        BufferBuilder::new().static_slots(&self.slots)
+    }
+
+    /// Build a buffer with default settings on this ring
+    pub fn build(&'static self) -> Result<Arc<Buffer<T>>, BuildError> {
+        self.builder().build()
+    }
+}
+
+impl<T, const N: usize> Default for StaticBuffer<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::slot::SlotState;
+
+    #[test]
+    fn static_ring_backs_a_working_buffer() {
+        static RING: StaticBuffer<u64, 4> = StaticBuffer::new();
+        let buffer = RING.build().unwrap();
+        assert_eq!(buffer.capacity(), 4);
+
+        let producer = buffer.producer();
+        let mut consumer = buffer.consumer();
+        for i in 0..6 {
+            producer.push(i).unwrap();
+            buffer.flush();
+            assert_eq!(consumer.try_next().unwrap().unwrap().payload, i);
+        }
+        // The buffer writes straight into the static's slots
+        assert_eq!(
+            RING.slots[0].state.load(Ordering::Acquire),
+            SlotState::Sequenced as u8
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "already backs a Buffer")]
+    fn static_ring_backs_only_one_buffer() {
+        static RING: StaticBuffer<u64, 4> = StaticBuffer::new();
+        let _first = RING.build().unwrap();
+        let _second = RING.build();
+    }
+
+    #[test]
+    fn builder_rejects_a_different_capacity() {
+        static RING: StaticBuffer<u64, 4> = StaticBuffer::new();
+        let result = RING.builder().capacity(8).build();
+        assert_eq!(result.unwrap_err(), BuildError::InvalidCapacity);
+    }
+}
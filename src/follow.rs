@@ -0,0 +1,278 @@
+use crate::adapter::EventSource;
+use crate::consumer::Event;
+use crate::error::ConsumerError;
+use crate::persist::{read_durable, segments, LogPayload, LogReader, SegmentReader};
+use crate::replay::{log_error, logged_event};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Tails a log while a `LogWriter`, possibly in another process, is still appending to
+/// it, like `tail -f`, yielding each event once the writer has fsynced it.
+///
+/// Durability comes from the `durable` file the writer updates after every fsync: an
+/// event is held back until the sequence recorded there reaches it, so nothing is
+/// delivered that a crash could take back. Until a writer has synced once, nothing is.
+/// The follower moves on to the next segment once one appears and it has read the
+/// current one to its end, and reports damage as `ConsumerError::Log` only then,
+/// since until the writer moves on a damaged record may be one still being written.
+///
+/// Events come back as from `ReplayConsumer`, and a rewritten event is read once. The
+/// log never closes: `try_next` returns `None` while no durable event is waiting, and
+/// `next` polls every `poll_interval` until one is.
+pub struct LogFollower<T, M = ()> {
+    log: LogReader,
+    /// First sequence and path of the segment being read
+    segment: Option<(u64, PathBuf)>,
+    reader: Option<SegmentReader>,
+    /// Lowest sequence still to deliver
+    cursor: u64,
+    /// Last sequence the writer had recorded as fsynced, as of the last look
+    durable: Option<u64>,
+    /// Read, but not yet durable
+    pending: Option<Event<T, M>>,
+    poll_interval: Duration,
+    _event: PhantomData<fn() -> (T, M)>,
+}
+
+impl<T, M> LogFollower<T, M>
+where
+    T: LogPayload,
+    M: Default,
+{
+    pub(crate) fn new(log: LogReader) -> Self {
+        Self {
+            log,
+            segment: None,
+            reader: None,
+            cursor: 0,
+            durable: None,
+            pending: None,
+            poll_interval: Duration::from_millis(10),
+            _event: PhantomData,
+        }
+    }
+
+    /// How long `next` and `next_timeout` sleep between looks at the log (10 ms by
+    /// default)
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Lowest sequence the next event can have
+    pub fn position(&self) -> u64 {
+        self.pending.as_ref().map_or(self.cursor, |event| event.sequence)
+    }
+
+    /// Move to the first event at or after `sequence`, including one not written yet
+    pub fn seek(&mut self, sequence: u64) {
+        self.segment = None;
+        self.reader = None;
+        self.pending = None;
+        self.cursor = sequence;
+    }
+
+    /// The next event in the log, durable or not, or `None` if the writer has not
+    /// written it yet
+    fn read(&mut self) -> Result<Option<Event<T, M>>, ConsumerError> {
+        loop {
+            let Some(reader) = &mut self.reader else {
+                if !self.open_next()? {
+                    return Ok(None);
+                }
+                continue;
+            };
+            if let Some(record) = reader.next().map_err(log_error)? {
+                if record.sequence < self.cursor {
+                    continue;
+                }
+                self.cursor = record.sequence + 1;
+                return logged_event(record).map(Some);
+            }
+            if reader.offset == 0 {
+                // Opened before its header was written
+                self.reader = None;
+                return Ok(None);
+            }
+            // Look for a later segment before looking for more in this one: once the
+            // writer has started another, this one is as complete as it will get
+            let later = later_segment(&self.log.dir, &self.segment)?;
+            if reader.refresh().map_err(log_error)? {
+                continue;
+            }
+            let Some(later) = later else {
+                return Ok(None);
+            };
+            let (_, path) = self.segment.as_ref().expect("a segment is open");
+            if let Some(corruption) = reader.corruption(path).map_err(log_error)? {
+                return Err(ConsumerError::Log(corruption.to_string()));
+            }
+            self.segment = Some(later);
+            self.reader = None;
+        }
+    }
+
+    /// Open the segment to read next: the one holding the cursor to start with, then
+    /// each later one. Returns false if there is none yet.
+    fn open_next(&mut self) -> Result<bool, ConsumerError> {
+        let next = match &self.segment {
+            Some(segment) => Some(segment.clone()),
+            None => {
+                let segments = segments(&self.log.dir).map_err(log_error)?;
+                let holding = segments.partition_point(|&(first, _)| first <= self.cursor);
+                segments.into_iter().nth(holding.saturating_sub(1))
+            }
+        };
+        let Some((first, path)) = next else {
+            return Ok(false);
+        };
+        self.reader = Some(self.log.open_segment(&path).map_err(log_error)?);
+        self.segment = Some((first, path));
+        Ok(true)
+    }
+}
+
+impl<T, M> EventSource for LogFollower<T, M>
+where
+    T: LogPayload,
+    M: Default,
+{
+    type Item = Event<T, M>;
+
+    fn try_next(&mut self) -> Result<Option<Event<T, M>>, ConsumerError> {
+        if self.pending.is_none() {
+            self.pending = self.read()?;
+        }
+        let Some(sequence) = self.pending.as_ref().map(|event| event.sequence) else {
+            return Ok(None);
+        };
+        if self.durable.is_none_or(|durable| durable < sequence) {
+            let recorded = read_durable(&self.log.dir).map_err(log_error)?;
+            self.durable = self.durable.max(recorded);
+        }
+        match self.durable {
+            Some(durable) if durable >= sequence => Ok(self.pending.take()),
+            _ => Ok(None),
+        }
+    }
+
+    /// The next durable event, waiting as long as it takes
+    fn next(&mut self) -> Result<Event<T, M>, ConsumerError> {
+        loop {
+            if let Some(event) = self.try_next()? {
+                return Ok(event);
+            }
+            thread::sleep(self.poll_interval);
+        }
+    }
+
+    fn next_timeout(&mut self, timeout: Duration) -> Result<Event<T, M>, ConsumerError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(event) = self.try_next()? {
+                return Ok(event);
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(ConsumerError::Timeout);
+            }
+            thread::sleep(self.poll_interval.min(remaining));
+        }
+    }
+}
+
+/// The first segment in `dir` after `segment`, if the writer has started one
+fn later_segment(
+    dir: &Path,
+    segment: &Option<(u64, PathBuf)>,
+) -> Result<Option<(u64, PathBuf)>, ConsumerError> {
+    let current = segment.as_ref().map_or(0, |&(first, _)| first);
+    let segments = segments(dir).map_err(log_error)?;
+    Ok(segments.into_iter().find(|&(first, _)| first > current))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::Buffer;
+    use crate::persist::temp_dir;
+    use std::fs;
+    use std::sync::Arc;
+
+    fn push(buffer: &Arc<Buffer<u32>>, payloads: std::ops::Range<u32>) {
+        let producer = buffer.producer();
+        for payload in payloads {
+            producer.push(payload).unwrap();
+        }
+        buffer.flush();
+    }
+
+    fn sequences(follower: &mut LogFollower<u32>) -> Vec<u64> {
+        std::iter::from_fn(|| follower.try_next().unwrap()).map(|event| event.sequence).collect()
+    }
+
+    #[test]
+    fn follows_only_fsynced_events_across_segments() {
+        let dir = temp_dir("follow");
+        let buffer = Buffer::<u32>::builder().capacity(64).build().unwrap();
+        let mut writer = buffer
+            .consumer()
+            .log_writer(&dir)
+            .unwrap()
+            .segment_bytes(12 + 2 * 29)
+            .sync_interval(Duration::from_secs(60));
+        let mut follower = LogReader::new(&dir).follow::<u32, ()>();
+        assert!(follower.try_next().unwrap().is_none());
+
+        push(&buffer, 0..3);
+        writer.drain().unwrap();
+        assert!(sequences(&mut follower).is_empty());
+        writer.sync().unwrap();
+        assert_eq!(sequences(&mut follower), [0, 1, 2]);
+
+        push(&buffer, 3..6);
+        writer.drain().unwrap();
+        assert!(sequences(&mut follower).is_empty());
+        assert_eq!(follower.position(), 3);
+        writer.sync().unwrap();
+        assert_eq!(sequences(&mut follower), [3, 4, 5]);
+        let timeout = follower.next_timeout(Duration::from_millis(1));
+        assert_eq!(timeout.unwrap_err(), ConsumerError::Timeout);
+
+        // A second follower starts from the segment holding the sequence it seeks
+        let mut late = LogReader::new(&dir).follow::<u32, ()>();
+        late.seek(3);
+        assert_eq!(sequences(&mut late), [3, 4, 5]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn next_waits_for_the_writer() {
+        let dir = temp_dir("follow-wait");
+        let buffer = Buffer::<u32>::builder().capacity(64).build().unwrap();
+        let mut writer = buffer
+            .consumer()
+            .log_writer(&dir)
+            .unwrap()
+            .segment_bytes(12 + 3 * 29)
+            .sync_interval(Duration::ZERO);
+        let mut follower = LogReader::new(&dir)
+            .follow::<u32, ()>()
+            .poll_interval(Duration::from_millis(1));
+
+        let reader = std::thread::spawn(move || {
+            (0..8).map(|_| follower.next().unwrap().payload).collect::<Vec<_>>()
+        });
+        for i in 0..4 {
+            push(&buffer, 2 * i..2 * i + 2);
+            writer.drain().unwrap();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(reader.join().unwrap(), [0, 1, 2, 3, 4, 5, 6, 7]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
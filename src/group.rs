@@ -0,0 +1,139 @@
+use crate::buffer::Buffer;
+use crate::consumer::Consumer;
+use crate::cursor::Registration;
+use crate::sync::atomic::Ordering;
+use std::sync::Arc;
+
+/// What `Buffer::consumer()` handles do with each sequenced event
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeliveryMode {
+    /// Every consumer sees every event
+    #[default]
+    Broadcast,
+    /// Consumers share one cursor; each event goes to exactly one of them
+    WorkQueue,
+}
+
+/// A set of consumers sharing one cursor: each sequenced event goes to exactly one member
+pub struct ConsumerGroup<T, M = ()> {
+    buffer: Arc<Buffer<T, M>>,
+    cursor: Arc<Registration>,
+}
+
+impl<T, M> ConsumerGroup<T, M>
+where
+    T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
+{
+    pub(crate) fn new(buffer: Arc<Buffer<T, M>>) -> Self {
+        let cursor = Arc::new(buffer.register_consumer());
+        Self { buffer, cursor }
+    }
+
+    /// Create a new member handle
+    pub fn consumer(&self) -> Consumer<T, M> {
+        Consumer::with_group(self.buffer.clone(), self.cursor.clone())
+    }
+
+    /// Next sequence the group will hand out
+    pub fn position(&self) -> u64 {
+        self.cursor.position().load(Ordering::Acquire)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn members_share_one_cursor() {
+        let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        let mut handle = buffer.start();
+        let producer = buffer.producer();
+        for i in 0..4 {
+            producer.push(i).unwrap();
+        }
+
+        let group = buffer.consumer_group();
+        let mut a = group.consumer();
+        let mut b = group.consumer();
+
+        let first = a.next_timeout(Duration::from_secs(5)).unwrap();
+        let second = b.next_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(first.sequence, 0);
+        assert_eq!(second.sequence, 1);
+        assert_eq!(group.position(), 2);
+
+        handle.stop();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn each_event_delivered_to_exactly_one_member() {
+        const EVENTS: u64 = 200;
+
+        let buffer = Buffer::<u64>::builder().capacity(256).build().unwrap();
+        let mut handle = buffer.start();
+        let producer = buffer.producer();
+        for i in 0..EVENTS {
+            producer.push(i).unwrap();
+        }
+
+        let group = buffer.consumer_group();
+        let workers: Vec<_> = (0..4)
+            .map(|_| {
+                let mut member = group.consumer();
+                thread::spawn(move || {
+                    let mut seen = vec![];
+                    while let Ok(event) = member.next_timeout(Duration::from_millis(100)) {
+                        seen.push(event.payload);
+                    }
+                    seen
+                })
+            })
+            .collect();
+
+        let mut all = vec![];
+        for worker in workers {
+            all.extend(worker.join().unwrap());
+        }
+        let unique: HashSet<u64> = all.iter().copied().collect();
+        assert_eq!(all.len(), EVENTS as usize);
+        assert_eq!(unique.len(), EVENTS as usize);
+
+        handle.stop();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn work_queue_buffer_consumers_split_events() {
+        let buffer = Buffer::<u64>::builder()
+            .capacity(16)
+            .delivery(DeliveryMode::WorkQueue)
+            .build()
+            .unwrap();
+        let mut handle = buffer.start();
+        let producer = buffer.producer();
+        for i in 0..4 {
+            producer.push(i).unwrap();
+        }
+
+        let mut a = buffer.consumer();
+        let mut b = buffer.consumer();
+        assert_eq!(a.next_timeout(Duration::from_secs(5)).unwrap().sequence, 0);
+        assert_eq!(b.next_timeout(Duration::from_secs(5)).unwrap().sequence, 1);
+        assert_eq!(a.next_timeout(Duration::from_secs(5)).unwrap().sequence, 2);
+
+        handle.stop();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn broadcast_is_default() {
+        let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        assert_eq!(buffer.delivery(), DeliveryMode::Broadcast);
+    }
+}
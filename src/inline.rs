@@ -0,0 +1,184 @@
+use crate::consumer::{Consumer, Event};
+use crate::error::{ConsumerError, PushError};
+use crate::producer::Producer;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+
+/// Up to `N` bytes carried in the event itself, behind a two-byte length.
+///
+/// For small serialized messages that would otherwise need a hand-rolled `[u8; N]`
+/// and a length beside it. Being two-byte aligned, it packs straight after the slot
+/// header with no padding: the default 30 bytes (32 with the length) fill a 64-byte
+/// slot exactly, and larger `N` grow it by whole cache lines. With `sequence-32` the
+/// header is 8 bytes shorter, so up to 38 fit in the same slot. Bytes past the length
+/// are always zero, so equal messages are equal byte for byte.
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct InlineBytes<const N: usize = 30> {
+    len: u16,
+    bytes: [u8; N],
+}
+
+impl<const N: usize> InlineBytes<N> {
+    /// Copy `bytes` in, or `None` if there are more than `N` of them
+    pub fn new(bytes: &[u8]) -> Option<Self> {
+        const { assert!(N <= u16::MAX as usize, "InlineBytes<N> holds at most 65535 bytes") };
+        if bytes.len() > N {
+            return None;
+        }
+        let mut inline = Self::default();
+        inline.bytes[..bytes.len()].copy_from_slice(bytes);
+        inline.len = bytes.len() as u16;
+        Some(inline)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..self.len as usize]
+    }
+
+    /// The most bytes this type holds
+    pub const fn capacity() -> usize {
+        N
+    }
+}
+
+impl<const N: usize> Default for InlineBytes<N> {
+    /// No bytes
+    fn default() -> Self {
+        Self {
+            len: 0,
+            bytes: [0; N],
+        }
+    }
+}
+
+impl<const N: usize> Deref for InlineBytes<N> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl<const N: usize> AsRef<[u8]> for InlineBytes<N> {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl<const N: usize> PartialEq for InlineBytes<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_bytes() == other.as_bytes()
+    }
+}
+
+impl<const N: usize> Eq for InlineBytes<N> {}
+
+impl<const N: usize> Hash for InlineBytes<N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_bytes().hash(state);
+    }
+}
+
+impl<const N: usize> fmt::Debug for InlineBytes<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("InlineBytes").field(&self.as_bytes()).finish()
+    }
+}
+
+impl<const N: usize, M> Producer<InlineBytes<N>, M>
+where
+    M: Copy + Default + Send + 'static,
+{
+    /// Copy `bytes` into an event and push it; `TooLarge` if there are more than `N`
+    pub fn push_bytes(&self, bytes: &[u8]) -> Result<(), PushError> {
+        self.push(InlineBytes::new(bytes).ok_or(PushError::TooLarge)?)
+    }
+}
+
+impl<const N: usize, M> Consumer<InlineBytes<N>, M>
+where
+    M: Copy + Send + 'static,
+{
+    /// Run `f` against the next event's bytes in its slot, without copying; the
+    /// counterpart of `BytesConsumer::try_next_with`
+    pub fn try_next_bytes_with<R>(
+        &mut self,
+        f: impl FnOnce(&Event<&[u8], M>) -> R,
+    ) -> Result<Option<R>, ConsumerError> {
+        self.try_next_with(|event| f(&bytes_of(event)))
+    }
+
+    /// Block until the next event is sequenced and run `f` against its bytes
+    pub fn next_bytes_with<R>(
+        &mut self,
+        f: impl FnOnce(&Event<&[u8], M>) -> R,
+    ) -> Result<R, ConsumerError> {
+        self.next_with(|event| f(&bytes_of(event)))
+    }
+}
+
+fn bytes_of<'a, const N: usize, M: Copy>(
+    event: &Event<&'a InlineBytes<N>, M>,
+) -> Event<&'a [u8], M> {
+    Event {
+        sequence: event.sequence,
+        generation: event.generation,
+        timestamp: event.timestamp,
+        producer_id: event.producer_id,
+        flags: event.flags,
+        late: event.late,
+        metadata: event.metadata,
+        payload: event.payload.as_bytes(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::Buffer;
+
+    #[test]
+    fn messages_round_trip_through_their_slots() {
+        let buffer = Buffer::<InlineBytes>::builder().capacity(8).build().unwrap();
+        let producer = buffer.producer();
+        let mut consumer = buffer.consumer();
+
+        producer.push_bytes(b"hello").unwrap();
+        producer.push_bytes(&[]).unwrap();
+        producer.push_bytes(&[7; 30]).unwrap();
+        assert_eq!(producer.push_bytes(&[7; 31]), Err(PushError::TooLarge));
+        buffer.flush();
+
+        let first = consumer.try_next_bytes_with(|event| event.payload.to_vec());
+        assert_eq!(first.unwrap(), Some(b"hello".to_vec()));
+        assert_eq!(consumer.next_bytes_with(|event| event.payload.len()).unwrap(), 0);
+        let last = consumer.try_next().unwrap().unwrap();
+        assert_eq!((last.sequence, &*last.payload), (2, &[7; 30][..]));
+        assert!(consumer.try_next().unwrap().is_none());
+    }
+
+    #[test]
+    fn equality_compares_only_the_held_bytes() {
+        let mut dirty = InlineBytes::<4>::new(b"ab").unwrap();
+        dirty.bytes[3] = 9;
+        assert_eq!(Some(dirty), InlineBytes::new(b"ab"));
+        assert_ne!(InlineBytes::<4>::new(b"ab"), InlineBytes::new(b"ab\0"));
+        assert_eq!(InlineBytes::<4>::new(b"abcde"), None);
+        assert_eq!(InlineBytes::<4>::default().len(), 0);
+    }
+
+    #[test]
+    #[cfg(not(feature = "compact-slots"))]
+    fn default_capacity_fits_a_single_slot() {
+        use crate::slot::Slot;
+        use std::mem::size_of;
+
+        assert_eq!(size_of::<InlineBytes>(), 32);
+        assert_eq!(size_of::<Slot<InlineBytes>>(), size_of::<Slot<u64>>());
+        if cfg!(feature = "sequence-32") {
+            assert_eq!(size_of::<Slot<InlineBytes<38>>>(), size_of::<Slot<u64>>());
+        }
+    }
+}
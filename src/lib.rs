@@ -1,13 +1,81 @@
+mod adapter;
+mod any;
+mod affinity;
+mod arena;
+mod backpressure;
+mod bitmap;
 mod buffer;
+mod bytes;
+mod checksum;
+mod conflate;
+mod config;
 mod consumer;
+mod cursor;
+mod dump;
+mod encryption;
 mod error;
+// Its ring is built in a constant, which loom's atomics don't allow
+#[cfg(not(loom))]
+mod fixed;
+mod follow;
+mod group;
+mod inline;
+mod merge;
+mod notify;
+pub mod numa;
+mod pad;
+mod partition;
+mod persist;
+mod policy;
+mod pool;
+mod prefetch;
 mod producer;
+pub mod registry;
+mod replay;
+mod segment;
 mod sequencer;
+mod sink;
 mod slot;
+mod snapshot;
+mod store;
+mod subscription;
+mod sync;
+mod ttl;
+mod wait;
+mod weak;
 
 // Public re-exports
-pub use buffer::{Buffer, BufferBuilder};
-pub use consumer::{Consumer, Event};
-pub use error::{BuildError, PushError};
-pub use producer::Producer;
-pub use sequencer::SequencerHandle;
+pub use adapter::{EventSource, Filter, Map};
+pub use any::{AnyEvent, Typed};
+pub use arena::{ArenaBuffer, ArenaConsumer, ArenaProducer};
+pub use backpressure::BackpressureMode;
+pub use buffer::{Buffer, BufferBuilder, MemoryUsage, RangeRead};
+pub use bytes::{BytesBuffer, BytesConsumer, BytesProducer};
+pub use config::BufferConfig;
+pub use conflate::{Conflate, ConflateByKey};
+pub use consumer::{Checkpoint, Consumer, Event, EventRef};
+pub use dump::{ClaimedSlot, DebugDump};
+#[cfg(feature = "encryption")]
+pub use encryption::KeyProvider;
+pub use error::{BuildError, ConsumerError, PushError, RegistryError};
+#[cfg(not(loom))]
+pub use fixed::StaticBuffer;
+pub use follow::LogFollower;
+pub use group::{ConsumerGroup, DeliveryMode};
+pub use inline::InlineBytes;
+pub use merge::{LatePolicy, MergeConsumer};
+pub use partition::{PartitionedBuffer, PartitionedProducer};
+pub use persist::{
+    Corruption, LogPayload, LogPosition, LogReader, LogWriter, LoggedEvent, Recovery, TornTail,
+};
+pub use policy::{Candidate, Lanes, SequencerPolicy, SlotOrder};
+pub use pool::BufferPool;
+pub use producer::{OnFull, Producer};
+pub use replay::{PacedReplay, ReplayConsumer};
+pub use sequencer::{SequencerHandle, SequencerStats};
+pub use sink::{Sink, SinkFormat, SinkPayload};
+pub use snapshot::{Snapshot, SnapshotState, Snapshotter};
+pub use store::{CursorStore, FileCursorStore, MemoryCursorStore};
+pub use subscription::SubscriptionHandle;
+pub use wait::WaitStrategy;
+pub use weak::WeakConsumer;
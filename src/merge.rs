@@ -0,0 +1,304 @@
+use crate::adapter::EventSource;
+use crate::consumer::{Consumer, Event};
+use crate::error::ConsumerError;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How long to wait on one idle source before checking the others
+const POLL_SLICE: Duration = Duration::from_millis(1);
+
+/// What `MergeConsumer` does with an event older than one it has already released
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LatePolicy {
+    /// Discard it, counting it in `MergeConsumer::dropped_late`
+    Drop,
+    /// Deliver it with the rest, behind the newer events, with `Event::late` set
+    #[default]
+    SequenceAnyway,
+    /// Keep it out of the merged stream for `MergeConsumer::next_late` to hand out
+    RouteToSideBuffer,
+}
+
+/// Reads from several buffers and yields their events in timestamp order.
+///
+/// An event is released once every source has an event pending, or once some
+/// source has seen an event at least `lateness` ticks newer than it. An event
+/// arriving after a newer one was released is late, and handled by the
+/// `LatePolicy`; by default it is delivered as soon as ordering allows, behind
+/// newer events. Events read while waiting on an idle source, and late events
+/// routed aside, are held in memory.
+pub struct MergeConsumer<T, M = ()> {
+    sources: Vec<Consumer<T, M>>,
+    /// Events read from each source but not yet released
+    pending: Vec<VecDeque<Event<T, M>>>,
+    lateness: u64,
+    /// Newest timestamp seen on any source
+    newest: u64,
+    /// Newest timestamp released; anything older read afterwards is late
+    released: u64,
+    late_policy: LatePolicy,
+    /// Late events routed aside by `LatePolicy::RouteToSideBuffer`
+    late: VecDeque<Event<T, M>>,
+    dropped_late: u64,
+    /// Next source to wait on when all pending events are held back
+    next_wait: usize,
+}
+
+impl<T, M> MergeConsumer<T, M>
+where
+    T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
+{
+    /// Merge `sources`, holding events back for up to `lateness` timestamp ticks
+    pub fn new(sources: Vec<Consumer<T, M>>, lateness: u64) -> Self {
+        let pending = sources.iter().map(|_| VecDeque::new()).collect();
+        Self {
+            sources,
+            pending,
+            lateness,
+            newest: 0,
+            released: 0,
+            late_policy: LatePolicy::default(),
+            late: VecDeque::new(),
+            dropped_late: 0,
+            next_wait: 0,
+        }
+    }
+
+    /// Handle events older than one already released by `policy`
+    pub fn with_late_policy(mut self, policy: LatePolicy) -> Self {
+        self.late_policy = policy;
+        self
+    }
+
+    /// Take the oldest late event routed aside by `LatePolicy::RouteToSideBuffer`
+    pub fn next_late(&mut self) -> Option<Event<T, M>> {
+        self.late.pop_front()
+    }
+
+    /// Late events discarded by `LatePolicy::Drop`
+    pub fn dropped_late(&self) -> u64 {
+        self.dropped_late
+    }
+
+    /// Get the wrapped consumers back. Events held back for ordering or routed aside
+    /// are dropped.
+    pub fn into_inner(self) -> Vec<Consumer<T, M>> {
+        self.sources
+    }
+
+    /// Drain everything currently sequenced on every source
+    fn fill(&mut self) -> Result<(), ConsumerError> {
+        for index in 0..self.sources.len() {
+            while let Some(event) = self.sources[index].try_next()? {
+                self.admit(index, event);
+            }
+        }
+        Ok(())
+    }
+
+    /// Queue an event read from source `index`, unless it is late and the policy keeps
+    /// it out of the merged stream
+    fn admit(&mut self, index: usize, mut event: Event<T, M>) {
+        if event.timestamp < self.released {
+            match self.late_policy {
+                LatePolicy::Drop => {
+                    self.dropped_late += 1;
+                    return;
+                }
+                LatePolicy::SequenceAnyway => event.late = true,
+                LatePolicy::RouteToSideBuffer => {
+                    self.late.push_back(event);
+                    return;
+                }
+            }
+        }
+        self.newest = self.newest.max(event.timestamp);
+        self.pending[index].push_back(event);
+    }
+
+    /// Take the oldest pending event if nothing earlier can still arrive within the bound
+    fn release(&mut self) -> Option<Event<T, M>> {
+        let (index, oldest) = self
+            .pending
+            .iter()
+            .enumerate()
+            .filter_map(|(i, queue)| queue.front().map(|event| (i, event.timestamp)))
+            .min_by_key(|&(_, timestamp)| timestamp)?;
+
+        let all_pending = self.pending.iter().all(|queue| !queue.is_empty());
+        if all_pending || oldest.saturating_add(self.lateness) <= self.newest {
+            self.released = self.released.max(oldest);
+            self.pending[index].pop_front()
+        } else {
+            None
+        }
+    }
+
+    /// Wait briefly on one source with nothing pending, rotating between calls
+    fn wait_on_idle(&mut self, deadline: Option<Instant>) -> Result<(), ConsumerError> {
+        let count = self.sources.len();
+        let Some(index) = (0..count)
+            .map(|offset| (self.next_wait + offset) % count)
+            .find(|&i| self.pending[i].is_empty())
+        else {
+            return Ok(());
+        };
+        self.next_wait = (index + 1) % count;
+
+        let slice = match deadline {
+            Some(deadline) => POLL_SLICE.min(deadline.saturating_duration_since(Instant::now())),
+            None => POLL_SLICE,
+        };
+        match self.sources[index].next_timeout(slice) {
+            Ok(event) => {
+                self.admit(index, event);
+                Ok(())
+            }
+            Err(ConsumerError::Timeout) => Ok(()),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+impl<T, M> EventSource for MergeConsumer<T, M>
+where
+    T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
+{
+    type Item = Event<T, M>;
+
+    fn try_next(&mut self) -> Result<Option<Event<T, M>>, ConsumerError> {
+        self.fill()?;
+        Ok(self.release())
+    }
+
+    fn next(&mut self) -> Result<Event<T, M>, ConsumerError> {
+        loop {
+            if let Some(event) = self.try_next()? {
+                return Ok(event);
+            }
+            self.wait_on_idle(None)?;
+        }
+    }
+
+    fn next_timeout(&mut self, timeout: Duration) -> Result<Event<T, M>, ConsumerError> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(event) = self.try_next()? {
+                return Ok(event);
+            }
+            if Instant::now() >= deadline {
+                return Err(ConsumerError::Timeout);
+            }
+            self.wait_on_idle(Some(deadline))?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::Buffer;
+    use crate::slot::SlotState;
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+
+    /// A buffer holding one sequenced event per timestamp, with the timestamp as payload
+    fn sequenced(timestamps: &[u64]) -> Arc<Buffer<u64>> {
+        let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        for (i, &timestamp) in timestamps.iter().enumerate() {
+            let slot = &buffer.slots[i];
+            slot.stage(timestamp, timestamp);
+            slot.sequence.store(i as u64, Ordering::Release);
+            slot.generation
+                .store(buffer.generation(i as u64), Ordering::Release);
+            slot.state
+                .store(SlotState::Sequenced as u8, Ordering::Release);
+        }
+        buffer
+            .next_seq
+            .store(timestamps.len() as u64, Ordering::Release);
+        buffer
+    }
+
+    #[test]
+    fn merges_sources_in_timestamp_order() {
+        let a = sequenced(&[10, 40, 50]);
+        let b = sequenced(&[20, 30, 60]);
+        let mut merged = MergeConsumer::new(vec![a.consumer(), b.consumer()], 5);
+
+        let timestamps: Vec<u64> = merged
+            .try_next_batch(10)
+            .unwrap()
+            .iter()
+            .map(|event| event.payload)
+            .collect();
+        // 60 is held back: `a` is drained and could still produce something older
+        assert_eq!(timestamps, vec![10, 20, 30, 40, 50]);
+    }
+
+    #[test]
+    fn lateness_bound_holds_events_for_idle_sources() {
+        let a = sequenced(&[10, 15, 100]);
+        let b = sequenced(&[]);
+        let mut merged = MergeConsumer::new(vec![a.consumer(), b.consumer()], 50);
+
+        // Only events at least 50 ticks older than the newest seen (100) are released
+        let timestamps: Vec<u64> = merged
+            .try_next_batch(10)
+            .unwrap()
+            .iter()
+            .map(|event| event.payload)
+            .collect();
+        assert_eq!(timestamps, vec![10, 15]);
+        assert_eq!(
+            merged.next_timeout(Duration::from_millis(10)).unwrap_err(),
+            ConsumerError::Timeout
+        );
+    }
+    /// Merge `a`, holding 10 and 100, with an idle source that then gets 5 and 60,
+    /// after 10 was released: 5 is late
+    fn late_arrival(policy: LatePolicy) -> (MergeConsumer<u64>, Vec<(u64, bool)>) {
+        let a = sequenced(&[10, 100]);
+        let b = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        let mut merged =
+            MergeConsumer::new(vec![a.consumer(), b.consumer()], 50).with_late_policy(policy);
+        assert_eq!(merged.try_next().unwrap().unwrap().payload, 10);
+        assert!(merged.try_next().unwrap().is_none());
+
+        let producer = b.producer();
+        for timestamp in [5, 60] {
+            producer.push_replayed(timestamp, (), timestamp).unwrap();
+        }
+        b.flush();
+        let events = merged
+            .try_next_batch(10)
+            .unwrap()
+            .iter()
+            .map(|event| (event.payload, event.late))
+            .collect();
+        (merged, events)
+    }
+
+    #[test]
+    fn late_events_are_sequenced_anyway_by_default() {
+        let (_, events) = late_arrival(LatePolicy::default());
+        assert_eq!(events, vec![(5, true), (60, false)]);
+    }
+
+    #[test]
+    fn late_events_can_be_dropped_or_routed_aside() {
+        let (mut merged, events) = late_arrival(LatePolicy::Drop);
+        assert_eq!(events, vec![(60, false)]);
+        assert_eq!(merged.dropped_late(), 1);
+        assert!(merged.next_late().is_none());
+
+        let (mut merged, events) = late_arrival(LatePolicy::RouteToSideBuffer);
+        assert_eq!(events, vec![(60, false)]);
+        assert_eq!(merged.dropped_late(), 0);
+        let late = merged.next_late().unwrap();
+        assert_eq!((late.payload, late.late), (5, false));
+        assert!(merged.next_late().is_none());
+    }
+}
@@ -0,0 +1,149 @@
+use crate::sync::atomic::{AtomicUsize, Ordering};
+use crate::sync::{hint, ZeroedSlice};
+
+/// Bounded MPSC queue of published slot indices.
+///
+/// Producers push a slot's index once it is Published; the sequencer pops them and
+/// flags the slots in its `PublishedMap`, so it learns about new work without
+/// rescanning the ring and can park while the queue is empty. Each cell carries a
+/// stamp saying which lap it is ready for (Vyukov's bounded queue), counted from the
+/// start of that lap rather than from the cell's position, so a new queue is all zeros.
+#[derive(Debug)]
+pub(crate) struct PublishQueue {
+    cells: ZeroedSlice<Cell>,
+    mask: usize,
+    enqueue: AtomicUsize,
+    /// Only advanced by the holder of `Buffer::sequencing`
+    dequeue: AtomicUsize,
+}
+
+#[derive(Debug)]
+struct Cell {
+    stamp: AtomicUsize,
+    index: AtomicUsize,
+}
+
+impl PublishQueue {
+    /// Room for `capacity` indices, rounded up to a power of two, on pages of their
+    /// own with `own_pages`, as for `ZeroedSlice::new`
+    pub(crate) fn new(capacity: usize, own_pages: bool) -> Self {
+        let capacity = capacity.next_power_of_two();
+        // SAFETY: A cell of zero atomics is all zero bytes
+        let cells = unsafe {
+            ZeroedSlice::new(capacity, own_pages, || Cell {
+                stamp: AtomicUsize::new(0),
+                index: AtomicUsize::new(0),
+            })
+        };
+        Self {
+            cells,
+            mask: capacity - 1,
+            enqueue: AtomicUsize::new(0),
+            dequeue: AtomicUsize::new(0),
+        }
+    }
+
+    /// Queue `index` for the sequencer. Waits if the queue is full, which only
+    /// happens briefly after the sequencer rebuilt its bitmap from slot states.
+    pub(crate) fn push(&self, index: usize) {
+        loop {
+            let pos = self.enqueue.load(Ordering::Relaxed);
+            let lap = pos & !self.mask;
+            let cell = &self.cells[pos & self.mask];
+            let stamp = cell.stamp.load(Ordering::Acquire);
+
+            if stamp == lap {
+                if self
+                    .enqueue
+                    .compare_exchange_weak(pos, pos + 1, Ordering::Relaxed, Ordering::Relaxed)
+                    .is_ok()
+                {
+                    cell.index.store(index, Ordering::Relaxed);
+                    cell.stamp.store(lap + 1, Ordering::Release);
+                    return;
+                }
+            } else if stamp < lap {
+                // Full: the cell still holds last lap's index
+                std::thread::yield_now();
+            }
+            hint::spin_loop();
+        }
+    }
+
+    /// Take the oldest index. Caller must hold `Buffer::sequencing`.
+    pub(crate) fn pop(&self) -> Option<usize> {
+        let pos = self.dequeue.load(Ordering::Relaxed);
+        let lap = pos & !self.mask;
+        let cell = &self.cells[pos & self.mask];
+        if cell.stamp.load(Ordering::Acquire) != lap + 1 {
+            return None;
+        }
+        let index = cell.index.load(Ordering::Relaxed);
+        cell.stamp.store(lap + self.mask + 1, Ordering::Release);
+        self.dequeue.store(pos + 1, Ordering::Relaxed);
+        Some(index)
+    }
+
+    /// Address and length in bytes of the cells, for `memory_usage` and `mlock`
+    pub(crate) fn byte_range(&self) -> (*const u8, usize) {
+        (self.cells.as_ptr().cast(), size_of_val::<[Cell]>(&self.cells))
+    }
+
+    /// Write every cell so its pages are faulted in. Only before anything is pushed.
+    pub(crate) fn prefault(&self) {
+        for cell in self.cells.iter() {
+            cell.stamp.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Indices queued and not yet taken, counting pushes still in progress
+    pub(crate) fn len(&self) -> usize {
+        let dequeue = self.dequeue.load(Ordering::Relaxed);
+        self.enqueue.load(Ordering::Relaxed).saturating_sub(dequeue)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        let pos = self.dequeue.load(Ordering::Relaxed);
+        let lap = pos & !self.mask;
+        self.cells[pos & self.mask].stamp.load(Ordering::Acquire) != lap + 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn pops_in_push_order_across_laps() {
+        let queue = PublishQueue::new(3, false);
+        assert!(queue.is_empty());
+        for lap in 0..3 {
+            for index in 0..4 {
+                queue.push(lap * 4 + index);
+            }
+            let popped: Vec<usize> = std::iter::from_fn(|| queue.pop()).collect();
+            assert_eq!(popped, (lap * 4..lap * 4 + 4).collect::<Vec<_>>());
+        }
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn full_queue_waits_for_the_sequencer() {
+        let queue = Arc::new(PublishQueue::new(2, false));
+        queue.push(0);
+        queue.push(1);
+
+        let pusher = {
+            let queue = queue.clone();
+            std::thread::spawn(move || queue.push(2))
+        };
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        assert!(!pusher.is_finished());
+
+        assert_eq!(queue.pop(), Some(0));
+        pusher.join().unwrap();
+        let popped: Vec<usize> = std::iter::from_fn(|| queue.pop()).collect();
+        assert_eq!(popped, vec![1, 2]);
+    }
+}
@@ -0,0 +1,84 @@
+//! NUMA topology, for placing rings and sequencers on one node of a multi-socket host.
+//!
+//! `BufferBuilder::numa_node` allocates a ring on a node and runs its sequencer on
+//! that node's CPUs; `PartitionedBuffer::per_numa_node` builds one partition per node.
+
+use std::io;
+use std::ptr::NonNull;
+
+/// Online NUMA nodes, in order. A host without NUMA, or any OS but Linux, reports
+/// the single node 0.
+pub fn nodes() -> Vec<usize> {
+    std::fs::read_to_string("/sys/devices/system/node/online")
+        .ok()
+        .and_then(|list| parse_list(&list))
+        .filter(|nodes| !nodes.is_empty())
+        .unwrap_or_else(|| vec![0])
+}
+
+/// CPUs belonging to `node`
+pub(crate) fn cpus(node: usize) -> io::Result<Vec<usize>> {
+    let path = format!("/sys/devices/system/node/node{}/cpulist", node);
+    let list = std::fs::read_to_string(path)?;
+    parse_list(&list).ok_or_else(|| io::Error::from(io::ErrorKind::InvalidData))
+}
+
+/// Parse a kernel list like `0-3,8,10-11`
+fn parse_list(list: &str) -> Option<Vec<usize>> {
+    let mut items = Vec::new();
+    for range in list.trim().split(',').filter(|range| !range.is_empty()) {
+        let (start, end): (usize, usize) = match range.split_once('-') {
+            Some((start, end)) => (start.parse().ok()?, end.parse().ok()?),
+            None => {
+                let item = range.parse().ok()?;
+                (item, item)
+            }
+        };
+        items.extend(start..=end);
+    }
+    Some(items)
+}
+
+/// Have the pages of `len` bytes at `ptr` come from `node` when they are first touched
+#[cfg(target_os = "linux")]
+pub(crate) fn bind(ptr: NonNull<u8>, len: usize, node: usize) -> io::Result<()> {
+    const MPOL_BIND: libc::c_long = 2;
+    let bits = u64::BITS as usize;
+    let mut mask = vec![0u64; node / bits + 1];
+    mask[node / bits] |= 1 << (node % bits);
+    // SAFETY: The kernel reads `mask.len() * 64` bits of node mask and only changes
+    // the placement policy of our own mapping
+    let rc = unsafe {
+        libc::syscall(
+            libc::SYS_mbind,
+            ptr.as_ptr(),
+            len,
+            MPOL_BIND,
+            mask.as_ptr(),
+            mask.len() * bits,
+            0,
+        )
+    };
+    if rc != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub(crate) fn bind(_ptr: NonNull<u8>, _len: usize, _node: usize) -> io::Result<()> {
+    Err(io::Error::from(io::ErrorKind::Unsupported))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kernel_lists_expand_ranges() {
+        assert_eq!(parse_list("0-3,8,10-11\n"), Some(vec![0, 1, 2, 3, 8, 10, 11]));
+        assert_eq!(parse_list("0"), Some(vec![0]));
+        assert_eq!(parse_list("a-b"), None);
+        assert!(nodes().contains(&0));
+    }
+}
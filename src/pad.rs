@@ -0,0 +1,36 @@
+use std::ops::{Deref, DerefMut};
+
+/// Alignment of slots and padded counters: a cache line, or an adjacent-line pair
+/// with the `align-128` feature
+pub(crate) const CACHE_LINE: usize = if cfg!(feature = "align-128") { 128 } else { 64 };
+
+/// A value on a cache line of its own, so writes to it don't invalidate the line
+/// holding its neighbours. Used for counters that different threads hammer: producers
+/// bump `head`, the sequencer `next_seq`, and consumers and the reclaimer `tail`.
+#[derive(Debug, Default)]
+#[cfg_attr(not(feature = "align-128"), repr(align(64)))]
+#[cfg_attr(feature = "align-128", repr(align(128)))]
+pub(crate) struct CachePadded<T>(T);
+
+// The `repr` attributes can't name `CACHE_LINE`, so check they agree with it
+const _: () = assert!(align_of::<CachePadded<u8>>() == CACHE_LINE);
+
+impl<T> CachePadded<T> {
+    pub(crate) const fn new(value: T) -> Self {
+        Self(value)
+    }
+}
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for CachePadded<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
@@ -0,0 +1,234 @@
+use crate::buffer::{Buffer, BufferBuilder};
+use crate::consumer::Consumer;
+use crate::error::{BuildError, PushError};
+use crate::merge::MergeConsumer;
+use crate::numa;
+use crate::producer::Producer;
+use crate::sequencer::SequencerHandle;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::Arc;
+
+/// `N` independent buffers behind one handle, with events routed by key.
+///
+/// Every event pushed under the same key lands in the same partition, so it keeps
+/// its order relative to the others with that key while partitions are sequenced and
+/// consumed in parallel. The key hash is unseeded, so with the same partition count a
+/// key maps to the same partition in every process built with the same toolchain.
+pub struct PartitionedBuffer<T, M = ()> {
+    partitions: Vec<Arc<Buffer<T, M>>>,
+}
+
+impl<T, M> PartitionedBuffer<T, M>
+where
+    T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
+{
+    /// Build `partitions` buffers with `build`, which is passed each partition's index
+    pub fn new(
+        partitions: usize,
+        build: impl FnMut(usize) -> Result<Arc<Buffer<T, M>>, BuildError>,
+    ) -> Result<Self, BuildError> {
+        if partitions == 0 {
+            return Err(BuildError::InvalidConfig("no partitions".into()));
+        }
+        Ok(Self {
+            partitions: (0..partitions).map(build).collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// One partition per online NUMA node, each ring allocated and sequenced on its
+    /// node. `configure` sets everything else, e.g. capacity and wait strategy; partition
+    /// `i` lives on node `numa::nodes()[i]`, so pin producers and consumers to match.
+    pub fn per_numa_node(
+        mut configure: impl FnMut(BufferBuilder<T, M>) -> BufferBuilder<T, M>,
+    ) -> Result<Self, BuildError> {
+        let nodes = numa::nodes();
+        Self::new(nodes.len(), |index| {
+            configure(Buffer::builder()).numa_node(nodes[index]).build()
+        })
+    }
+
+    /// Number of partitions
+    pub fn partitions(&self) -> usize {
+        self.partitions.len()
+    }
+
+    /// The buffer backing partition `index`
+    pub fn partition(&self, index: usize) -> &Arc<Buffer<T, M>> {
+        &self.partitions[index]
+    }
+
+    /// Partition that events pushed under `key` go to
+    pub fn partition_for<K: Hash + ?Sized>(&self, key: &K) -> usize {
+        route(key, self.partitions.len())
+    }
+
+    /// Start a sequencer thread per partition
+    pub fn start(&self) -> Vec<SequencerHandle> {
+        self.partitions.iter().map(|buffer| buffer.start()).collect()
+    }
+
+    /// Run one sequencing pass over every partition on the calling thread
+    pub fn sequence_available(&self) -> usize {
+        self.partitions
+            .iter()
+            .map(|buffer| buffer.sequence_available())
+            .sum()
+    }
+
+    /// Block until every event pushed to any partition before the call has been sequenced
+    pub fn flush(&self) {
+        self.partitions.iter().for_each(|buffer| buffer.flush());
+    }
+
+    /// Close every partition; see `Buffer::close`
+    pub fn close(&self) {
+        self.partitions.iter().for_each(|buffer| buffer.close());
+    }
+
+    /// A producer holding one handle per partition
+    pub fn producer(&self) -> PartitionedProducer<T, M> {
+        PartitionedProducer {
+            producers: self.partitions.iter().map(|buffer| buffer.producer()).collect(),
+        }
+    }
+
+    /// A consumer of partition `index` alone
+    pub fn consumer(&self, index: usize) -> Consumer<T, M> {
+        self.partitions[index].consumer()
+    }
+
+    /// One consumer over every partition, yielding events in timestamp order;
+    /// see `MergeConsumer` for how `lateness` bounds reordering
+    pub fn merged_consumer(&self, lateness: u64) -> MergeConsumer<T, M> {
+        let consumers = self.partitions.iter().map(|buffer| buffer.consumer()).collect();
+        MergeConsumer::new(consumers, lateness)
+    }
+}
+
+pub struct PartitionedProducer<T, M = ()> {
+    producers: Vec<Producer<T, M>>,
+}
+
+impl<T, M> PartitionedProducer<T, M>
+where
+    T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
+{
+    /// Push `event` to the partition `key` hashes to
+    pub fn push<K: Hash + ?Sized>(&self, key: &K, event: T) -> Result<(), PushError>
+    where
+        M: Default,
+    {
+        self.producers[route(key, self.producers.len())].push(event)
+    }
+
+    /// Push `event` with `metadata` to the partition `key` hashes to
+    pub fn push_with_metadata<K: Hash + ?Sized>(
+        &self,
+        key: &K,
+        event: T,
+        metadata: M,
+    ) -> Result<(), PushError> {
+        self.producers[route(key, self.producers.len())].push_with_metadata(event, metadata)
+    }
+
+    /// Push `event` to partition `index` directly
+    pub fn push_to(&self, index: usize, event: T) -> Result<(), PushError>
+    where
+        M: Default,
+    {
+        self.producers[index].push(event)
+    }
+}
+
+/// `DefaultHasher::new` always starts from the same keys, unlike `RandomState`
+fn route<K: Hash + ?Sized>(key: &K, partitions: usize) -> usize {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    (hasher.finish() % partitions as u64) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::EventSource;
+
+    fn partitioned(n: usize) -> PartitionedBuffer<(u32, u32)> {
+        PartitionedBuffer::new(n, |_| Buffer::builder().capacity(64).build()).unwrap()
+    }
+
+    #[test]
+    fn a_key_keeps_its_order_within_one_partition() {
+        let buffer = partitioned(4);
+        let producer = buffer.producer();
+        for seq in 0..10 {
+            for key in 0..8u32 {
+                producer.push(&key, (key, seq)).unwrap();
+            }
+        }
+        buffer.flush();
+
+        for index in 0..buffer.partitions() {
+            let mut consumer = buffer.consumer(index);
+            let mut last = std::collections::HashMap::new();
+            for event in consumer.iter() {
+                let (key, seq) = event.payload;
+                assert_eq!(buffer.partition_for(&key), index);
+                assert_eq!(last.insert(key, seq).map_or(0, |prev| prev + 1), seq);
+            }
+        }
+    }
+
+    #[test]
+    fn merged_consumer_sees_every_partition() {
+        let buffer = partitioned(3);
+        let mut merged = buffer.merged_consumer(0);
+        let producer = buffer.producer();
+        for i in 0..3 {
+            producer.push_to(i as usize, (i, 0)).unwrap();
+        }
+        buffer.flush();
+
+        let mut keys: Vec<u32> = merged
+            .try_next_batch(10)
+            .unwrap()
+            .iter()
+            .map(|event| event.payload.0)
+            .collect();
+        keys.sort();
+        assert_eq!(keys, vec![0, 1, 2]);
+        assert!(matches!(
+            PartitionedBuffer::<u64>::new(0, |_| unreachable!()),
+            Err(BuildError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn partitions_carry_metadata() {
+        let buffer = PartitionedBuffer::<u64, u32>::new(2, |_| {
+            Buffer::builder().capacity(16).build()
+        })
+        .unwrap();
+        buffer.producer().push_with_metadata(&"key", 7, 70).unwrap();
+        buffer.flush();
+        let index = buffer.partition_for(&"key");
+        let event = buffer.consumer(index).try_next().unwrap().unwrap();
+        assert_eq!((event.payload, event.metadata), (7, 70));
+    }
+
+    #[test]
+    fn per_numa_node_builds_a_partition_per_node() {
+        let partitioned = match PartitionedBuffer::<u64>::per_numa_node(|builder| builder.capacity(16)) {
+            Ok(partitioned) => partitioned,
+            // Kernels without NUMA support reject mbind
+            Err(BuildError::NumaNode(_)) => return,
+            Err(e) => panic!("{}", e),
+        };
+        assert_eq!(partitioned.partitions(), numa::nodes().len());
+        partitioned.producer().push(&1, 7).unwrap();
+        partitioned.flush();
+        let index = partitioned.partition_for(&1);
+        assert_eq!(partitioned.consumer(index).try_next().unwrap().unwrap().payload, 7);
+    }
+}
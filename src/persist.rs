@@ -0,0 +1,1684 @@
+use crate::buffer::Buffer;
+use crate::checksum::Crc32;
+use crate::consumer::{Consumer, Event};
+#[cfg(feature = "encryption")]
+use crate::encryption::KeyProvider;
+use crate::encryption::{Keys, SEAL_OVERHEAD, Sealer};
+use crate::error::{ConsumerError, PushError};
+use crate::follow::LogFollower;
+use crate::producer::Producer;
+use crate::replay::ReplayConsumer;
+use crate::sink::SinkPayload;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::hash::Hasher;
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::mem;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// First bytes of every segment: the magic, then the format version as a little-endian `u32`
+const SEGMENT_HEADER: [u8; 12] = *b"LFTESLOG\x02\0\0\0";
+
+/// First bytes of an encrypted segment, followed by the `u32` id of its key
+const ENCRYPTED_HEADER: [u8; 12] = *b"LFTESENC\x02\0\0\0";
+
+/// Magic of the trailer that closes a segment
+const TRAILER_MAGIC: [u8; 8] = *b"LFTESEND";
+
+/// Bytes in a segment trailer: a `u32` 0 where a record's length would be, the magic,
+/// the `u64` record count and last sequence, the `u32` CRC-32 of the records' CRCs in
+/// order, and a `u32` CRC-32 of the trailer from the magic on
+const TRAILER_LEN: usize = 36;
+
+/// First bytes of every index file, versioned like segments
+const INDEX_HEADER: [u8; 12] = *b"LFTESIDX\x01\0\0\0";
+
+/// Bytes in an index entry: the `u64`s of an `IndexEntry`, in field order
+const INDEX_ENTRY_LEN: usize = 24;
+
+/// The file in a log directory that records the last sequence fsynced to the log
+const DURABLE_FILE: &str = "durable";
+
+/// First bytes of the durable file, versioned like segments
+const DURABLE_HEADER: [u8; 12] = *b"LFTESDUR\x01\0\0\0";
+
+/// An open segment file and how many bytes it holds
+struct Segment {
+    file: File,
+    len: u64,
+    /// Bytes before the first record
+    header_len: u64,
+    /// Set if the segment is encrypted
+    sealer: Option<Sealer>,
+    /// Records in the segment, the last one's sequence, and the CRC of their CRCs, for
+    /// its trailer
+    records: u64,
+    last: u64,
+    chain: Crc32,
+    /// The segment's timestamp index, appended to as records are written
+    index: File,
+    /// Offset of the last record indexed, if any has been in this writer's lifetime
+    indexed: Option<u64>,
+}
+
+/// Appends a consumer's events to segment files in a directory, for a durable copy
+/// of the stream.
+///
+/// Each segment is named for the first sequence in it (`00000000000000000042.log`,
+/// zero-padded to 20 digits so names sort in sequence order) and starts with the
+/// 8 bytes `LFTESLOG` and a `u32` format version, currently 2. Records follow
+/// back to back, all little-endian: a `u32` length, the `u64` sequence, `u64`
+/// timestamp and `u8` producer id, the payload's `SinkPayload::write_binary` bytes,
+/// and a `u32` CRC-32 (IEEE). The length and the CRC both cover the sequence
+/// through the payload, so a torn write at the end of a segment shows up as a
+/// short or mismatched last record. A closed segment ends with a 36-byte trailer: a
+/// `u32` 0, `LFTESEND`, the `u64` count of records and last sequence in the segment,
+/// the `u32` CRC-32 of the records' CRCs in order, and a `u32` CRC-32 of the trailer
+/// from `LFTESEND` on. Damage in a closed segment is never mistaken for a torn write,
+/// and records lost whole, or out of place, fail the trailer's check. Version 1
+/// segments, which have no trailers, are still read.
+///
+/// Beside each segment is a sparse timestamp index with the same name ending `.idx`:
+/// `LFTESIDX` and a `u32` version, then entries of three little-endian `u64`s, the
+/// largest timestamp of any record before the entry in the whole log, and the
+/// sequence and segment offset of the record the entry points at. Each segment's
+/// first record gets an entry, and so does the first after `index_interval` bytes of
+/// records since the last. `LogReader::seek_to_timestamp` searches them.
+///
+/// Events are written in batches of up to `batch_size`, one `write` per batch, and
+/// the segment is fsynced once `sync_interval` has passed since the last fsync, when
+/// a segment is closed, and when `run` returns. A segment is closed once it reaches
+/// `segment_bytes`. If the consumer has a cursor store, its position is committed
+/// after every fsync, so a restarted writer picks up after the last durable event;
+/// a segment that already exists under the name it would open is appended to, once
+/// any torn tail is cut off it.
+/// Lags are skipped over and counted rather than reported.
+///
+/// After every fsync, the writer records the last sequence it has fsynced in a file
+/// named `durable`, for `LogFollower`s in other processes: `LFTESDUR` and a `u32`
+/// version, the `u64` sequence, and a `u32` CRC-32 of the sequence.
+///
+/// With a `key_provider` (the `encryption` feature), segments start with `LFTESENC`,
+/// the version and the `u32` id of the key instead, and each payload is replaced by a
+/// 24-byte random nonce, the payload encrypted with XChaCha20-Poly1305 and its 16-byte
+/// tag, with the sequence, timestamp and producer id as associated data. Those three
+/// and the index stay readable without the key.
+pub struct LogWriter<T, M = ()> {
+    consumer: Consumer<T, M>,
+    dir: PathBuf,
+    segment_bytes: u64,
+    batch_size: usize,
+    sync_interval: Duration,
+    index_interval: u64,
+    keys: Keys,
+    /// Opened by the first event written
+    segment: Option<Segment>,
+    /// Records of the batch being written
+    pending: Vec<u8>,
+    /// Index entries for the records in `pending`
+    pending_index: Vec<u8>,
+    /// The payload of the record being added
+    payload: Vec<u8>,
+    /// Largest timestamp written to the log so far, including by earlier writers
+    max_timestamp: u64,
+    /// Sequence of the last record in `pending`, and of the last written out
+    pending_last: u64,
+    written: Option<u64>,
+    /// The durable file, and the sequence last recorded in it
+    durable: File,
+    marked: Option<u64>,
+    /// Whether anything has been written since the last fsync
+    dirty: bool,
+    last_sync: Instant,
+    /// Events lost to overruns
+    skipped: u64,
+}
+
+impl<T, M> LogWriter<T, M>
+where
+    T: Copy + Send + 'static + SinkPayload,
+    M: Copy + Send + 'static,
+{
+    pub(crate) fn new(consumer: Consumer<T, M>, dir: PathBuf) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        let max_timestamp = last_max_timestamp(&dir)?;
+        // Kept as the last writer left it until this one has synced
+        let durable = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(false)
+            .open(dir.join(DURABLE_FILE))?;
+        Ok(Self {
+            consumer,
+            dir,
+            segment_bytes: 64 << 20,
+            batch_size: 256,
+            sync_interval: Duration::from_secs(1),
+            index_interval: 4096,
+            keys: Keys::default(),
+            segment: None,
+            pending: Vec::new(),
+            pending_index: Vec::new(),
+            payload: Vec::new(),
+            max_timestamp,
+            pending_last: 0,
+            written: None,
+            durable,
+            marked: None,
+            dirty: false,
+            last_sync: Instant::now(),
+            skipped: 0,
+        })
+    }
+
+    /// Start a new segment once the current one holds `bytes` (64 MiB by default).
+    /// A single record larger than this still gets a segment to itself.
+    pub fn segment_bytes(mut self, bytes: u64) -> Self {
+        self.segment_bytes = bytes;
+        self
+    }
+
+    /// Write at most `batch_size` events at a time (256 by default)
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Fsync at most this often (every second by default). `Duration::ZERO` fsyncs
+    /// after every batch.
+    pub fn sync_interval(mut self, interval: Duration) -> Self {
+        self.sync_interval = interval;
+        self
+    }
+
+    /// Index a record at least every `bytes` of records (4 KiB by default), bounding how
+    /// far `LogReader::seek_to_timestamp` scans past the entry it finds
+    pub fn index_interval(mut self, bytes: u64) -> Self {
+        self.index_interval = bytes;
+        self
+    }
+
+    /// Encrypt the payloads of new segments with keys from `keys`. A segment that
+    /// already exists is appended to only if it is encrypted too.
+    #[cfg(feature = "encryption")]
+    pub fn key_provider(mut self, keys: impl KeyProvider + 'static) -> Self {
+        self.keys = Keys::new(keys);
+        self
+    }
+
+    /// Write everything currently sequenced, returning how many events were written.
+    /// Fsyncs only if `sync_interval` has passed; call `sync` to force it.
+    pub fn drain(&mut self) -> io::Result<u64> {
+        let mut written = 0;
+        loop {
+            let batch = self.write_available(self.batch_size)?;
+            if batch == 0 {
+                return Ok(written);
+            }
+            written += batch;
+            self.end_batch()?;
+        }
+    }
+
+    /// Write events as they arrive until the sequencer stops, returning how many were
+    /// written. While unsynced records are waiting, no more than `sync_interval` goes
+    /// by without an fsync, even if no further events arrive.
+    pub fn run(&mut self) -> io::Result<u64> {
+        let mut written = 0;
+        loop {
+            let next = if self.dirty {
+                let due = self.sync_interval.saturating_sub(self.last_sync.elapsed());
+                match self.consumer.next_timeout(due) {
+                    Err(ConsumerError::Timeout) => {
+                        self.sync()?;
+                        continue;
+                    }
+                    Err(ConsumerError::Closed) => break,
+                    next => next,
+                }
+            } else {
+                match self.consumer.blocking_iter().next() {
+                    None => break,
+                    Some(next) => next,
+                }
+            };
+            let first = match next {
+                Ok(event) => event,
+                Err(ConsumerError::Lagged { skipped }) => {
+                    self.skipped += skipped;
+                    continue;
+                }
+                Err(err) => return Err(io::Error::other(err)),
+            };
+            self.append(&first)?;
+            written += 1 + self.write_available(self.batch_size - 1)?;
+            self.end_batch()?;
+        }
+        self.sync()?;
+        Ok(written)
+    }
+
+    /// Fsync the open segment and, with a cursor store, commit the consumer's position
+    pub fn sync(&mut self) -> io::Result<()> {
+        self.write_pending()?;
+        if let Some(segment) = &self.segment {
+            segment.file.sync_data()?;
+            segment.index.sync_data()?;
+        }
+        self.dirty = false;
+        self.last_sync = Instant::now();
+        if self.written != self.marked
+            && let Some(sequence) = self.written
+        {
+            let mut crc = Crc32::new();
+            crc.write(&sequence.to_le_bytes());
+            let mut marker = DURABLE_HEADER.to_vec();
+            marker.extend_from_slice(&sequence.to_le_bytes());
+            marker.extend_from_slice(&(crc.finish() as u32).to_le_bytes());
+            self.durable.seek(SeekFrom::Start(0))?;
+            self.durable.write_all(&marker)?;
+            self.marked = self.written;
+        }
+        if self.consumer.has_store() {
+            self.consumer.commit().map_err(io::Error::other)?;
+        }
+        Ok(())
+    }
+
+    /// Number of events lost because the writer fell behind
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+
+    /// Get the consumer back. Does not fsync.
+    pub fn into_inner(self) -> Consumer<T, M> {
+        self.consumer
+    }
+
+    /// Write up to `max` events that are available without waiting
+    fn write_available(&mut self, max: usize) -> io::Result<u64> {
+        let mut written = 0;
+        while written < max as u64 {
+            let batch = match self.consumer.try_next_batch(max - written as usize) {
+                Ok(batch) => batch,
+                Err(ConsumerError::Lagged { skipped }) => {
+                    self.skipped += skipped;
+                    continue;
+                }
+                Err(err) => return Err(io::Error::other(err)),
+            };
+            if batch.is_empty() {
+                break;
+            }
+            for event in &batch {
+                self.append(event)?;
+            }
+            written += batch.len() as u64;
+        }
+        Ok(written)
+    }
+
+    fn end_batch(&mut self) -> io::Result<()> {
+        self.write_pending()?;
+        if self.dirty && self.last_sync.elapsed() >= self.sync_interval {
+            self.sync()?;
+        }
+        Ok(())
+    }
+
+    /// Add the event's record to the batch, closing the segment first if it is full
+    fn append(&mut self, event: &Event<T, M>) -> io::Result<()> {
+        self.payload.clear();
+        event.payload.write_binary(&mut self.payload);
+        let sealed = if self.keys.encrypts() { SEAL_OVERHEAD } else { 0 };
+        let record_len = (4 + 17 + self.payload.len() + sealed + 4) as u64;
+        let roll = match &self.segment {
+            None => true,
+            Some(segment) => {
+                let before = segment.len + self.pending.len() as u64;
+                before > segment.header_len && before + record_len > self.segment_bytes
+            }
+        };
+        if roll {
+            self.close_segment()?;
+            self.segment = Some(self.open_segment(event.sequence)?);
+        }
+
+        let segment = self.segment.as_mut().expect("a segment is open once rolled");
+        let start = self.pending.len();
+        self.pending.extend_from_slice(&[0; 4]);
+        self.pending.extend_from_slice(&event.sequence.to_le_bytes());
+        self.pending.extend_from_slice(&event.timestamp.to_le_bytes());
+        self.pending.push(event.producer_id);
+        match &segment.sealer {
+            Some(sealer) => {
+                let header: [u8; 17] = self.pending[start + 4..].try_into().unwrap();
+                sealer.seal(&header, &self.payload, &mut self.pending)?;
+            }
+            None => self.pending.extend_from_slice(&self.payload),
+        }
+        let len = (self.pending.len() - start - 4) as u32;
+        self.pending[start..start + 4].copy_from_slice(&len.to_le_bytes());
+        let mut crc = Crc32::new();
+        crc.write(&self.pending[start + 4..]);
+        let crc = (crc.finish() as u32).to_le_bytes();
+        self.pending.extend_from_slice(&crc);
+        segment.chain.write(&crc);
+        segment.records += 1;
+        segment.last = event.sequence;
+        self.pending_last = event.sequence;
+
+        let offset = segment.len + start as u64;
+        if segment.indexed.is_none_or(|last| offset - last >= self.index_interval) {
+            self.pending_index.extend_from_slice(&self.max_timestamp.to_le_bytes());
+            self.pending_index.extend_from_slice(&event.sequence.to_le_bytes());
+            self.pending_index.extend_from_slice(&offset.to_le_bytes());
+            segment.indexed = Some(offset);
+        }
+        self.max_timestamp = self.max_timestamp.max(event.timestamp);
+        Ok(())
+    }
+
+    fn write_pending(&mut self) -> io::Result<()> {
+        if let Some(segment) = &mut self.segment
+            && !self.pending.is_empty()
+        {
+            segment.file.write_all(&self.pending)?;
+            segment.len += self.pending.len() as u64;
+            self.pending.clear();
+            self.written = Some(self.pending_last);
+            self.dirty = true;
+            // After the records, so an entry never points past what was written
+            segment.index.write_all(&self.pending_index)?;
+            self.pending_index.clear();
+        }
+        Ok(())
+    }
+
+    /// Write out the open segment's last records and its trailer, and fsync it. The
+    /// rest of the batch is still unwritten, so the cursor is left for the next `sync`
+    /// to commit.
+    fn close_segment(&mut self) -> io::Result<()> {
+        self.write_pending()?;
+        if let Some(segment) = &mut self.segment {
+            let trailer = Trailer {
+                records: segment.records,
+                last: segment.last,
+                chain: segment.chain.finish() as u32,
+            };
+            segment.file.write_all(&trailer.encode())?;
+            segment.len += TRAILER_LEN as u64;
+            segment.file.sync_data()?;
+            segment.index.sync_data()?;
+        }
+        Ok(())
+    }
+
+    fn open_segment(&self, sequence: u64) -> io::Result<Segment> {
+        let path = self.dir.join(format!("{:020}.log", sequence));
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let mut len = file.metadata()?.len();
+        let mut index = OpenOptions::new().create(true).append(true).open(index_path(&path))?;
+        if index.metadata()?.len() == 0 {
+            index.write_all(&INDEX_HEADER)?;
+        }
+        // Carry on the trailer's count and checksum from the records already there
+        let (mut records, mut last, mut chain) = (0, 0, Crc32::new());
+        let mut existing = None;
+        if len > 0 {
+            let mut reader = SegmentReader::open(&path)?;
+            while let Some(record) = reader.next()? {
+                last = record.sequence;
+            }
+            if let Some(corruption) = reader.corruption(&path)? {
+                return Err(corruption.into());
+            }
+            // Cut off what a crash left half-written, header and all if need be
+            if reader.offset < len {
+                file.set_len(reader.offset)?;
+                truncate_index(&path, reader.offset)?;
+                len = reader.offset;
+            }
+            records = reader.records;
+            chain = reader.chain.take().unwrap_or_else(Crc32::new);
+            existing = Some(reader);
+        }
+        let (header_len, sealer) = match existing {
+            Some(existing) if len > 0 => {
+                if existing.key_id.is_some() != self.keys.encrypts() {
+                    return Err(invalid_data("cannot append to a segment encrypted differently"));
+                }
+                (existing.header_len, existing.key_id.map(|id| self.keys.sealer(id)).transpose()?)
+            }
+            _ => {
+                let (header, sealer) = match self.keys.current()? {
+                    Some((id, sealer)) => {
+                        ([&ENCRYPTED_HEADER[..], &id.to_le_bytes()].concat(), Some(sealer))
+                    }
+                    None => (SEGMENT_HEADER.to_vec(), None),
+                };
+                file.write_all(&header)?;
+                file.sync_data()?;
+                len = header.len() as u64;
+                // The new names are only durable once the directory is
+                #[cfg(unix)]
+                File::open(&self.dir)?.sync_all()?;
+                (len, sealer)
+            }
+        };
+        Ok(Segment {
+            file,
+            len,
+            header_len,
+            sealer,
+            records,
+            last,
+            chain,
+            index,
+            indexed: None,
+        })
+    }
+}
+
+/// Payloads a `LogReader` can decode from their `SinkPayload::write_binary` bytes
+pub trait LogPayload: SinkPayload + Sized {
+    /// The payload `bytes` encode, or `None` if they are not a valid encoding
+    fn read_binary(bytes: &[u8]) -> Option<Self>;
+}
+
+macro_rules! number_payload {
+    ($($ty:ty),*) => {$(
+        impl LogPayload for $ty {
+            fn read_binary(bytes: &[u8]) -> Option<Self> {
+                Some(<$ty>::from_le_bytes(bytes.try_into().ok()?))
+            }
+        }
+    )*};
+}
+
+number_payload!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+impl LogPayload for bool {
+    fn read_binary(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [0] => Some(false),
+            [1] => Some(true),
+            _ => None,
+        }
+    }
+}
+
+/// One record read back from a log
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoggedEvent<T> {
+    pub sequence: u64,
+    pub timestamp: u64,
+    pub producer_id: u8,
+    pub payload: T,
+}
+
+/// Bytes at the end of a segment that do not form an intact record
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TornTail {
+    pub path: PathBuf,
+    /// Where the first damaged record starts
+    pub offset: u64,
+    /// Bytes from there to the end of the segment
+    pub len: u64,
+}
+
+/// A damaged stretch of a segment with intact records, or the segment's trailer, after
+/// it, so that it cannot be a write cut short by a crash. Also the records before a
+/// trailer that does not match them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Corruption {
+    pub path: PathBuf,
+    /// Where the damage starts
+    pub offset: u64,
+    /// Bytes from there to where intact records pick up again
+    pub len: u64,
+    /// Sequences that cannot be read back: from the one after the last intact record
+    /// before the damage up to the first after it, or the segment's last
+    pub sequences: Range<u64>,
+}
+
+impl fmt::Display for Corruption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "log segment {} is corrupt at bytes {}..{}: sequences {}..{} are unreadable",
+            self.path.display(),
+            self.offset,
+            self.offset + self.len,
+            self.sequences.start,
+            self.sequences.end
+        )
+    }
+}
+
+impl std::error::Error for Corruption {}
+
+impl From<Corruption> for io::Error {
+    fn from(corruption: Corruption) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, corruption)
+    }
+}
+
+/// What `LogReader::recover` found in a log
+#[derive(Debug, Clone)]
+pub struct Recovery<T> {
+    /// Every intact event, in sequence order
+    pub events: Vec<LoggedEvent<T>>,
+    /// Segments whose last records were cut short or fail their CRC, in segment order
+    pub torn: Vec<TornTail>,
+}
+
+impl<T> Recovery<T> {
+    /// The last durable sequence, where a writer left off
+    pub fn last_sequence(&self) -> Option<u64> {
+        self.events.last().map(|event| event.sequence)
+    }
+}
+
+impl<T: Copy + Send + 'static> Recovery<T> {
+    /// Push every recovered event into `buffer`, in order, with its original timestamp
+    /// and producer id; flags and metadata, which the log does not keep, start out
+    /// zero and `M::default()`.
+    ///
+    /// On a fresh buffer, a log that holds every sequence from 0 (written from the
+    /// start by a writer that never lagged) lines its sequence numbers up with the
+    /// buffer's again, and later pushes carry on from `last_sequence() + 1`. Otherwise
+    /// the events are numbered afresh in the same order. Pushes wait for free slots
+    /// like any other, so a ring smaller than the log needs a running sequencer or no
+    /// registered consumers; without a sequencer thread, events are sequenced as they
+    /// go in. Returns how many events were pushed.
+    ///
+    /// A `single_producer` buffer only takes a log written by one producer, and fails
+    /// with `TooManyProducers` before pushing anything otherwise.
+    pub fn replay_into<M>(&self, buffer: &Arc<Buffer<T, M>>) -> Result<u64, PushError>
+    where
+        M: Copy + Default + Send + 'static,
+    {
+        if buffer.single_producer
+            && let Some(first) = self.events.first()
+            && self.events.iter().any(|event| event.producer_id != first.producer_id)
+        {
+            return Err(PushError::TooManyProducers);
+        }
+        let mut producers = HashMap::new();
+        for event in &self.events {
+            let producer = producers
+                .entry(event.producer_id)
+                .or_insert_with(|| Producer::new(buffer.clone(), event.producer_id));
+            producer.push_replayed(event.payload, M::default(), event.timestamp)?;
+            buffer.sequence_available();
+        }
+        Ok(self.events.len() as u64)
+    }
+}
+
+/// Where a record sits in a log
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogPosition {
+    pub sequence: u64,
+    pub timestamp: u64,
+    /// The segment holding the record
+    pub path: PathBuf,
+    /// Where the record starts in the segment
+    pub offset: u64,
+}
+
+/// Reads back the segments a `LogWriter` left in a directory.
+///
+/// Segments are read in sequence order. Each is read up to its first record that is
+/// cut short or fails its CRC. If nothing intact follows, the rest is reported as a
+/// `TornTail`, since a crash can leave a partial write at the end of any segment that
+/// was still open. Anything else is a `Corruption`: `recover` fails with it as an
+/// `InvalidData` error, and `verify` lists every one in the log.
+/// Where a later segment, or later records in the same one, go back to a sequence
+/// already read, they win: a writer restarted from its last commit rewrites events
+/// that had been written but not yet fsynced.
+///
+/// Payloads of encrypted segments are decrypted with the keys of the `key_provider`;
+/// reading one without it, or whose record fails authentication, is an error. Seeking
+/// by timestamp reads only what is stored in the clear and works without the keys.
+#[derive(Debug, Clone)]
+pub struct LogReader {
+    pub(crate) dir: PathBuf,
+    truncate: bool,
+    keys: Keys,
+}
+
+impl LogReader {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            truncate: false,
+            keys: Keys::default(),
+        }
+    }
+
+    /// Cut torn tails off their segments during `recover`, so the log holds only
+    /// intact records afterwards
+    pub fn truncate(mut self, truncate: bool) -> Self {
+        self.truncate = truncate;
+        self
+    }
+
+    /// Decrypt encrypted segments with keys from `keys`
+    #[cfg(feature = "encryption")]
+    pub fn key_provider(mut self, keys: impl KeyProvider + 'static) -> Self {
+        self.keys = Keys::new(keys);
+        self
+    }
+
+    /// Read every intact event in the log. A directory that does not exist yet holds
+    /// no events. Fails with `InvalidData` on a file that is not a segment of this
+    /// format, on an intact record whose payload does not decode as `T`, or on a
+    /// `Corruption`, which the error wraps. Torn tails are read past, and with
+    /// `truncate` cut off; corrupt segments are left as they are.
+    pub fn recover<T: LogPayload>(&self) -> io::Result<Recovery<T>> {
+        let mut recovery = Recovery {
+            events: Vec::new(),
+            torn: Vec::new(),
+        };
+        for (first, path) in segments(&self.dir)? {
+            let superseded = recovery.events.partition_point(|event| event.sequence < first);
+            recovery.events.truncate(superseded);
+            let mut reader = self.open_segment(&path)?;
+            while let Some(record) = reader.next()? {
+                // Rewritten after a restart into the segment it was first written to
+                if recovery.events.last().is_some_and(|last| last.sequence >= record.sequence) {
+                    let superseded =
+                        recovery.events.partition_point(|event| event.sequence < record.sequence);
+                    recovery.events.truncate(superseded);
+                }
+                let payload = T::read_binary(record.payload).ok_or_else(|| {
+                    invalid_data("log payload does not decode as the requested type")
+                })?;
+                recovery.events.push(LoggedEvent {
+                    sequence: record.sequence,
+                    timestamp: record.timestamp,
+                    producer_id: record.producer_id,
+                    payload,
+                });
+            }
+            if let Some(corruption) = reader.corruption(&path)? {
+                return Err(corruption.into());
+            }
+            let (intact, len) = (reader.offset, reader.len);
+            if intact < len {
+                if self.truncate {
+                    let file = OpenOptions::new().write(true).open(&path)?;
+                    file.set_len(intact)?;
+                    file.sync_all()?;
+                    truncate_index(&path, intact)?;
+                }
+                recovery.torn.push(TornTail {
+                    path,
+                    offset: intact,
+                    len: len - intact,
+                });
+            }
+        }
+        Ok(recovery)
+    }
+
+    /// Check every record and trailer in the log without decoding payloads, returning
+    /// each corrupt stretch, in order. Torn tails are not corruption; `recover` reports
+    /// them.
+    pub fn verify(&self) -> io::Result<Vec<Corruption>> {
+        let mut found = Vec::new();
+        for (_, path) in segments(&self.dir)? {
+            let mut reader = SegmentReader::open(&path)?;
+            loop {
+                while reader.next()?.is_some() {}
+                match reader.corruption(&path)? {
+                    Some(corruption) => found.push(corruption),
+                    None => break,
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    /// Find the first record, in sequence order, whose timestamp is at least
+    /// `timestamp`, or `None` if there is none. Binary searches the segments and then
+    /// one segment's timestamp index, and scans on from the entry it lands on: at most
+    /// an index interval when timestamps rise with sequence numbers, further past
+    /// stragglers stamped out of order. Without an index, scans the log from the start.
+    pub fn seek_to_timestamp(&self, timestamp: u64) -> io::Result<Option<LogPosition>> {
+        let segments = segments(&self.dir)?;
+        // Count the segments whose first record has only earlier timestamps before it
+        let (mut low, mut high) = (0, segments.len());
+        while low < high {
+            let mid = (low + high) / 2;
+            match first_index_key(&segments[mid].1)? {
+                Some(key) if key < timestamp => low = mid + 1,
+                Some(_) => high = mid,
+                None => return scan_for_timestamp(&segments, 0, 0, timestamp),
+            }
+        }
+        let Some(segment) = low.checked_sub(1) else {
+            return scan_for_timestamp(&segments, 0, 0, timestamp);
+        };
+        let path = &segments[segment].1;
+        let entries = read_index(path, fs::metadata(path)?.len())?;
+        let entry = entries.partition_point(|entry| entry.max_before < timestamp);
+        let offset = entry.checked_sub(1).map_or(0, |entry| entries[entry].offset);
+        scan_for_timestamp(&segments, segment, offset, timestamp)
+    }
+
+    /// Read the log back event by event, from its first event, through the same
+    /// `EventSource` surface as a live consumer
+    pub fn replay<T: LogPayload, M: Default>(&self) -> io::Result<ReplayConsumer<T, M>> {
+        ReplayConsumer::new(self.clone())
+    }
+
+    /// Tail the log as a writer appends to it, from its first event, yielding each one
+    /// once the writer has fsynced it
+    pub fn follow<T: LogPayload, M: Default>(&self) -> LogFollower<T, M> {
+        LogFollower::new(self.clone())
+    }
+
+    /// Open the segment at `path` to read its payloads, decrypting them if it is encrypted
+    pub(crate) fn open_segment(&self, path: &Path) -> io::Result<SegmentReader> {
+        let mut reader = SegmentReader::open(path)?;
+        if let Some(id) = reader.key_id {
+            reader.sealer = Some(self.keys.sealer(id)?);
+        }
+        Ok(reader)
+    }
+}
+
+/// The last sequence the writer of the log in `dir` has recorded as fsynced, if any.
+/// `None` as well while the record is being rewritten.
+pub(crate) fn read_durable(dir: &Path) -> io::Result<Option<u64>> {
+    let bytes = match fs::read(dir.join(DURABLE_FILE)) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let Some(marker) = bytes.strip_prefix(&DURABLE_HEADER).and_then(|rest| rest.get(..12)) else {
+        return Ok(None);
+    };
+    let (sequence, stored) = marker.split_at(8);
+    let mut crc = Crc32::new();
+    crc.write(sequence);
+    Ok((stored == (crc.finish() as u32).to_le_bytes())
+        .then(|| u64::from_le_bytes(sequence.try_into().unwrap())))
+}
+
+/// `(first sequence, path)` of every segment in `dir`, in sequence order
+pub(crate) fn segments(dir: &Path) -> io::Result<Vec<(u64, PathBuf)>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut segments = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let first = path
+            .file_name()
+            .and_then(|name| name.to_str()?.strip_suffix(".log")?.parse::<u64>().ok());
+        if let Some(first) = first {
+            segments.push((first, path));
+        }
+    }
+    segments.sort();
+    Ok(segments)
+}
+
+/// The first record stamped `timestamp` or later, starting at `offset` in
+/// `segments[segment]` and going on through the later segments
+fn scan_for_timestamp(
+    segments: &[(u64, PathBuf)],
+    segment: usize,
+    mut offset: u64,
+    timestamp: u64,
+) -> io::Result<Option<LogPosition>> {
+    for (_, path) in segments.iter().skip(segment) {
+        let mut reader = SegmentReader::open(path)?;
+        reader.seek(offset)?;
+        loop {
+            let start = reader.offset;
+            let Some(record) = reader.next()? else {
+                break;
+            };
+            if record.timestamp >= timestamp {
+                return Ok(Some(LogPosition {
+                    sequence: record.sequence,
+                    timestamp: record.timestamp,
+                    path: path.clone(),
+                    offset: start,
+                }));
+            }
+        }
+        offset = 0;
+    }
+    Ok(None)
+}
+
+/// A record as it is stored, payload undecoded. The payload of an encrypted segment
+/// read without its key is still sealed.
+pub(crate) struct Record<'a> {
+    pub(crate) sequence: u64,
+    pub(crate) timestamp: u64,
+    pub(crate) producer_id: u8,
+    pub(crate) payload: &'a [u8],
+}
+
+/// Reads a segment's records in order, up to the first one that is cut short or
+/// fails its CRC, checking the trailers it passes
+pub(crate) struct SegmentReader {
+    reader: BufReader<File>,
+    /// Where the next record starts
+    pub(crate) offset: u64,
+    /// Bytes in the segment when it was opened
+    len: u64,
+    /// Where the first record starts
+    header_len: u64,
+    /// The segment's first sequence, from its name
+    first: u64,
+    /// Records read, the last one's sequence, and the CRC of their CRCs; the CRC is
+    /// dropped once the reader skips any, leaving trailers unchecked
+    records: u64,
+    last: Option<u64>,
+    chain: Option<Crc32>,
+    /// Set when `next` stopped at a trailer that does not match the records before it
+    mismatch: bool,
+    /// The last record read: sequence through payload, then the CRC
+    record: Vec<u8>,
+    /// Set if the segment is encrypted
+    pub(crate) key_id: Option<u32>,
+    /// Decrypts payloads of an encrypted segment into `payload`; without it they are
+    /// returned sealed
+    sealer: Option<Sealer>,
+    payload: Vec<u8>,
+}
+
+impl SegmentReader {
+    /// Open the segment at `path`, checking its header. One that was created but whose
+    /// header never made it to disk reads as empty, with nothing intact.
+    pub(crate) fn open(path: &Path) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        let mut reader = Self {
+            reader: BufReader::new(file),
+            offset: 0,
+            len,
+            record: Vec::new(),
+            header_len: 0,
+            first: path
+                .file_stem()
+                .and_then(|name| name.to_str()?.parse().ok())
+                .unwrap_or(0),
+            records: 0,
+            last: None,
+            chain: Some(Crc32::new()),
+            mismatch: false,
+            key_id: None,
+            sealer: None,
+            payload: Vec::new(),
+        };
+        if len >= SEGMENT_HEADER.len() as u64 {
+            let mut header = [0; SEGMENT_HEADER.len()];
+            reader.reader.read_exact(&mut header)?;
+            let (magic, version) = header.split_at(8);
+            if !matches!(version, [1 | 2, 0, 0, 0]) {
+                return Err(invalid_data("not a version 1 or 2 lftes log segment"));
+            }
+            if magic == &ENCRYPTED_HEADER[..8] {
+                if len < SEGMENT_HEADER.len() as u64 + 4 {
+                    return Ok(reader);
+                }
+                let mut id = [0; 4];
+                reader.reader.read_exact(&mut id)?;
+                reader.key_id = Some(u32::from_le_bytes(id));
+                reader.offset = SEGMENT_HEADER.len() as u64 + 4;
+            } else if magic == &SEGMENT_HEADER[..8] {
+                reader.offset = SEGMENT_HEADER.len() as u64;
+            } else {
+                return Err(invalid_data("not an lftes log segment"));
+            }
+            reader.header_len = reader.offset;
+        }
+        Ok(reader)
+    }
+
+    /// Move on to the record starting at `offset`; an offset before the first record
+    /// leaves the reader where it is
+    pub(crate) fn seek(&mut self, offset: u64) -> io::Result<()> {
+        if offset > self.offset {
+            self.reader.seek(SeekFrom::Start(offset))?;
+            self.offset = offset;
+            self.chain = None;
+        }
+        Ok(())
+    }
+
+    /// Pick up whatever was appended to the segment since it was opened, returning
+    /// whether it grew. Reading starts over from the record `next` stopped at. A
+    /// segment opened before its header was written has to be opened again instead.
+    pub(crate) fn refresh(&mut self) -> io::Result<bool> {
+        let len = self.reader.get_ref().metadata()?.len();
+        let grew = len > self.len;
+        self.len = len;
+        self.reader.seek(SeekFrom::Start(self.offset))?;
+        Ok(grew)
+    }
+
+    /// The next intact record, or `None` at the end of the segment, a damaged record
+    /// or a trailer that does not match the records before it
+    pub(crate) fn next(&mut self) -> io::Result<Option<Record<'_>>> {
+        let len = loop {
+            let rest = self.len.saturating_sub(self.offset);
+            if self.offset == 0 || rest < 4 {
+                return Ok(None);
+            }
+            let mut len = [0; 4];
+            self.reader.read_exact(&mut len)?;
+            let len = u32::from_le_bytes(len) as u64;
+            if len != 0 {
+                if len < 17 || rest < len + 8 {
+                    return Ok(None);
+                }
+                break len;
+            }
+            if rest < TRAILER_LEN as u64 {
+                return Ok(None);
+            }
+            let mut trailer = [0; TRAILER_LEN];
+            self.reader.read_exact(&mut trailer[4..])?;
+            let Some(trailer) = Trailer::decode(&trailer) else {
+                return Ok(None);
+            };
+            if let Some(chain) = &self.chain
+                && (trailer.records != self.records
+                    || trailer.chain != chain.finish() as u32
+                    || self.last.is_some_and(|last| last != trailer.last))
+            {
+                self.mismatch = true;
+                return Ok(None);
+            }
+            self.offset += TRAILER_LEN as u64;
+        };
+        self.record.resize(len as usize + 4, 0);
+        self.reader.read_exact(&mut self.record)?;
+        let (body, stored) = self.record.split_at(len as usize);
+        let mut crc = Crc32::new();
+        crc.write(body);
+        if u32::from_le_bytes(stored.try_into().unwrap()) != crc.finish() as u32 {
+            return Ok(None);
+        }
+        self.offset += len + 8;
+        if let Some(chain) = &mut self.chain {
+            chain.write(stored);
+        }
+        self.records += 1;
+        let sequence = u64::from_le_bytes(body[..8].try_into().unwrap());
+        self.last = Some(sequence);
+        let payload = match &self.sealer {
+            Some(sealer) => {
+                if !sealer.open(&body[..17], &body[17..], &mut self.payload) {
+                    return Err(invalid_data(&format!(
+                        "log record {} fails authentication: wrong key, or altered",
+                        sequence
+                    )));
+                }
+                &self.payload[..]
+            }
+            None => &body[17..],
+        };
+        Ok(Some(Record {
+            sequence,
+            timestamp: u64::from_le_bytes(body[8..16].try_into().unwrap()),
+            producer_id: body[16],
+            payload,
+        }))
+    }
+}
+
+impl SegmentReader {
+    /// Once `next` has stopped short of the end of the segment, what is wrong there:
+    /// `None` for a torn tail, with nothing intact after it, or the corruption. Leaves
+    /// the reader at the next intact record or trailer, so reading can go on past it.
+    pub(crate) fn corruption(&mut self, path: &Path) -> io::Result<Option<Corruption>> {
+        if self.offset == 0 || self.offset >= self.len {
+            return Ok(None);
+        }
+        let from = self.last.map_or(self.first, |last| last + 1);
+        let damaged = self.offset;
+        let resume = if mem::take(&mut self.mismatch) {
+            // Every record is intact, but they are not the ones the trailer counted
+            let corruption = Corruption {
+                path: path.to_path_buf(),
+                offset: self.header_len,
+                len: damaged - self.header_len,
+                sequences: self.first..from,
+            };
+            Some((TRAILER_LEN as u64, corruption))
+        } else {
+            self.reader.seek(SeekFrom::Start(damaged))?;
+            let mut rest = Vec::new();
+            (&mut self.reader).take(self.len - damaged).read_to_end(&mut rest)?;
+            (1..rest.len()).find_map(|skip| {
+                let to = match intact_record(&rest[skip..]) {
+                    Some(sequence) => sequence,
+                    None => Trailer::decode(&rest[skip..])?.last + 1,
+                };
+                let corruption = Corruption {
+                    path: path.to_path_buf(),
+                    offset: damaged,
+                    len: skip as u64,
+                    sequences: from..to.max(from),
+                };
+                Some((skip as u64, corruption))
+            })
+        };
+        let Some((skip, corruption)) = resume else {
+            self.reader.seek(SeekFrom::Start(damaged))?;
+            return Ok(None);
+        };
+        self.offset = damaged + skip;
+        self.reader.seek(SeekFrom::Start(self.offset))?;
+        self.chain = None;
+        Ok(Some(corruption))
+    }
+}
+
+/// The sequence of the record at the start of `bytes`, if an intact one is there
+fn intact_record(bytes: &[u8]) -> Option<u64> {
+    let len = u32::from_le_bytes(bytes.get(..4)?.try_into().unwrap()) as usize;
+    if len < 17 {
+        return None;
+    }
+    let body = bytes.get(4..4 + len)?;
+    let stored = bytes.get(4 + len..8 + len)?;
+    let mut crc = Crc32::new();
+    crc.write(body);
+    (u32::from_le_bytes(stored.try_into().unwrap()) == crc.finish() as u32)
+        .then(|| u64::from_le_bytes(body[..8].try_into().unwrap()))
+}
+
+/// The trailer that closes a segment
+struct Trailer {
+    records: u64,
+    last: u64,
+    chain: u32,
+}
+
+impl Trailer {
+    fn encode(&self) -> [u8; TRAILER_LEN] {
+        let mut bytes = [0; TRAILER_LEN];
+        bytes[4..12].copy_from_slice(&TRAILER_MAGIC);
+        bytes[12..20].copy_from_slice(&self.records.to_le_bytes());
+        bytes[20..28].copy_from_slice(&self.last.to_le_bytes());
+        bytes[28..32].copy_from_slice(&self.chain.to_le_bytes());
+        let mut crc = Crc32::new();
+        crc.write(&bytes[4..32]);
+        bytes[32..].copy_from_slice(&(crc.finish() as u32).to_le_bytes());
+        bytes
+    }
+
+    /// The trailer at the start of `bytes`, if an intact one is there
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        let bytes = bytes.get(..TRAILER_LEN)?;
+        if bytes[..4] != [0; 4] || bytes[4..12] != TRAILER_MAGIC {
+            return None;
+        }
+        let mut crc = Crc32::new();
+        crc.write(&bytes[4..32]);
+        if bytes[32..] != (crc.finish() as u32).to_le_bytes() {
+            return None;
+        }
+        let field = |i: usize| u64::from_le_bytes(bytes[i..i + 8].try_into().unwrap());
+        Some(Self {
+            records: field(12),
+            last: field(20),
+            chain: u32::from_le_bytes(bytes[28..32].try_into().unwrap()),
+        })
+    }
+}
+
+/// One entry of a segment's timestamp index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct IndexEntry {
+    /// Largest timestamp of any record before this one in the log
+    pub(crate) max_before: u64,
+    pub(crate) sequence: u64,
+    pub(crate) offset: u64,
+}
+
+fn index_path(segment: &Path) -> PathBuf {
+    segment.with_extension("idx")
+}
+
+/// The intact entries of the index beside `segment` that point at records before
+/// `len`, in order. Empty if there is no index.
+pub(crate) fn read_index(segment: &Path, len: u64) -> io::Result<Vec<IndexEntry>> {
+    let bytes = match fs::read(index_path(segment)) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let Some(entries) = bytes.strip_prefix(&INDEX_HEADER) else {
+        return Ok(Vec::new());
+    };
+    let mut index: Vec<IndexEntry> = Vec::new();
+    for entry in entries.chunks_exact(INDEX_ENTRY_LEN) {
+        let field = |i: usize| u64::from_le_bytes(entry[i * 8..][..8].try_into().unwrap());
+        let entry = IndexEntry {
+            max_before: field(0),
+            sequence: field(1),
+            offset: field(2),
+        };
+        if entry.offset >= len || index.last().is_some_and(|last| last.offset >= entry.offset) {
+            break;
+        }
+        index.push(entry);
+    }
+    Ok(index)
+}
+
+/// `max_before` of the first entry in the index beside `segment`, or `None` if it has none
+fn first_index_key(segment: &Path) -> io::Result<Option<u64>> {
+    let mut bytes = [0; INDEX_HEADER.len() + INDEX_ENTRY_LEN];
+    let read = File::open(index_path(segment)).and_then(|mut file| file.read_exact(&mut bytes));
+    match read {
+        Ok(()) => Ok(bytes
+            .strip_prefix(&INDEX_HEADER)
+            .map(|entry| u64::from_le_bytes(entry[..8].try_into().unwrap()))),
+        Err(e) if matches!(e.kind(), io::ErrorKind::NotFound | io::ErrorKind::UnexpectedEof) => {
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Drop the entries of the index beside `segment` that point at or past `len`, once
+/// the segment is cut back to `len`
+fn truncate_index(segment: &Path, len: u64) -> io::Result<()> {
+    let kept = read_index(segment, len)?.len();
+    match OpenOptions::new().write(true).open(index_path(segment)) {
+        Ok(file) => {
+            file.set_len((INDEX_HEADER.len() + kept * INDEX_ENTRY_LEN) as u64)?;
+            file.sync_all()
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+/// The largest timestamp in the log in `dir`, from the last segment's last index entry
+/// and the records after it, so a new writer carries the index's running maximum on
+fn last_max_timestamp(dir: &Path) -> io::Result<u64> {
+    let Some((_, path)) = segments(dir)?.pop() else {
+        return Ok(0);
+    };
+    let last = read_index(&path, fs::metadata(&path)?.len())?.pop();
+    let mut max = last.map_or(0, |entry| entry.max_before);
+    let mut reader = SegmentReader::open(&path)?;
+    reader.seek(last.map_or(0, |entry| entry.offset))?;
+    while let Some(record) = reader.next()? {
+        max = max.max(record.timestamp);
+    }
+    Ok(max)
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// An empty path under the system temp directory for test `name`, unique to this process
+#[cfg(test)]
+pub(crate) fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("lftes-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::EventSource;
+    use crate::buffer::Buffer;
+    use crate::store::{CursorStore, MemoryCursorStore};
+    use std::path::Path;
+
+    /// `(sequence, payload)` of each record in the segment, checking lengths, CRCs and
+    /// trailers
+    fn records(path: &Path) -> Vec<(u64, u32)> {
+        let bytes = fs::read(path).unwrap();
+        assert_eq!(bytes[..12], SEGMENT_HEADER);
+        let mut rest = &bytes[12..];
+        let mut records: Vec<(u64, u32)> = Vec::new();
+        while !rest.is_empty() {
+            let len = u32::from_le_bytes(rest[..4].try_into().unwrap()) as usize;
+            if len == 0 {
+                let trailer = Trailer::decode(rest).unwrap();
+                assert_eq!(trailer.records, records.len() as u64);
+                assert_eq!(trailer.last, records.last().unwrap().0);
+                rest = &rest[TRAILER_LEN..];
+                continue;
+            }
+            assert_eq!(len, 8 + 8 + 1 + 4);
+            let body = &rest[4..4 + len];
+            let mut crc = Crc32::new();
+            crc.write(body);
+            let stored = u32::from_le_bytes(rest[4 + len..8 + len].try_into().unwrap());
+            assert_eq!(stored, crc.finish() as u32);
+            let sequence = u64::from_le_bytes(body[..8].try_into().unwrap());
+            records.push((sequence, u32::from_le_bytes(body[17..].try_into().unwrap())));
+            rest = &rest[8 + len..];
+        }
+        records
+    }
+
+    #[test]
+    fn records_roll_over_into_segments_named_by_first_sequence() {
+        let dir = temp_dir("log-roll");
+        let buffer = Buffer::<u32>::builder().capacity(16).build().unwrap();
+        let producer = buffer.producer();
+        for i in 0..5 {
+            producer.push(100 + i).unwrap();
+        }
+        buffer.flush();
+
+        // Header plus two 29-byte records
+        let mut writer = buffer
+            .consumer()
+            .log_writer(&dir)
+            .unwrap()
+            .segment_bytes(12 + 2 * 29)
+            .batch_size(3);
+        assert_eq!(writer.drain().unwrap(), 5);
+        writer.sync().unwrap();
+
+        let mut names: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "00000000000000000000.idx",
+                "00000000000000000000.log",
+                "00000000000000000002.idx",
+                "00000000000000000002.log",
+                "00000000000000000004.idx",
+                "00000000000000000004.log",
+                "durable"
+            ]
+        );
+        assert_eq!(read_durable(&dir).unwrap(), Some(4));
+        names.retain(|name| name.ends_with(".log"));
+        assert_eq!(records(&dir.join(&names[0])), [(0, 100), (1, 101)]);
+        assert_eq!(records(&dir.join(&names[1])), [(2, 102), (3, 103)]);
+        assert_eq!(records(&dir.join(&names[2])), [(4, 104)]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn syncs_commit_the_cursor_so_a_restart_resumes_after_them() {
+        let dir = temp_dir("log-resume");
+        let buffer = Buffer::<u32>::builder().capacity(16).build().unwrap();
+        let producer = buffer.producer();
+        let store = MemoryCursorStore::new();
+        let consumer = buffer.consumer().with_store("log", store.clone()).unwrap();
+        let mut writer = consumer.log_writer(&dir).unwrap();
+
+        producer.push(1).unwrap();
+        producer.push(2).unwrap();
+        buffer.flush();
+        writer.drain().unwrap();
+        assert_eq!(store.load("log").unwrap(), None);
+        writer.sync().unwrap();
+        assert_eq!(store.load("log").unwrap().unwrap().sequence, 2);
+        drop(writer);
+
+        producer.push(3).unwrap();
+        buffer.flush();
+        let consumer = buffer.consumer().with_store("log", store).unwrap();
+        let mut writer = consumer.log_writer(&dir).unwrap();
+        assert_eq!(writer.drain().unwrap(), 1);
+        writer.sync().unwrap();
+
+        let first = dir.join("00000000000000000000.log");
+        assert_eq!(records(&first), [(0, 1), (1, 2)]);
+        assert_eq!(records(&dir.join("00000000000000000002.log")), [(2, 3)]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn run_syncs_and_returns_when_sequencer_stops() {
+        let dir = temp_dir("log-run");
+        let buffer = Buffer::<u32>::builder().capacity(16).build().unwrap();
+        let mut handle = buffer.start();
+        let producer = buffer.producer();
+        let mut writer = buffer
+            .consumer()
+            .log_writer(&dir)
+            .unwrap()
+            .sync_interval(Duration::from_millis(10));
+
+        let thread = std::thread::spawn(move || writer.run().unwrap());
+        for i in 0..3 {
+            producer.push(i).unwrap();
+        }
+        buffer.flush();
+        handle.stop();
+        handle.join().unwrap();
+
+        assert_eq!(thread.join().unwrap(), 3);
+        let records = records(&dir.join("00000000000000000000.log"));
+        assert_eq!(records, [(0, 0), (1, 1), (2, 2)]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn recover_reads_every_segment_and_truncates_a_torn_tail() {
+        let dir = temp_dir("log-recover");
+        let buffer = Buffer::<u32>::builder().capacity(16).build().unwrap();
+        let producer = buffer.producer();
+        for i in 0..5 {
+            producer.push(100 + i).unwrap();
+        }
+        buffer.flush();
+        let mut writer = buffer
+            .consumer()
+            .log_writer(&dir)
+            .unwrap()
+            .segment_bytes(12 + 2 * 29);
+        writer.drain().unwrap();
+        writer.sync().unwrap();
+
+        // Cut the last record short, as a crash mid-write would
+        let last = dir.join("00000000000000000004.log");
+        let file = OpenOptions::new().write(true).open(&last).unwrap();
+        file.set_len(12 + 20).unwrap();
+
+        let recovery = LogReader::new(&dir)
+            .truncate(true)
+            .recover::<u32>()
+            .unwrap();
+        let payloads: Vec<_> = recovery
+            .events
+            .iter()
+            .map(|e| (e.sequence, e.payload))
+            .collect();
+        assert_eq!(payloads, [(0, 100), (1, 101), (2, 102), (3, 103)]);
+        assert_eq!(recovery.last_sequence(), Some(3));
+        assert_eq!(
+            recovery.torn,
+            [TornTail {
+                path: last.clone(),
+                offset: 12,
+                len: 20
+            }]
+        );
+        assert_eq!(fs::metadata(&last).unwrap().len(), 12);
+        assert_eq!(fs::metadata(index_path(&last)).unwrap().len(), 12);
+
+        let again = LogReader::new(&dir).recover::<u32>().unwrap();
+        assert_eq!(again.events, recovery.events);
+        assert!(again.torn.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn damage_with_intact_records_after_it_is_corruption() {
+        let dir = temp_dir("log-crc");
+        let buffer = Buffer::<u32>::builder().capacity(16).build().unwrap();
+        let producer = buffer.producer();
+        for i in 0..7 {
+            producer.push(i).unwrap();
+        }
+        buffer.flush();
+        let mut writer = buffer
+            .consumer()
+            .log_writer(&dir)
+            .unwrap()
+            .segment_bytes(12 + 3 * 29);
+        writer.drain().unwrap();
+        writer.sync().unwrap();
+
+        // Flip a payload bit in the second record
+        let first = dir.join("00000000000000000000.log");
+        let mut bytes = fs::read(&first).unwrap();
+        bytes[12 + 29 + 4 + 17] ^= 1;
+        fs::write(&first, &bytes).unwrap();
+        // Cut the last record out of the closed second segment, trailer intact
+        let second = dir.join("00000000000000000003.log");
+        let mut cut = fs::read(&second).unwrap();
+        cut.drain(12 + 2 * 29..12 + 3 * 29);
+        fs::write(&second, &cut).unwrap();
+
+        let err = LogReader::new(&dir).truncate(true).recover::<u32>().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let corruption = err.get_ref().unwrap().downcast_ref::<Corruption>().unwrap();
+        let damaged = Corruption {
+            path: first.clone(),
+            offset: 12 + 29,
+            len: 29,
+            sequences: 1..2,
+        };
+        assert_eq!(*corruption, damaged);
+        assert_eq!(fs::read(&first).unwrap(), bytes);
+
+        let missing = Corruption {
+            path: second.clone(),
+            offset: 12,
+            len: 2 * 29,
+            sequences: 3..5,
+        };
+        assert_eq!(LogReader::new(&dir).verify().unwrap(), [damaged, missing]);
+        let mut replay = LogReader::new(&dir).replay::<u32, ()>().unwrap();
+        assert_eq!(replay.next().unwrap().sequence, 0);
+        assert!(matches!(replay.next(), Err(ConsumerError::Log(_))));
+        assert_eq!(replay.next().unwrap().sequence, 2);
+
+        // Damage running into a trailer loses the rest of its segment
+        bytes.truncate(12 + 29 + 10);
+        bytes.extend_from_slice(&fs::read(&first).unwrap()[12 + 3 * 29..]);
+        fs::write(&first, &bytes).unwrap();
+        assert_eq!(LogReader::new(&dir).verify().unwrap()[0].sequences, 1..3);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_restarted_writer_cuts_a_torn_tail_before_appending() {
+        let dir = temp_dir("log-reopen");
+        let buffer = Buffer::<u32>::builder().capacity(16).build().unwrap();
+        let producer = buffer.producer();
+        for i in 0..2 {
+            producer.push(i).unwrap();
+        }
+        buffer.flush();
+        let mut writer = buffer.consumer().log_writer(&dir).unwrap().segment_bytes(12 + 3 * 29);
+        writer.drain().unwrap();
+        writer.sync().unwrap();
+        let path = dir.join("00000000000000000000.log");
+        let file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(12 + 29 + 10).unwrap();
+
+        // Rewrites 0 and 1 after the intact 0, closing the segment as 2 rolls over
+        producer.push(2).unwrap();
+        producer.push(3).unwrap();
+        buffer.flush();
+        let mut consumer = buffer.consumer();
+        consumer.seek(0).unwrap();
+        let mut writer = consumer.log_writer(&dir).unwrap().segment_bytes(12 + 3 * 29);
+        writer.drain().unwrap();
+        writer.sync().unwrap();
+
+        assert_eq!(records(&path), [(0, 0), (0, 0), (1, 1)]);
+        assert!(LogReader::new(&dir).verify().unwrap().is_empty());
+        let recovery = LogReader::new(&dir).recover::<u32>().unwrap();
+        assert_eq!(recovery.last_sequence(), Some(3));
+        assert!(recovery.torn.is_empty());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn replay_into_a_fresh_buffer_continues_the_sequence() {
+        let dir = temp_dir("log-replay");
+        let buffer = Buffer::<u32>::builder().capacity(16).build().unwrap();
+        let first = buffer.producer();
+        let second = buffer.producer();
+        first.push(1).unwrap();
+        second.push(2).unwrap();
+        first.push(3).unwrap();
+        buffer.flush();
+        let mut writer = buffer.consumer().log_writer(&dir).unwrap();
+        writer.drain().unwrap();
+        writer.sync().unwrap();
+        let original: Vec<_> = buffer
+            .snapshot()
+            .iter()
+            .map(|e| (e.timestamp, e.producer_id))
+            .collect();
+
+        let recovery = LogReader::new(&dir).recover::<u32>().unwrap();
+        let restored = Buffer::<u32>::builder().capacity(16).build().unwrap();
+        let mut consumer = restored.consumer();
+        assert_eq!(recovery.replay_into(&restored).unwrap(), 3);
+        restored.producer().push(4).unwrap();
+        restored.flush();
+
+        let mut events = Vec::new();
+        while let Some(event) = consumer.try_next().unwrap() {
+            events.push(event);
+        }
+        let sequences: Vec<_> = events.iter().map(|e| (e.sequence, e.payload)).collect();
+        assert_eq!(sequences, [(0, 1), (1, 2), (2, 3), (3, 4)]);
+        let stamps: Vec<_> = events[..3]
+            .iter()
+            .map(|e| (e.timestamp, e.producer_id))
+            .collect();
+        assert_eq!(stamps, original);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn replay_into_a_single_producer_buffer_needs_a_single_producer_log() {
+        let dir = temp_dir("log-replay-single");
+        let buffer = Buffer::<u32>::builder().capacity(16).build().unwrap();
+        buffer.producer().push(1).unwrap();
+        buffer.producer().push(2).unwrap();
+        buffer.flush();
+        let mut writer = buffer.consumer().log_writer(&dir).unwrap();
+        writer.drain().unwrap();
+        writer.sync().unwrap();
+
+        let mut recovery = LogReader::new(&dir).recover::<u32>().unwrap();
+        let restored = Buffer::<u32>::builder()
+            .capacity(16)
+            .single_producer()
+            .build()
+            .unwrap();
+        let mut consumer = restored.consumer();
+        assert_eq!(recovery.replay_into(&restored), Err(PushError::TooManyProducers));
+        assert!(consumer.try_next().unwrap().is_none());
+
+        recovery.events.truncate(1);
+        assert_eq!(recovery.replay_into(&restored).unwrap(), 1);
+        assert_eq!(consumer.try_next().unwrap().unwrap().payload, 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Log events with the given timestamps, 5 records to a segment and an index entry
+    /// every other record
+    fn write_stamped(dir: &Path, timestamps: &[u64]) {
+        let buffer = Buffer::<u32>::builder().capacity(64).build().unwrap();
+        let producer = buffer.producer();
+        for (i, &timestamp) in timestamps.iter().enumerate() {
+            producer.push_replayed(i as u32, (), timestamp).unwrap();
+        }
+        buffer.flush();
+        let mut writer = buffer
+            .consumer()
+            .log_writer(dir)
+            .unwrap()
+            .segment_bytes(12 + 5 * 29)
+            .index_interval(2 * 29);
+        writer.drain().unwrap();
+        writer.sync().unwrap();
+    }
+
+    #[test]
+    fn seek_to_timestamp_finds_the_first_event_stamped_at_or_after_it() {
+        let dir = temp_dir("log-seek");
+        // Mostly rising, with stragglers stamped out of order
+        let timestamps = [10, 20, 15, 30, 40, 35, 50, 60, 45, 70, 80, 90, 100, 95, 110, 120];
+        write_stamped(&dir, &timestamps);
+        let index = read_index(&dir.join("00000000000000000005.log"), u64::MAX).unwrap();
+        let entries: Vec<_> = index.iter().map(|e| (e.max_before, e.sequence, e.offset)).collect();
+        assert_eq!(entries, [(40, 5, 12), (50, 7, 12 + 2 * 29), (60, 9, 12 + 4 * 29)]);
+
+        let check = |reader: &LogReader| {
+            for timestamp in 0..=125 {
+                let expected = timestamps.iter().position(|&t| t >= timestamp);
+                let found = reader.seek_to_timestamp(timestamp).unwrap();
+                assert_eq!(found.as_ref().map(|p| p.sequence), expected.map(|i| i as u64));
+                if let Some(found) = found {
+                    let bytes = fs::read(&found.path).unwrap();
+                    let at = found.offset as usize + 4;
+                    let sequence = u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+                    assert_eq!(sequence, found.sequence);
+                    assert_eq!(found.timestamp, timestamps[sequence as usize]);
+                }
+            }
+        };
+        let reader = LogReader::new(&dir);
+        check(&reader);
+
+        // Without the indexes it falls back to a scan
+        for first in [0, 5, 10, 15] {
+            fs::remove_file(dir.join(format!("{:020}.idx", first))).unwrap();
+        }
+        check(&reader);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_restarted_writer_carries_the_largest_timestamp_on() {
+        let dir = temp_dir("log-index-restart");
+        write_stamped(&dir, &[10, 50, 20]);
+        let buffer = Buffer::<u32>::builder().capacity(16).build().unwrap();
+        let producer = buffer.producer();
+        for _ in 0..3 {
+            producer.push_replayed(0, (), 0).unwrap();
+        }
+        producer.push_replayed(7, (), 30).unwrap();
+        buffer.flush();
+        // Only the last event is new
+        let mut consumer = buffer.consumer();
+        consumer.seek(3).unwrap();
+        let mut writer = consumer.log_writer(&dir).unwrap();
+        writer.drain().unwrap();
+        writer.sync().unwrap();
+
+        let index = read_index(&dir.join("00000000000000000003.log"), u64::MAX).unwrap();
+        assert_eq!((index[0].max_before, index[0].sequence), (50, 3));
+        assert_eq!(LogReader::new(&dir).seek_to_timestamp(25).unwrap().unwrap().sequence, 1);
+        assert_eq!(LogReader::new(&dir).seek_to_timestamp(51).unwrap(), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,236 @@
+use std::fmt;
+use std::sync::Mutex;
+
+/// A published event waiting for a sequence number
+#[derive(Debug)]
+pub struct Candidate<'a, T> {
+    pub timestamp: u64,
+    pub producer_id: u8,
+    pub payload: &'a T,
+}
+
+impl<T> Clone for Candidate<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Candidate<'_, T> {}
+
+/// Decides which published event gets the next sequence number.
+///
+/// The sequencer offers up to `window()` consecutive published events, oldest
+/// claim first, and sequences whichever one `select` picks. The rest are offered
+/// again, still oldest first, for the following sequence number.
+pub trait SequencerPolicy<T>: Send {
+    /// How many published events `select` chooses between. Read once when the buffer is built.
+    fn window(&self) -> usize {
+        1
+    }
+
+    /// Index into `candidates` of the event to sequence next. `candidates` is never empty.
+    fn select(&mut self, candidates: &[Candidate<'_, T>]) -> usize;
+}
+
+/// The default policy: events are sequenced in the order their slots were claimed
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SlotOrder;
+
+impl<T> SequencerPolicy<T> for SlotOrder {
+    fn select(&mut self, _candidates: &[Candidate<'_, T>]) -> usize {
+        0
+    }
+}
+
+/// Sequences events by lane, with lane chosen per payload by `lane_of`.
+///
+/// `strict` always takes the lowest-numbered lane waiting in the window, so control
+/// events jump ahead of bulk data. `weighted` gives lane `i` up to `weights[i]` picks
+/// per round, so every lane with a non-zero weight keeps moving. Within a lane events
+/// keep their claim order. Lanes past the last weight share the last lane's turn.
+pub struct Lanes<F> {
+    lane_of: F,
+    window: usize,
+    /// Picks per round for each lane; `None` for strict priority
+    weights: Option<Vec<u32>>,
+    credits: Vec<u32>,
+    /// Lane whose turn it is
+    current: usize,
+}
+
+impl<F> Lanes<F> {
+    /// Lowest lane first, choosing among `window` published events
+    pub fn strict(window: usize, lane_of: F) -> Self {
+        Self {
+            lane_of,
+            window,
+            weights: None,
+            credits: Vec::new(),
+            current: 0,
+        }
+    }
+
+    /// Weighted round robin between lanes. Zero weights are raised to one.
+    pub fn weighted(window: usize, weights: Vec<u32>, lane_of: F) -> Self {
+        let weights: Vec<u32> = weights.into_iter().map(|weight| weight.max(1)).collect();
+        Self {
+            lane_of,
+            window,
+            credits: weights.clone(),
+            weights: Some(weights).filter(|weights| !weights.is_empty()),
+            current: 0,
+        }
+    }
+}
+
+impl<T, F> SequencerPolicy<T> for Lanes<F>
+where
+    F: FnMut(&T) -> usize + Send,
+{
+    fn window(&self) -> usize {
+        self.window
+    }
+
+    fn select(&mut self, candidates: &[Candidate<'_, T>]) -> usize {
+        let Some(weights) = &self.weights else {
+            return (0..candidates.len())
+                .min_by_key(|&i| (self.lane_of)(candidates[i].payload))
+                .unwrap_or(0);
+        };
+
+        let last = weights.len() - 1;
+        let lanes: Vec<usize> = candidates
+            .iter()
+            .map(|candidate| (self.lane_of)(candidate.payload).min(last))
+            .collect();
+        // A second pass after refilling always finds a lane
+        for _ in 0..2 {
+            for step in 0..weights.len() {
+                let lane = (self.current + step) % weights.len();
+                if self.credits[lane] == 0 {
+                    continue;
+                }
+                if let Some(index) = lanes.iter().position(|&l| l == lane) {
+                    self.credits[lane] -= 1;
+                    // Stay on this lane until its credits run out
+                    self.current = if self.credits[lane] == 0 {
+                        (lane + 1) % weights.len()
+                    } else {
+                        lane
+                    };
+                    return index;
+                }
+            }
+            self.credits.copy_from_slice(weights);
+        }
+        0
+    }
+}
+
+impl<F> fmt::Debug for Lanes<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Lanes")
+            .field("window", &self.window)
+            .field("weights", &self.weights)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The buffer's policy along with its window, which the sequencer checks without locking
+pub(crate) struct PolicyCell<T> {
+    pub(crate) window: usize,
+    pub(crate) policy: Mutex<Box<dyn SequencerPolicy<T>>>,
+}
+
+impl<T> PolicyCell<T> {
+    pub(crate) fn new(policy: Box<dyn SequencerPolicy<T>>) -> Self {
+        Self {
+            window: policy.window().max(1),
+            policy: Mutex::new(policy),
+        }
+    }
+}
+
+impl<T> fmt::Debug for PolicyCell<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PolicyCell")
+            .field("window", &self.window)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::Buffer;
+
+    /// Highest payload first among whatever is waiting
+    struct Priority;
+
+    impl SequencerPolicy<u64> for Priority {
+        fn window(&self) -> usize {
+            4
+        }
+
+        fn select(&mut self, candidates: &[Candidate<'_, u64>]) -> usize {
+            (0..candidates.len())
+                .max_by_key(|&i| *candidates[i].payload)
+                .unwrap()
+        }
+    }
+
+    #[test]
+    fn policy_reorders_within_window() {
+        let buffer = Buffer::<u64>::builder()
+            .capacity(16)
+            .sequencer_policy(Priority)
+            .build()
+            .unwrap();
+        let producer = buffer.producer();
+        for payload in [1, 4, 2, 3, 0] {
+            producer.push(payload).unwrap();
+        }
+        assert_eq!(buffer.sequence_available(), 5);
+
+        let mut consumer = buffer.consumer();
+        let payloads: Vec<u64> = consumer.iter().map(|event| event.payload).collect();
+        // The first four compete; 0 only enters the window once 4 has been sequenced
+        assert_eq!(payloads, vec![4, 3, 2, 1, 0]);
+    }
+
+    fn sequence(policy: impl SequencerPolicy<u64> + 'static, payloads: &[u64]) -> Vec<u64> {
+        let buffer = Buffer::<u64>::builder()
+            .capacity(16)
+            .sequencer_policy(policy)
+            .build()
+            .unwrap();
+        let producer = buffer.producer();
+        for &payload in payloads {
+            producer.push(payload).unwrap();
+        }
+        while buffer.sequence_available() > 0 {}
+        let mut consumer = buffer.consumer();
+        consumer.iter().map(|event| event.payload).collect()
+    }
+
+    #[test]
+    fn strict_lanes_put_control_events_first() {
+        // Payloads >= 100 are bulk data in lane 1
+        let lanes = Lanes::strict(8, |payload: &u64| (*payload >= 100) as usize);
+        let payloads = sequence(lanes, &[100, 101, 1, 102, 2]);
+        assert_eq!(payloads, vec![1, 2, 100, 101, 102]);
+    }
+
+    #[test]
+    fn weighted_lanes_interleave_without_starving_bulk() {
+        let lanes = Lanes::weighted(8, vec![2, 1], |payload: &u64| (*payload >= 100) as usize);
+        let payloads = sequence(lanes, &[100, 101, 102, 103, 1, 2, 3, 4]);
+        assert_eq!(payloads, vec![1, 2, 100, 3, 4, 101, 102, 103]);
+    }
+
+    #[test]
+    fn slot_order_is_the_default() {
+        let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        assert_eq!(buffer.policy.window, 1);
+    }
+}
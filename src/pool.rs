@@ -0,0 +1,158 @@
+use crate::buffer::Buffer;
+use crate::error::BuildError;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+type Build<T, M> = Box<dyn Fn() -> Result<Arc<Buffer<T, M>>, BuildError> + Send + Sync>;
+
+/// Keeps finished buffers for reuse, so short-lived streams (one per backtest, one per
+/// connection) don't allocate a fresh ring each time.
+///
+/// A released buffer is reset to its just-built state: every slot free, sequence
+/// numbers starting again at 0, no consumers registered and not closed. Its settings
+/// are the ones `build` gave it.
+pub struct BufferPool<T, M = ()> {
+    build: Build<T, M>,
+    idle: Mutex<Vec<Arc<Buffer<T, M>>>>,
+    max_idle: usize,
+}
+
+impl<T, M> BufferPool<T, M>
+where
+    T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
+{
+    /// A pool that builds buffers with `build` when it has none to hand out and keeps
+    /// at most `max_idle` released ones
+    pub fn new<F>(max_idle: usize, build: F) -> Self
+    where
+        F: Fn() -> Result<Arc<Buffer<T, M>>, BuildError> + Send + Sync + 'static,
+    {
+        Self {
+            build: Box::new(build),
+            idle: Mutex::new(Vec::new()),
+            max_idle,
+        }
+    }
+
+    /// A reset buffer from the pool, or a newly built one if the pool is empty
+    pub fn acquire(&self) -> Result<Arc<Buffer<T, M>>, BuildError> {
+        let reused = self.idle.lock().unwrap_or_else(|e| e.into_inner()).pop();
+        match reused {
+            Some(buffer) => Ok(buffer),
+            None => (self.build)(),
+        }
+    }
+
+    /// Reset `buffer` and keep it for the next `acquire`, returning whether it was kept.
+    ///
+    /// It is dropped instead if the pool is full, if anything else still holds it
+    /// (a producer, consumer or running sequencer), or if it has pushed events that were
+    /// never sequenced. Join the sequencer and drop every handle, `SequencerHandle`
+    /// included, before releasing.
+    pub fn release(&self, mut buffer: Arc<Buffer<T, M>>) -> bool {
+        let Some(inner) = Arc::get_mut(&mut buffer) else {
+            return false;
+        };
+        if inner.pending() > 0 || inner.overflowed() > 0 {
+            return false;
+        }
+        let mut idle = self.idle.lock().unwrap_or_else(|e| e.into_inner());
+        if idle.len() >= self.max_idle {
+            return false;
+        }
+        inner.reset();
+        idle.push(buffer);
+        true
+    }
+
+    /// Buffers waiting to be handed out again
+    pub fn idle(&self) -> usize {
+        self.idle.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+}
+
+impl<T, M> fmt::Debug for BufferPool<T, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BufferPool")
+            .field("idle", &self.idle.lock().unwrap_or_else(|e| e.into_inner()).len())
+            .field("max_idle", &self.max_idle)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wait::WaitStrategy;
+
+    fn pool() -> BufferPool<u64> {
+        BufferPool::new(1, || {
+            Buffer::builder()
+                .capacity(4)
+                .sequencer_wait_strategy(WaitStrategy::Blocking)
+                .build()
+        })
+    }
+
+    #[test]
+    fn released_buffers_come_back_reset() {
+        let pool = pool();
+        let buffer = pool.acquire().unwrap();
+        let ring = buffer.slots.as_ptr();
+        let mut handle = buffer.start();
+        {
+            let producer = buffer.producer();
+            let mut consumer = buffer.consumer();
+            for i in 0..6 {
+                producer.push(i).unwrap();
+                assert_eq!(consumer.next().unwrap().payload, i);
+            }
+        }
+        buffer.close();
+        handle.join().unwrap();
+        drop(handle);
+        assert!(pool.release(buffer));
+        assert_eq!(pool.idle(), 1);
+
+        let buffer = pool.acquire().unwrap();
+        assert_eq!(buffer.slots.as_ptr(), ring);
+        assert!(!buffer.is_closed());
+        let producer = buffer.producer();
+        let mut consumer = buffer.consumer();
+        producer.push(7).unwrap();
+        buffer.flush();
+        let event = consumer.try_next().unwrap().unwrap();
+        assert_eq!((event.sequence, event.payload, event.producer_id), (0, 7, 0));
+    }
+
+    #[test]
+    fn buffers_still_in_use_are_not_pooled() {
+        let pool = pool();
+        let buffer = pool.acquire().unwrap();
+        let consumer = buffer.consumer();
+        assert!(!pool.release(buffer));
+        drop(consumer);
+
+        let buffer = pool.acquire().unwrap();
+        buffer.producer().push(1).unwrap();
+        assert!(!pool.release(buffer), "unsequenced event");
+        assert_eq!(pool.idle(), 0);
+    }
+
+    #[test]
+    fn pools_buffers_with_metadata() {
+        let pool = BufferPool::new(1, || Buffer::<u64, u32>::builder().capacity(4).build());
+        let buffer = pool.acquire().unwrap();
+        buffer.producer().push_with_metadata(1, 10).unwrap();
+        buffer.flush();
+        assert_eq!(buffer.consumer().try_next().unwrap().unwrap().metadata, 10);
+        assert!(pool.release(buffer));
+
+        let buffer = pool.acquire().unwrap();
+        buffer.producer().push_with_metadata(2, 20).unwrap();
+        buffer.flush();
+        let event = buffer.consumer().try_next().unwrap().unwrap();
+        assert_eq!((event.sequence, event.payload, event.metadata), (0, 2, 20));
+    }
+}
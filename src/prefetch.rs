@@ -0,0 +1,29 @@
+/// Slots ahead of the current one to request, far enough for the load to land
+/// before the scan gets there without evicting lines it still needs
+pub(crate) const DISTANCE: usize = 4;
+
+/// Hint that the cache line at `ptr` is about to be read. Compiles to nothing
+/// without the `prefetch` feature, or on targets other than x86_64 and aarch64.
+#[inline(always)]
+pub(crate) fn prefetch<T>(ptr: *const T) {
+    #[cfg(all(feature = "prefetch", target_arch = "x86_64"))]
+    // SAFETY: A prefetch never faults, whatever the address
+    unsafe {
+        use core::arch::x86_64::{_mm_prefetch, _MM_HINT_T0};
+        _mm_prefetch::<_MM_HINT_T0>(ptr.cast());
+    }
+    #[cfg(all(feature = "prefetch", target_arch = "aarch64"))]
+    // SAFETY: As above
+    unsafe {
+        core::arch::asm!(
+            "prfm pldl1keep, [{}]",
+            in(reg) ptr,
+            options(nostack, readonly, preserves_flags)
+        );
+    }
+    #[cfg(not(all(
+        feature = "prefetch",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    )))]
+    let _ = ptr;
+}
@@ -1,87 +1,475 @@
+use crate::backpressure::{Backpressure, BackpressureMode};
 use crate::buffer::Buffer;
 use crate::error::PushError;
-use crate::slot::SlotState;
-use std::sync::atomic::Ordering;
+use crate::segment::Entry;
+use crate::slot::{Slot, SlotState, SlotWriteGuard, SKIPPED};
+use crate::sync::atomic::{fence, AtomicU64, AtomicUsize, Ordering};
+use crate::sync::hint;
+use crate::wait::WaitStrategy;
+use std::cell::Cell;
+use std::marker::PhantomData;
+use std::mem::{ManuallyDrop, MaybeUninit};
 use std::sync::Arc;
 
-pub struct Producer<T> {
-    buffer: Arc<Buffer<T>>,
+/// What `Producer::push` does when the next slot is still held by unsequenced or unread events
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnFull {
+    /// Return `PushError::BufferFull` without claiming a slot
+    Fail,
+    /// Spin, then yield, until the slot is reusable
+    #[default]
+    Block,
+    /// Reuse the oldest sequenced slot even if registered consumers have not read it;
+    /// they see a lag. Still waits for slots that are not sequenced yet.
+    OverwriteOldest,
+    /// Queue the event in overflow segments of `capacity` events each, linked in as
+    /// needed, and move it into the ring once a slot frees up. Never waits or fails,
+    /// but memory grows with the backlog: a push that fills a segment allocates the
+    /// next one while holding the overflow lock, unless an emptied one is spare, so
+    /// that push, and others spilling meanwhile, take an allocator call's latency.
+    /// Acts like `Block` with `single_producer`.
+    Grow,
+}
+
+/// Pushes events into a buffer. `Send` but not `Sync`: each thread pushes through a
+/// producer of its own, so a `single_producer` buffer's one producer can sequence its
+/// events as it claims them without racing itself.
+pub struct Producer<T, M = ()> {
+    buffer: Arc<Buffer<T, M>>,
     id: u8,
+    cache: ClaimCache,
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+/// A producer's view of the ring as of its last claim, so a burst of pushes does not
+/// load the shared `head` and `tail` for every event. Only ever a hint: a stale head
+/// fails its claim and is reloaded, and sequences below any tail seen were read.
+#[derive(Debug)]
+pub(crate) struct ClaimCache {
+    /// One past this producer's last claim, where its next one most likely lands.
+    /// Unused with `sequence-32`, whose 32-bit generations could mistake a position
+    /// stale by 2^32 laps for a current one.
+    head: AtomicUsize,
+    /// The consumers' tail when last loaded
+    tail: AtomicU64,
+    /// `CursorRegistry::epoch` when `tail` was loaded; the tail may have been lowered
+    /// since if it moved on
+    epoch: AtomicU64,
+}
+
+impl ClaimCache {
+    fn new() -> Self {
+        Self {
+            head: AtomicUsize::new(0),
+            tail: AtomicU64::new(0),
+            epoch: AtomicU64::new(0),
+        }
+    }
 }
 
-impl<T> Producer<T>
+impl<T, M> Producer<T, M>
 where
     T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
 {
-    pub(crate) fn new(buffer: Arc<Buffer<T>>, id: u8) -> Self {
-        Self { buffer, id }
+    pub(crate) fn new(buffer: Arc<Buffer<T, M>>, id: u8) -> Self {
+        Self {
+            buffer,
+            id,
+            cache: ClaimCache::new(),
+            _not_sync: PhantomData,
+        }
+    }
+
+    pub fn push(&self, event: T) -> Result<(), PushError>
+    where
+        M: Default,
+    {
+        self.push_with_metadata(event, M::default())
     }
 
-    pub fn push(&self, event: T) -> Result<(), PushError> {
+    /// Push `event` with `metadata` stored beside it as the event's header
+    pub fn push_with_metadata(&self, event: T, metadata: M) -> Result<(), PushError> {
+        self.push_with_metadata_and_flags(event, metadata, 0)
+    }
+
+    /// Push `event` tagged with `flags`, which consumers see as `Event::flags`. The
+    /// bits mean whatever the application says; the buffer never reads them.
+    pub fn push_with_flags(&self, event: T, flags: u32) -> Result<(), PushError>
+    where
+        M: Default,
+    {
+        self.push_with_metadata_and_flags(event, M::default(), flags)
+    }
+
+    /// Push `event` with `metadata` as its header and tagged with `flags`, as
+    /// `push_with_metadata` and `push_with_flags` together
+    pub fn push_with_metadata_and_flags(
+        &self,
+        event: T,
+        metadata: M,
+        flags: u32,
+    ) -> Result<(), PushError> {
+        self.push_stamped(event, metadata, flags, None)
+    }
+
+    /// Push an event read back from a log, keeping the timestamp it was first pushed with
+    pub(crate) fn push_replayed(
+        &self,
+        event: T,
+        metadata: M,
+        timestamp: u64,
+    ) -> Result<(), PushError> {
+        self.push_stamped(event, metadata, 0, Some(timestamp))
+    }
+
+    /// Push with `timestamp`, or a clock reading taken as the event is published if `None`
+    fn push_stamped(
+        &self,
+        event: T,
+        metadata: M,
+        flags: u32,
+        timestamp: Option<u64>,
+    ) -> Result<(), PushError> {
+        if self.buffer.closed.load(Ordering::Relaxed) {
+            return Err(PushError::Shutdown);
+        }
+        if let Some(backpressure) = &self.buffer.backpressure {
+            self.hold_back(backpressure)?;
+        }
+
+        // Once anything has spilled, later events queue behind it to keep push order
+        if self.buffer.overflowed() > 0 {
+            return self.spill(event, metadata, flags, timestamp);
+        }
+
         // Claim a slot
-        let slot_ref = self.claim()?;
+        let held = match self.claim() {
+            Err(PushError::BufferFull) if self.buffer.overflow.is_some() => {
+                return self.spill(event, metadata, flags, timestamp);
+            }
+            claimed => claimed?,
+        };
 
-        // Write payload, timestamp, and producer_id
         // SAFETY: We own exclusive access via Claimed state
-        unsafe {
-            (*slot_ref.slot.payload.get()).write(event);
-            *slot_ref.slot.timestamp.get() = timestamp();
-            *slot_ref.slot.producer_id.get() = self.id;
+        let mut contents = unsafe { SlotWriteGuard::new(held.0.slot) };
+        contents.write_payload(event);
+        self.publish(held, contents, metadata, flags, timestamp)
+    }
+
+    /// Claim a slot and let `write` update its payload in place, then publish it.
+    /// For wrappers that keep something in the payload from one lap to the next, like
+    /// `ArenaBuffer`'s arena index; such buffers must not use `OnFull::Grow`.
+    ///
+    /// SAFETY: the payload must be initialized once `write` returns, whether `write`
+    /// or a previous lap initialized it.
+    pub(crate) unsafe fn push_in_place(
+        &self,
+        metadata: M,
+        write: impl FnOnce(&mut MaybeUninit<T>),
+    ) -> Result<(), PushError> {
+        if self.buffer.closed.load(Ordering::Relaxed) {
+            return Err(PushError::Shutdown);
+        }
+        if let Some(backpressure) = &self.buffer.backpressure {
+            self.hold_back(backpressure)?;
+        }
+        let held = self.claim()?;
+        // SAFETY: We own exclusive access via Claimed state
+        let mut contents = unsafe { SlotWriteGuard::new(held.0.slot) };
+        write(contents.payload_mut());
+        // SAFETY: Our caller promised `write` leaves the payload initialized
+        unsafe { contents.assume_written() };
+        self.publish(held, contents, metadata, 0, None)
+    }
+
+    /// Fill in the rest of a claimed slot whose payload is written, and publish it
+    fn publish(
+        &self,
+        held: Held<'_, T, M>,
+        contents: SlotWriteGuard<'_, T, M>,
+        metadata: M,
+        flags: u32,
+        timestamp: Option<u64>,
+    ) -> Result<(), PushError> {
+        let timestamp = timestamp.unwrap_or_else(|| self.timestamp());
+        contents.finish(metadata, timestamp, self.id, flags, self.buffer.checksum);
+
+        // A close that raced this claim may have finished draining already, so the
+        // event is published as a tombstone that readers skip. SeqCst pairs with the
+        // claim and with the fence before `drain`: either the drain sees the claim or
+        // this load sees the close.
+        let result = if self.buffer.closed.load(Ordering::SeqCst) {
+            held.0.slot.flags.fetch_or(SKIPPED, Ordering::Relaxed);
+            Err(PushError::Shutdown)
+        } else {
+            Ok(())
+        };
+
+        // A lone producer claims in sequence order, so it can sequence the slot itself
+        if self.buffer.single_producer {
+            self.buffer.sequence_in_place(held.published().index);
+            return result;
         }
 
-        // Publish (transition Claimed → Published)
-        slot_ref
+        // Publish (transition Claimed → Published), then queue it for the sequencer.
+        // This only fails if the sequencer timed the claim out and skipped the slot,
+        // and then `held` lets go of it.
+        held.0
             .slot
             .state
-            .store(SlotState::Published as u8, Ordering::Release);
+            .compare_exchange(
+                SlotState::Claimed as u8,
+                SlotState::Published as u8,
+                Ordering::Release,
+                Ordering::Relaxed,
+            )
+            .map_err(|_| PushError::ClaimExpired)?;
+        self.buffer.publish_queue.push(held.published().index);
+
+        // Only a parked sequencer needs waking; skip the fence otherwise
+        if self.buffer.sequencer_wait_strategy == WaitStrategy::Blocking {
+            self.buffer.publish_notifier.notify_all();
+        }
+
+        result
+    }
+
+    /// While the sequencer has raised backpressure, fail or wait until consumers catch up.
+    /// The lag is re-checked here because consumers may catch up while the sequencer is idle.
+    fn hold_back(&self, backpressure: &Backpressure) -> Result<(), PushError> {
+        while backpressure.engaged() && backpressure.update(self.buffer.lag()) {
+            match backpressure.mode {
+                BackpressureMode::FailFast => return Err(PushError::Backpressure),
+                BackpressureMode::Wait => std::thread::yield_now(),
+            }
+        }
+        Ok(())
+    }
 
+    /// Queue the event behind the ring; the sequencer moves it in once a slot frees up
+    fn spill(
+        &self,
+        event: T,
+        metadata: M,
+        flags: u32,
+        timestamp: Option<u64>,
+    ) -> Result<(), PushError> {
+        let overflow = self.buffer.overflow.as_ref().expect("spill needs OnFull::Grow");
+        overflow.push(Entry {
+            payload: event,
+            metadata,
+            timestamp: timestamp.unwrap_or_else(|| self.timestamp()),
+            producer_id: self.id,
+            flags,
+        });
+        if self.buffer.sequencer_wait_strategy == WaitStrategy::Blocking {
+            self.buffer.publish_notifier.notify_all();
+        }
         Ok(())
     }
 
-    fn claim(&self) -> Result<SlotRef<'_, T>, PushError> {
+    /// The clock reading for a new event, or 0 if the buffer does not capture timestamps
+    #[inline(always)]
+    fn timestamp(&self) -> u64 {
+        if self.buffer.timestamps { timestamp() } else { 0 }
+    }
+
+    fn claim(&self) -> Result<Held<'_, T, M>, PushError> {
         let mut attempts = 0;
         const MAX_SPIN: usize = 10000;
 
         loop {
-            let pos = self.buffer.head.load(Ordering::Acquire);
-            let slot_idx = pos & self.buffer.mask;
-            let slot = &self.buffer.slots[slot_idx];
+            match try_claim(&self.buffer, Some(&self.cache)) {
+                Claim::Claimed(slot_ref) => return Ok(Held(slot_ref)),
+                // Lost race, retry
+                Claim::Contended => hint::spin_loop(),
+                Claim::Full if matches!(self.buffer.on_full, OnFull::Fail | OnFull::Grow) => {
+                    return Err(PushError::BufferFull);
+                }
+                Claim::Full => {
+                    // Slot not free - backpressure
+                    attempts += 1;
+                    if attempts > MAX_SPIN {
+                        std::thread::yield_now();
+                        attempts = 0;
+                    }
+                    hint::spin_loop();
+                }
+            }
+        }
+    }
+}
+
+pub(crate) struct SlotRef<'a, T, M> {
+    pub(crate) slot: &'a Slot<T, M>,
+    pub(crate) index: usize,
+}
+
+/// A producer's claim, until it publishes. Dropped otherwise, it lets go of the slot, so
+/// once the sequencer skips the claim the slot can be reused.
+struct Held<'a, T, M>(SlotRef<'a, T, M>);
+
+impl<'a, T, M> Held<'a, T, M> {
+    /// The claim published, so the slot is no longer the producer's to let go of
+    fn published(self) -> SlotRef<'a, T, M> {
+        let held = ManuallyDrop::new(self);
+        SlotRef {
+            slot: held.0.slot,
+            index: held.0.index,
+        }
+    }
+}
+
+impl<T, M> Drop for Held<'_, T, M> {
+    fn drop(&mut self) {
+        self.0.slot.let_go();
+    }
+}
+
+/// Outcome of one attempt to claim the slot at `head`
+pub(crate) enum Claim<'a, T, M> {
+    Claimed(SlotRef<'a, T, M>),
+    /// Another claimer got there first
+    Contended,
+    /// The slot still holds an event that is unsequenced or unread
+    Full,
+}
+
+/// Try once to claim the slot at `head` for writing. With a `cache`, try where it
+/// expects `head` to be instead of loading it, and refresh it if that was wrong.
+pub(crate) fn try_claim<'a, T, M>(
+    buffer: &'a Buffer<T, M>,
+    cache: Option<&ClaimCache>,
+) -> Claim<'a, T, M>
+where
+    T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
+{
+    let pos = match cache {
+        Some(cache) if !cfg!(feature = "sequence-32") => cache.head.load(Ordering::Relaxed),
+        _ => buffer.head.load(Ordering::Acquire),
+    };
+    let slot_idx = pos & buffer.mask;
+    let slot = &buffer.slots[slot_idx];
 
-            let state = slot.state.load(Ordering::Acquire);
+    let state = slot.state.load(Ordering::Acquire);
 
-            if state == SlotState::Free as u8 {
-                // Try to claim
-                match slot.state.compare_exchange_weak(
-                    SlotState::Free as u8,
-                    SlotState::Claimed as u8,
+    // A sequenced slot can be reused once every registered consumer has passed it, and
+    // the producer of a skipped claim is done with it. It must hold the previous lap:
+    // with a stale `pos` it may already hold this one, and claiming that again would
+    // leave the real next position unfilled for good.
+    let reusable = state == SlotState::Free as u8
+        || (state == SlotState::Sequenced as u8
+            && !slot.is_held()
+            && previous_lap(buffer, slot, pos).is_some_and(|sequence| {
+                buffer.on_full == OnFull::OverwriteOldest || recyclable(buffer, cache, sequence)
+            }));
+    if !reusable {
+        // The slot may hold a claim for this very position whose head bump we have not
+        // seen yet. It only holds an older event if the previous lap is unsequenced or unread.
+        let previous = (pos as u64).checked_sub(buffer.capacity as u64);
+        let fresh_claim = state != SlotState::Sequenced as u8
+            && previous.is_none_or(|previous| previous < buffer.next_seq.load(Ordering::Acquire));
+        let head = buffer.head.load(Ordering::Acquire);
+        if let Some(cache) = cache {
+            cache.head.store(head, Ordering::Relaxed);
+        }
+        if fresh_claim || head != pos {
+            return Claim::Contended;
+        }
+        return Claim::Full;
+    }
+
+    match slot.state.compare_exchange_weak(
+        state,
+        SlotState::Claimed as u8,
+        Ordering::SeqCst,
+        Ordering::Acquire,
+    ) {
+        Ok(_) => {
+            // The state alone can have come back round: with a stale `pos`, another
+            // claimer may have filled this position, and the sequencer moved it on to
+            // Sequenced, since the state was read. The claim only owns `pos` if it moves
+            // the generation on from the previous lap's; otherwise put the state back.
+            // Release, so a reader that sees the new generation also sees it is Claimed.
+            let generation = buffer.generation(pos as u64);
+            if slot
+                .generation
+                .compare_exchange(
+                    generation.wrapping_sub(1),
+                    generation,
                     Ordering::AcqRel,
-                    Ordering::Acquire,
-                ) {
-                    Ok(_) => {
-                        // Successfully claimed - advance head
-                        self.buffer.head.fetch_add(1, Ordering::Release);
-                        return Ok(SlotRef { slot });
-                    }
-                    Err(_) => {
-                        // Lost race, retry
-                        std::hint::spin_loop();
-                    }
+                    Ordering::Relaxed,
+                )
+                .is_err()
+            {
+                slot.state.store(state, Ordering::Release);
+                if let Some(cache) = cache {
+                    cache.head.store(buffer.head.load(Ordering::Acquire), Ordering::Relaxed);
                 }
-            } else {
-                // Slot not free - backpressure
-                attempts += 1;
-                if attempts > MAX_SPIN {
-                    std::thread::yield_now();
-                    attempts = 0;
-                }
-                std::hint::spin_loop();
+                return Claim::Contended;
+            }
+            // Keep the event's writes after the new generation, so a reader whose copy
+            // caught any of them also sees the generation; see `Buffer::copy_slot`
+            fence(Ordering::Release);
+            slot.flags.store(0, Ordering::Relaxed);
+            buffer.head.fetch_add(1, Ordering::Release);
+            if let Some(cache) = cache {
+                cache.head.store(pos + 1, Ordering::Relaxed);
+            }
+            Claim::Claimed(SlotRef {
+                slot,
+                index: slot_idx,
+            })
+        }
+        Err(_) => {
+            if let Some(cache) = cache {
+                cache.head.store(buffer.head.load(Ordering::Acquire), Ordering::Relaxed);
             }
+            Claim::Contended
         }
     }
 }
 
-struct SlotRef<'a, T> {
-    slot: &'a crate::slot::Slot<T>,
+/// The sequence one lap before `pos`, if that is what `slot` holds.
+///
+/// A 32-bit slot sequence is ambiguous across wraparound, so with `sequence-32` the
+/// slot's generation tells the lap instead: the claim of the previous lap's event set
+/// it to that lap plus one. Generations wrap too, but only every 2^32 laps.
+fn previous_lap<T, M>(buffer: &Buffer<T, M>, slot: &Slot<T, M>, pos: usize) -> Option<u64>
+where
+    T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
+{
+    let previous = (pos as u64).checked_sub(buffer.capacity as u64)?;
+    #[cfg(feature = "sequence-32")]
+    let held = slot.generation.load(Ordering::Acquire) == buffer.generation(previous);
+    #[cfg(not(feature = "sequence-32"))]
+    let held = slot.sequence.load(Ordering::Acquire) == previous;
+    held.then_some(previous)
+}
+
+/// `Buffer::recyclable`, answered from the cached tail when that is already past `sequence`
+/// and no consumer has lowered the tail since
+fn recyclable<T, M>(buffer: &Buffer<T, M>, cache: Option<&ClaimCache>, sequence: u64) -> bool
+where
+    T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
+{
+    let Some(cache) = cache else {
+        return buffer.recyclable(sequence);
+    };
+    let epoch = buffer.consumers.epoch();
+    if epoch == cache.epoch.load(Ordering::Relaxed) && sequence < cache.tail.load(Ordering::Relaxed)
+    {
+        return true;
+    }
+    let recyclable = buffer.recyclable(sequence);
+    // Loaded after the epoch, so a tail lowered after it was read is caught next time
+    cache.tail.store(buffer.tail.load(Ordering::Acquire), Ordering::Relaxed);
+    cache.epoch.store(epoch, Ordering::Relaxed);
+    recyclable
 }
 
 /// Capture a timestamp using the fastest available method
@@ -114,6 +502,8 @@ fn timestamp() -> u64 {
 mod tests {
     use super::*;
     use crate::buffer::Buffer;
+    use crate::slot::{SlotReadGuard, LET_GO};
+    use std::panic::AssertUnwindSafe;
 
     #[test]
     fn single_producer_can_push() {
@@ -137,6 +527,373 @@ mod tests {
         assert_eq!(state, SlotState::Published as u8);
     }
 
+    #[test]
+    fn panics_mid_publish_let_go_of_their_claims() {
+        let mut buffer = Buffer::<u64>::builder().capacity(4).build().unwrap();
+        // Panics after the event is written, like a checksum of a payload that panics
+        Arc::get_mut(&mut buffer).unwrap().checksum = Some(|_| panic!("checksum"));
+        let producer = Producer::new(buffer.clone(), 0);
+
+        let pushed = std::panic::catch_unwind(AssertUnwindSafe(|| producer.push(7)));
+        assert!(pushed.is_err());
+        let written = std::panic::catch_unwind(AssertUnwindSafe(|| {
+            // SAFETY: The write panics, so nothing publishes the slot
+            unsafe { producer.push_in_place((), |_| panic!("write")) }
+        }));
+        assert!(written.is_err());
+
+        // Both claims are left behind, let go of for a skip to hand on
+        let states: Vec<(u8, u8)> = buffer.slots[..2]
+            .iter()
+            .map(|slot| (slot.state.load(Ordering::Relaxed), slot.flags.load(Ordering::Relaxed)))
+            .collect();
+        assert_eq!(
+            states,
+            vec![
+                (SlotState::Claimed as u8, LET_GO),
+                (SlotState::Claimed as u8, LET_GO)
+            ]
+        );
+    }
+
+    #[test]
+    fn stale_cached_head_is_reloaded() {
+        let buffer = Buffer::<u64>::builder().capacity(8).build().unwrap();
+        let first = Producer::new(buffer.clone(), 0);
+        let second = Producer::new(buffer.clone(), 1);
+
+        // `first` expects to claim position 1 next, but `second` takes 1..4
+        first.push(0).unwrap();
+        for i in 1..4 {
+            second.push(i).unwrap();
+        }
+        first.push(4).unwrap();
+        assert_eq!(buffer.head.load(Ordering::Relaxed), 5);
+        assert_eq!(first.cache.head.load(Ordering::Relaxed), 5);
+
+        buffer.sequence_available();
+        let payloads: Vec<u64> = buffer.snapshot().iter().map(|event| event.payload).collect();
+        assert_eq!(payloads, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn a_seek_back_invalidates_the_cached_tail() {
+        let buffer = Buffer::<u64>::builder()
+            .capacity(4)
+            .on_full(OnFull::Fail)
+            .build()
+            .unwrap();
+        let producer = Producer::new(buffer.clone(), 0);
+        let mut consumer = buffer.consumer();
+        for i in 0..6 {
+            producer.push(i).unwrap();
+            buffer.flush();
+            consumer.try_next().unwrap();
+        }
+        assert_eq!(producer.cache.tail.load(Ordering::Relaxed), 3);
+
+        // Slot 2 is below the cached tail, but the consumer wants event 2 again
+        consumer.seek(2).unwrap();
+        assert_eq!(producer.push(6), Err(PushError::BufferFull));
+        assert_eq!(consumer.try_next().unwrap().unwrap().payload, 2);
+    }
+
+    #[test]
+    fn registered_consumer_gates_slot_reuse() {
+        let buffer = Buffer::<u64>::builder().capacity(4).build().unwrap();
+        let mut handle = buffer.start();
+        let producer = Producer::new(buffer.clone(), 0);
+        let mut consumer = buffer.consumer();
+
+        for i in 0..4 {
+            producer.push(i).unwrap();
+        }
+        while buffer.next_seq.load(Ordering::Acquire) < 4 {
+            std::thread::yield_now();
+        }
+
+        // The ring is full and the consumer hasn't read anything: slot 0 is pinned
+        assert!(!buffer.recyclable(0));
+
+        assert_eq!(consumer.try_next().unwrap().unwrap().payload, 0);
+        assert!(buffer.recyclable(0));
+        assert!(!buffer.recyclable(1));
+
+        // Wrapping around reuses slot 0 only
+        producer.push(4).unwrap();
+        while buffer.next_seq.load(Ordering::Acquire) < 5 {
+            std::thread::yield_now();
+        }
+        let payloads: Vec<u64> = consumer.iter().map(|event| event.payload).collect();
+        assert_eq!(payloads, vec![1, 2, 3, 4]);
+
+        handle.stop();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn single_producer_sequences_in_push() {
+        let buffer = Buffer::<u64>::builder()
+            .capacity(4)
+            .single_producer()
+            .build()
+            .unwrap();
+        let handle = buffer.start();
+        assert!(handle.is_finished(), "no sequencer thread should run");
+        let producer = buffer.producer();
+        let mut consumer = buffer.consumer();
+
+        for i in 0..6 {
+            producer.push(i).unwrap();
+            let event = consumer.try_next().unwrap().unwrap();
+            assert_eq!((event.sequence, event.payload), (i, i));
+        }
+
+        // Stopping the handle still ends blocking reads
+        handle.stop();
+        assert!(consumer.blocking_iter().next().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "already has a producer")]
+    fn single_producer_refuses_a_second_producer() {
+        let buffer = Buffer::<u64>::builder().single_producer().build().unwrap();
+        let _first = buffer.producer();
+        let _second = buffer.producer();
+    }
+
+    #[test]
+    fn on_full_fail_rejects_instead_of_waiting() {
+        let buffer = Buffer::<u64>::builder()
+            .capacity(4)
+            .on_full(OnFull::Fail)
+            .build()
+            .unwrap();
+        let producer = buffer.producer();
+        let mut consumer = buffer.consumer();
+
+        for i in 0..4 {
+            producer.push(i).unwrap();
+        }
+        // Unsequenced events hold the ring
+        assert_eq!(producer.push(4), Err(PushError::BufferFull));
+        buffer.flush();
+        // So does an unread consumer
+        assert_eq!(producer.push(4), Err(PushError::BufferFull));
+
+        consumer.try_next().unwrap();
+        producer.push(4).unwrap();
+    }
+
+    #[test]
+    fn on_full_overwrite_oldest_laps_slow_consumers() {
+        let buffer = Buffer::<u64>::builder()
+            .capacity(4)
+            .on_full(OnFull::OverwriteOldest)
+            .build()
+            .unwrap();
+        let producer = buffer.producer();
+        let mut consumer = buffer.consumer();
+
+        for i in 0..6 {
+            producer.push(i).unwrap();
+            buffer.flush();
+        }
+        assert_eq!(
+            consumer.try_next().unwrap_err(),
+            crate::error::ConsumerError::Lagged { skipped: 2 }
+        );
+        let payloads: Vec<u64> = consumer.iter().map(|event| event.payload).collect();
+        assert_eq!(payloads, vec![2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn on_full_grow_spills_and_keeps_push_order() {
+        let buffer = Buffer::<u64>::builder()
+            .capacity(4)
+            .on_full(OnFull::Grow)
+            .build()
+            .unwrap();
+        let producer = buffer.producer();
+        let mut consumer = buffer.consumer();
+
+        for i in 0..11 {
+            producer.push(i).unwrap();
+        }
+        buffer.flush();
+        assert_eq!(buffer.overflowed(), 7);
+
+        let mut payloads = Vec::new();
+        while payloads.len() < 11 {
+            payloads.extend(consumer.iter().map(|event| event.payload));
+            buffer.sequence_available();
+        }
+        assert_eq!(payloads, (0..11).collect::<Vec<_>>());
+        assert_eq!(buffer.overflowed(), 0);
+    }
+
+    #[test]
+    fn on_full_grow_delivers_spilled_events_after_close() {
+        let buffer = Buffer::<u64>::builder()
+            .capacity(4)
+            .sequencer_wait_strategy(WaitStrategy::Blocking)
+            .on_full(OnFull::Grow)
+            .build()
+            .unwrap();
+        let mut handle = buffer.start();
+        let producer = buffer.producer();
+        let mut consumer = buffer.consumer();
+
+        for i in 0..20 {
+            producer.push(i).unwrap();
+        }
+        buffer.close();
+        let payloads: Vec<u64> = std::iter::from_fn(|| consumer.next().ok())
+            .map(|event| event.payload)
+            .collect();
+        assert_eq!(payloads, (0..20).collect::<Vec<_>>());
+
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn overwrite_oldest_never_delivers_a_torn_event() {
+        let buffer = Buffer::<[u64; 4]>::builder()
+            .capacity(8)
+            .sequencer_wait_strategy(WaitStrategy::Blocking)
+            .on_full(OnFull::OverwriteOldest)
+            .build()
+            .unwrap();
+        let mut handle = buffer.start();
+        let mut consumer = buffer.consumer();
+
+        let writer = {
+            let buffer = buffer.clone();
+            std::thread::spawn(move || {
+                let producer = buffer.producer();
+                for i in 0..5_000u64 {
+                    producer.push([i; 4]).unwrap();
+                }
+            })
+        };
+
+        let mut delivered = 0;
+        loop {
+            match consumer.try_next_ref() {
+                Ok(Some(event)) => {
+                    assert_eq!(*event, [event.sequence; 4]);
+                    delivered += 1;
+                    if event.sequence == 4_999 {
+                        break;
+                    }
+                }
+                Ok(None) => std::thread::yield_now(),
+                Err(crate::error::ConsumerError::Lagged { .. }) => {}
+                Err(err) => panic!("unexpected {err}"),
+            }
+        }
+        writer.join().unwrap();
+        assert!(delivered > 0);
+
+        handle.stop();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn metadata_travels_beside_the_payload() {
+        #[derive(Debug, Clone, Copy, Default, PartialEq)]
+        struct Header {
+            correlation_id: u32,
+            source: u8,
+        }
+
+        let buffer = Buffer::<u64, Header>::builder()
+            .capacity(4)
+            .on_full(OnFull::Grow)
+            .build()
+            .unwrap();
+        let producer = buffer.producer();
+        let mut consumer = buffer.consumer();
+
+        // The last events spill to the overflow and keep their headers there too
+        for i in 0..6 {
+            let header = Header {
+                correlation_id: i as u32 * 10,
+                source: 1,
+            };
+            producer.push_with_metadata(i, header).unwrap();
+        }
+        producer.push(6).unwrap();
+        buffer.flush();
+
+        let event = consumer.try_next_ref().unwrap().unwrap();
+        assert_eq!((*event, event.metadata.correlation_id), (0, 0));
+        drop(event);
+        let mut events = Vec::new();
+        while events.len() < 6 {
+            events.extend(consumer.iter().map(|event| (event.payload, event.metadata)));
+            buffer.sequence_available();
+        }
+        assert_eq!(events[3].1, Header { correlation_id: 40, source: 1 });
+        assert_eq!(events[5], (6, Header::default()));
+    }
+
+    #[test]
+    fn flags_travel_with_the_event() {
+        const REPLAYED: u32 = 1 << 0;
+        const END_OF_BATCH: u32 = 1 << 7;
+
+        let buffer = Buffer::<u64>::builder()
+            .capacity(2)
+            .on_full(OnFull::Grow)
+            .build()
+            .unwrap();
+        let producer = buffer.producer();
+        let mut consumer = buffer.consumer();
+
+        // The third spills to the overflow, which keeps its flags too
+        producer.push_with_flags(0, REPLAYED).unwrap();
+        producer.push(1).unwrap();
+        producer.push_with_flags(2, REPLAYED | END_OF_BATCH).unwrap();
+        buffer.flush();
+
+        let event = consumer.try_next_ref().unwrap().unwrap();
+        assert_eq!(event.flags, REPLAYED);
+        drop(event);
+        let mut flags = Vec::new();
+        while flags.len() < 2 {
+            flags.extend(consumer.iter().map(|event| event.flags));
+            buffer.sequence_available();
+        }
+        assert_eq!(flags, vec![0, REPLAYED | END_OF_BATCH]);
+
+        // Slots with a 32-bit sequence keep the low byte
+        producer.push_with_flags(3, 1 << 8 | END_OF_BATCH).unwrap();
+        buffer.flush();
+        let kept = if cfg!(feature = "sequence-32") {
+            END_OF_BATCH
+        } else {
+            1 << 8 | END_OF_BATCH
+        };
+        assert_eq!(consumer.try_next().unwrap().unwrap().flags, kept);
+    }
+
+    #[test]
+    fn timestamps_can_be_turned_off() {
+        let buffer = Buffer::<u64>::builder()
+            .capacity(16)
+            .timestamps(false)
+            .build()
+            .unwrap();
+        let producer = buffer.producer();
+        let mut consumer = buffer.consumer();
+
+        producer.push(42).unwrap();
+        buffer.flush();
+        let event = consumer.try_next().unwrap().unwrap();
+        assert_eq!((event.payload, event.timestamp), (42, 0));
+    }
+
     #[test]
     fn timestamp_captured_on_publish() {
         let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
@@ -145,8 +902,8 @@ mod tests {
         producer.push(42).unwrap();
 
         // Check that timestamp is non-zero
-        let slot = &buffer.slots[0];
-        let ts = unsafe { *slot.timestamp.get() };
+        // SAFETY: Nothing sequences or reclaims the slot meanwhile
+        let ts = unsafe { SlotReadGuard::new(&buffer.slots[0]) }.timestamp();
         assert!(ts > 0, "Timestamp should be captured");
     }
 }
@@ -0,0 +1,119 @@
+//! Process-wide buffers by name, so components can share a topic without passing
+//! `Arc`s around: one side registers `"md.trades"`, others look it up and attach
+//! producers or consumers.
+
+use crate::buffer::Buffer;
+use crate::error::RegistryError;
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, RwLock};
+
+type Topics = HashMap<String, Arc<dyn Any + Send + Sync>>;
+
+static TOPICS: LazyLock<RwLock<Topics>> = LazyLock::new(|| RwLock::new(HashMap::new()));
+
+/// Make `buffer` available under `name`. Fails if the name is already taken.
+pub fn register<T>(name: impl Into<String>, buffer: Arc<Buffer<T>>) -> Result<(), RegistryError>
+where
+    T: Copy + Send + 'static,
+{
+    let mut topics = TOPICS.write().unwrap_or_else(|e| e.into_inner());
+    let name = name.into();
+    if topics.contains_key(&name) {
+        return Err(RegistryError::NameTaken(name));
+    }
+    topics.insert(name, buffer);
+    Ok(())
+}
+
+/// The buffer registered under `name`. Fails if there is none, or if it holds events of another type.
+pub fn get<T>(name: &str) -> Result<Arc<Buffer<T>>, RegistryError>
+where
+    T: Copy + Send + 'static,
+{
+    let topics = TOPICS.read().unwrap_or_else(|e| e.into_inner());
+    let topic = topics
+        .get(name)
+        .ok_or_else(|| RegistryError::NotFound(name.to_string()))?;
+    topic
+        .clone()
+        .downcast::<Buffer<T>>()
+        .map_err(|_| RegistryError::WrongType(name.to_string()))
+}
+
+/// Register the buffer `build` returns under `name`, unless one is registered already.
+/// Lets every component that uses a topic create it on first use.
+pub fn get_or_register<T, E>(
+    name: &str,
+    build: impl FnOnce() -> Result<Arc<Buffer<T>>, E>,
+) -> Result<Arc<Buffer<T>>, E>
+where
+    T: Copy + Send + 'static,
+    E: From<RegistryError>,
+{
+    let mut topics = TOPICS.write().unwrap_or_else(|e| e.into_inner());
+    if let Some(topic) = topics.get(name) {
+        return topic
+            .clone()
+            .downcast::<Buffer<T>>()
+            .map_err(|_| RegistryError::WrongType(name.to_string()).into());
+    }
+    let buffer = build()?;
+    topics.insert(name.to_string(), buffer.clone());
+    Ok(buffer)
+}
+
+/// Remove `name` from the registry, returning whether it was registered.
+/// Handles already attached to the buffer keep working.
+pub fn unregister(name: &str) -> bool {
+    TOPICS
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(name)
+        .is_some()
+}
+
+/// Names of all registered buffers, sorted
+pub fn names() -> Vec<String> {
+    let topics = TOPICS.read().unwrap_or_else(|e| e.into_inner());
+    let mut names: Vec<String> = topics.keys().cloned().collect();
+    names.sort();
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn components_attach_by_name() {
+        let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        register("test.attach", buffer.clone()).unwrap();
+        assert_eq!(
+            register("test.attach", buffer),
+            Err(RegistryError::NameTaken("test.attach".to_string()))
+        );
+
+        get::<u64>("test.attach").unwrap().producer().push(5).unwrap();
+        let topic = get::<u64>("test.attach").unwrap();
+        topic.flush();
+        assert_eq!(topic.consumer().try_next().unwrap().unwrap().payload, 5);
+
+        assert!(matches!(get::<u32>("test.attach"), Err(RegistryError::WrongType(_))));
+        assert!(names().contains(&"test.attach".to_string()));
+        assert!(unregister("test.attach"));
+        assert!(matches!(get::<u64>("test.attach"), Err(RegistryError::NotFound(_))));
+    }
+
+    #[test]
+    fn get_or_register_builds_once() {
+        let build = || Ok(Buffer::<u64>::builder().capacity(8).build().unwrap());
+        let first = get_or_register::<u64, RegistryError>("test.once", build).unwrap();
+        let second = get_or_register::<u64, RegistryError>("test.once", build).unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+
+        let wrong = get_or_register::<u32, RegistryError>("test.once", || unreachable!());
+        assert!(matches!(wrong, Err(RegistryError::WrongType(_))));
+        unregister("test.once");
+    }
+}
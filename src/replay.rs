@@ -0,0 +1,413 @@
+use crate::adapter::EventSource;
+use crate::consumer::Event;
+use crate::error::ConsumerError;
+use crate::persist::{read_index, segments, LogPayload, LogReader, Record, SegmentReader};
+use std::fs;
+use std::io;
+use std::marker::PhantomData;
+use std::path::PathBuf;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Reads a log a `LogWriter` wrote back as a stream of events, through the same
+/// `EventSource` surface as a live `Consumer`, so processing code runs unchanged
+/// against history.
+///
+/// Events come back in sequence order with the timestamp and producer id they were
+/// pushed with. The log keeps no flags, metadata or slot generations, so those are
+/// zero and `M::default()`. An event a restarted writer wrote twice is read once, and
+/// a torn tail ends its segment, as in `LogReader::recover`.
+///
+/// The segments are listed when the consumer is created. Once it has read them all,
+/// `try_next` returns `None` and the blocking calls `ConsumerError::Closed`, as for a
+/// closed buffer. A segment that cannot be read, a `Corruption`, or a payload that
+/// does not decode as `T`, is `ConsumerError::Log`; the consumer moves past corrupt
+/// stretches and undecodable events.
+pub struct ReplayConsumer<T, M = ()> {
+    log: LogReader,
+    segments: Vec<(u64, PathBuf)>,
+    /// Which of `segments` is being read
+    segment: usize,
+    /// Opened on the first read from `segment`
+    reader: Option<SegmentReader>,
+    /// Lowest sequence still to deliver; anything below it was read or sought past
+    cursor: u64,
+    _event: PhantomData<fn() -> (T, M)>,
+}
+
+impl<T, M> ReplayConsumer<T, M>
+where
+    T: LogPayload,
+    M: Default,
+{
+    pub(crate) fn new(log: LogReader) -> io::Result<Self> {
+        let segments = segments(&log.dir)?;
+        Ok(Self {
+            log,
+            segments,
+            segment: 0,
+            reader: None,
+            cursor: 0,
+            _event: PhantomData,
+        })
+    }
+
+    /// Lowest sequence the next event can have
+    pub fn position(&self) -> u64 {
+        self.cursor
+    }
+
+    /// Move to the first event at or after `sequence`, using the timestamp index to
+    /// skip most of the segment it is in. A sequence past the end of the log leaves
+    /// nothing to read.
+    pub fn seek(&mut self, sequence: u64) -> Result<(), ConsumerError> {
+        let segment = self.segments.partition_point(|&(first, _)| first <= sequence);
+        self.segment = segment.saturating_sub(1);
+        self.reader = None;
+        self.cursor = sequence;
+        let Some((_, path)) = self.segments.get(self.segment) else {
+            return Ok(());
+        };
+        let len = fs::metadata(path).map_err(log_error)?.len();
+        let entries = read_index(path, len).map_err(log_error)?;
+        let entry = entries.partition_point(|entry| entry.sequence <= sequence);
+        if let Some(entry) = entry.checked_sub(1) {
+            self.open_at(entries[entry].offset)?;
+        }
+        Ok(())
+    }
+
+    /// Move to the first event stamped `timestamp` or later; see
+    /// `LogReader::seek_to_timestamp`. With none, there is nothing left to read.
+    pub fn seek_to_timestamp(&mut self, timestamp: u64) -> Result<(), ConsumerError> {
+        let found = self.log.seek_to_timestamp(timestamp).map_err(log_error)?;
+        self.reader = None;
+        let Some(found) = found else {
+            self.segment = self.segments.len();
+            return Ok(());
+        };
+        self.segment = self
+            .segments
+            .iter()
+            .position(|(_, path)| *path == found.path)
+            .unwrap_or(self.segments.len());
+        self.cursor = found.sequence;
+        self.open_at(found.offset)
+    }
+
+    /// Deliver events no faster than they were stamped: `ticks_per_second` is the rate
+    /// of the clock the timestamps came from, and `speed` scales time, so 2.0 replays
+    /// twice as fast. See `PacedReplay`.
+    pub fn paced(self, ticks_per_second: u64, speed: f64) -> PacedReplay<T, M> {
+        PacedReplay::new(self, ticks_per_second, speed)
+    }
+
+    /// Start reading the current segment at `offset`
+    fn open_at(&mut self, offset: u64) -> Result<(), ConsumerError> {
+        let Some((_, path)) = self.segments.get(self.segment) else {
+            return Ok(());
+        };
+        let mut reader = self.log.open_segment(path).map_err(log_error)?;
+        reader.seek(offset).map_err(log_error)?;
+        self.reader = Some(reader);
+        Ok(())
+    }
+}
+
+impl<T, M> EventSource for ReplayConsumer<T, M>
+where
+    T: LogPayload,
+    M: Default,
+{
+    type Item = Event<T, M>;
+
+    fn try_next(&mut self) -> Result<Option<Event<T, M>>, ConsumerError> {
+        loop {
+            if self.reader.is_none() {
+                if self.segment >= self.segments.len() {
+                    return Ok(None);
+                }
+                self.open_at(0)?;
+            }
+            let reader = self.reader.as_mut().expect("opened above");
+            let Some(record) = reader.next().map_err(log_error)? else {
+                let path = &self.segments[self.segment].1;
+                if let Some(corruption) = reader.corruption(path).map_err(log_error)? {
+                    return Err(ConsumerError::Log(corruption.to_string()));
+                }
+                self.reader = None;
+                self.segment += 1;
+                continue;
+            };
+            if record.sequence < self.cursor {
+                continue;
+            }
+            self.cursor = record.sequence + 1;
+            return logged_event(record).map(Some);
+        }
+    }
+
+    /// The next event, or `Closed` once the log is read to the end
+    fn next(&mut self) -> Result<Event<T, M>, ConsumerError> {
+        self.try_next()?.ok_or(ConsumerError::Closed)
+    }
+
+    /// As `next`; a log never has to be waited on
+    fn next_timeout(&mut self, _timeout: Duration) -> Result<Event<T, M>, ConsumerError> {
+        self.next()
+    }
+}
+
+/// A `ReplayConsumer` that holds each event back until as long after the first one
+/// as it was stamped after it, scaled by a speed multiplier, so a simulation or
+/// backtest sees the stream's original timing and not just its order.
+///
+/// Timestamps are in clock ticks: TSC cycles on x86_64 (the TSC frequency),
+/// `cntvct_el0` counts on aarch64 (`CNTFRQ_EL0`), nanoseconds elsewhere. The first
+/// event read, and the first after `seek` or `seek_to_timestamp`, is delivered at
+/// once and the timing runs from it; an event stamped before it is delivered at once
+/// too. `next` sleeps until the next event is due, `try_next` returns `None` before
+/// then, and `next_timeout` fails with `Timeout` if it is not due in time.
+pub struct PacedReplay<T, M = ()> {
+    replay: ReplayConsumer<T, M>,
+    /// Wall-clock nanoseconds per timestamp tick, divided by the speed
+    nanos_per_tick: f64,
+    /// When the first event was delivered, and its timestamp
+    start: Option<(Instant, u64)>,
+    /// Read but not yet due
+    pending: Option<Event<T, M>>,
+}
+
+impl<T, M> PacedReplay<T, M>
+where
+    T: LogPayload,
+    M: Default,
+{
+    fn new(replay: ReplayConsumer<T, M>, ticks_per_second: u64, speed: f64) -> Self {
+        assert!(ticks_per_second > 0, "ticks_per_second must be positive");
+        assert!(speed > 0.0, "speed must be positive");
+        Self {
+            replay,
+            nanos_per_tick: 1e9 / ticks_per_second as f64 / speed,
+            start: None,
+            pending: None,
+        }
+    }
+
+    /// Lowest sequence the next event can have
+    pub fn position(&self) -> u64 {
+        self.pending.as_ref().map_or(self.replay.position(), |event| event.sequence)
+    }
+
+    /// Move to the first event at or after `sequence`; timing restarts from it
+    pub fn seek(&mut self, sequence: u64) -> Result<(), ConsumerError> {
+        self.restart();
+        self.replay.seek(sequence)
+    }
+
+    /// Move to the first event stamped `timestamp` or later; timing restarts from it
+    pub fn seek_to_timestamp(&mut self, timestamp: u64) -> Result<(), ConsumerError> {
+        self.restart();
+        self.replay.seek_to_timestamp(timestamp)
+    }
+
+    /// Get the wrapped consumer back. An event read but not yet due is dropped, and the
+    /// consumer is left just past it.
+    pub fn into_inner(self) -> ReplayConsumer<T, M> {
+        self.replay
+    }
+
+    fn restart(&mut self) {
+        self.start = None;
+        self.pending = None;
+    }
+
+    /// The next event and when it is due, or `None` at the end of the log
+    fn peek(&mut self) -> Result<Option<Instant>, ConsumerError> {
+        if self.pending.is_none() {
+            self.pending = self.replay.try_next()?;
+        }
+        let Some(event) = &self.pending else {
+            return Ok(None);
+        };
+        let (start, first) = *self.start.get_or_insert((Instant::now(), event.timestamp));
+        let ticks = event.timestamp.saturating_sub(first);
+        Ok(Some(start + Duration::from_nanos((ticks as f64 * self.nanos_per_tick) as u64)))
+    }
+}
+
+impl<T, M> EventSource for PacedReplay<T, M>
+where
+    T: LogPayload,
+    M: Default,
+{
+    type Item = Event<T, M>;
+
+    fn try_next(&mut self) -> Result<Option<Event<T, M>>, ConsumerError> {
+        match self.peek()? {
+            Some(due) if due <= Instant::now() => Ok(self.pending.take()),
+            _ => Ok(None),
+        }
+    }
+
+    /// The next event once it is due, or `Closed` once the log is read to the end
+    fn next(&mut self) -> Result<Event<T, M>, ConsumerError> {
+        let due = self.peek()?.ok_or(ConsumerError::Closed)?;
+        thread::sleep(due.saturating_duration_since(Instant::now()));
+        Ok(self.pending.take().expect("peeked above"))
+    }
+
+    fn next_timeout(&mut self, timeout: Duration) -> Result<Event<T, M>, ConsumerError> {
+        let deadline = Instant::now() + timeout;
+        let due = self.peek()?.ok_or(ConsumerError::Closed)?;
+        if due > deadline {
+            thread::sleep(deadline.saturating_duration_since(Instant::now()));
+            return Err(ConsumerError::Timeout);
+        }
+        thread::sleep(due.saturating_duration_since(Instant::now()));
+        Ok(self.pending.take().expect("peeked above"))
+    }
+}
+
+/// The event a log record holds, without the flags, metadata and generation the log
+/// does not keep
+pub(crate) fn logged_event<T, M>(record: Record<'_>) -> Result<Event<T, M>, ConsumerError>
+where
+    T: LogPayload,
+    M: Default,
+{
+    let payload = T::read_binary(record.payload).ok_or_else(|| {
+        ConsumerError::Log(format!("event {} does not decode", record.sequence))
+    })?;
+    Ok(Event {
+        sequence: record.sequence,
+        generation: 0,
+        timestamp: record.timestamp,
+        producer_id: record.producer_id,
+        flags: 0,
+        late: false,
+        metadata: M::default(),
+        payload,
+    })
+}
+
+pub(crate) fn log_error(err: io::Error) -> ConsumerError {
+    ConsumerError::Log(err.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::Buffer;
+    use crate::persist::temp_dir;
+    use std::path::Path;
+
+    /// Log `count` events stamped 10, 20, ..., 3 records to a segment
+    fn write_log(dir: &Path, count: u32) -> std::sync::Arc<Buffer<u32>> {
+        let buffer = Buffer::<u32>::builder().capacity(64).build().unwrap();
+        let producer = buffer.producer();
+        for i in 0..count {
+            producer.push_replayed(100 + i, (), 10 * (i as u64 + 1)).unwrap();
+        }
+        buffer.flush();
+        let mut writer = buffer
+            .consumer()
+            .log_writer(dir)
+            .unwrap()
+            .segment_bytes(12 + 3 * 29)
+            .index_interval(29);
+        writer.drain().unwrap();
+        writer.sync().unwrap();
+        buffer
+    }
+
+    /// Processing code written once against `EventSource`
+    fn payloads<S: EventSource<Item = Event<u32>>>(source: &mut S) -> Vec<(u64, u64, u32)> {
+        std::iter::from_fn(|| source.try_next().unwrap())
+            .map(|event| (event.sequence, event.timestamp, event.payload))
+            .collect()
+    }
+
+    #[test]
+    fn replay_reads_the_log_like_a_live_consumer() {
+        let dir = temp_dir("replay");
+        let buffer = write_log(&dir, 8);
+
+        let live = payloads(&mut buffer.consumer());
+        let mut replay = LogReader::new(&dir).replay::<u32, ()>().unwrap();
+        assert_eq!(payloads(&mut replay), live);
+        assert_eq!(replay.position(), 8);
+        assert_eq!(replay.next().unwrap_err(), ConsumerError::Closed);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn replay_seeks_by_sequence_and_timestamp() {
+        let dir = temp_dir("replay-seek");
+        write_log(&dir, 8);
+        let mut replay = LogReader::new(&dir).replay::<u32, ()>().unwrap();
+
+        replay.seek(4).unwrap();
+        assert_eq!(replay.next().unwrap().sequence, 4);
+        replay.seek(1).unwrap();
+        assert_eq!(payloads(&mut replay).len(), 7);
+        replay.seek_to_timestamp(55).unwrap();
+        assert_eq!(replay.next().unwrap().timestamp, 60);
+        replay.seek_to_timestamp(81).unwrap();
+        assert!(replay.try_next().unwrap().is_none());
+        replay.seek(20).unwrap();
+        assert!(replay.try_next().unwrap().is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn paced_replay_keeps_the_recorded_gaps() {
+        let dir = temp_dir("replay-paced");
+        write_log(&dir, 4);
+        // Stamped 200 ms apart, replayed at double speed: gaps wide enough that checks
+        // for an event not yet due are done long before it is, however slow the machine
+        let mut paced = LogReader::new(&dir).replay::<u32, ()>().unwrap().paced(50, 2.0);
+
+        let start = Instant::now();
+        assert_eq!(paced.try_next().unwrap().unwrap().timestamp, 10);
+        assert!(paced.try_next().unwrap().is_none());
+        assert_eq!(paced.position(), 1);
+        assert_eq!(paced.next_timeout(Duration::ZERO).unwrap_err(), ConsumerError::Timeout);
+        assert_eq!(paced.next().unwrap().timestamp, 20);
+        assert_eq!(paced.next().unwrap().timestamp, 30);
+        assert!(start.elapsed() >= Duration::from_millis(200));
+        assert_eq!(paced.next_timeout(Duration::from_secs(10)).unwrap().timestamp, 40);
+        assert!(start.elapsed() >= Duration::from_millis(300));
+        assert_eq!(paced.next().unwrap_err(), ConsumerError::Closed);
+
+        // A seek restarts the timing from the event it lands on
+        paced.seek(2).unwrap();
+        assert_eq!(paced.try_next().unwrap().unwrap().sequence, 2);
+        assert!(paced.try_next().unwrap().is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn events_a_restarted_writer_rewrote_are_read_once() {
+        let dir = temp_dir("replay-rewrite");
+        let buffer = write_log(&dir, 5);
+        // A writer that never committed its cursor writes 3 and 4 again
+        let mut consumer = buffer.consumer();
+        consumer.seek(3).unwrap();
+        let mut writer = consumer.log_writer(&dir).unwrap().segment_bytes(12 + 3 * 29);
+        writer.drain().unwrap();
+        writer.sync().unwrap();
+
+        let mut replay = LogReader::new(&dir).replay::<u32, ()>().unwrap();
+        let sequences: Vec<_> = payloads(&mut replay).iter().map(|event| event.0).collect();
+        assert_eq!(sequences, [0, 1, 2, 3, 4]);
+        let recovered = LogReader::new(&dir).recover::<u32>().unwrap();
+        assert_eq!(recovered.last_sequence(), Some(4));
+        assert_eq!(recovered.events.len(), 5);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,159 @@
+use crate::sync::atomic::{AtomicUsize, Ordering};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// An event waiting outside the ring, stamped when it was pushed
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Entry<T, M> {
+    pub(crate) payload: T,
+    pub(crate) metadata: M,
+    pub(crate) timestamp: u64,
+    pub(crate) producer_id: u8,
+    pub(crate) flags: u32,
+}
+
+/// Events pushed while the ring was full, for `OnFull::Grow`.
+///
+/// A FIFO chain of fixed-size segments: producers append to the last one and link
+/// in a new segment when it fills; whoever holds `Buffer::sequencing` moves entries
+/// from the front into the ring as slots free up. Only touched once the ring is full,
+/// so a mutex is fine. One emptied segment is kept to spare the next allocation;
+/// otherwise `push` allocates a segment under the lock, on the producer's path, as
+/// `OnFull::Grow` documents. Nothing bounds the chain, so there is no limit to
+/// allocate up front.
+#[derive(Debug)]
+pub(crate) struct Overflow<T, M> {
+    segment_len: usize,
+    chain: Mutex<Chain<T, M>>,
+    len: AtomicUsize,
+}
+
+#[derive(Debug)]
+struct Chain<T, M> {
+    segments: VecDeque<Segment<T, M>>,
+    spare: Option<Vec<Entry<T, M>>>,
+}
+
+#[derive(Debug)]
+struct Segment<T, M> {
+    entries: Vec<Entry<T, M>>,
+    /// Index of the next entry to move into the ring
+    read: usize,
+}
+
+impl<T: Copy, M: Copy> Overflow<T, M> {
+    pub(crate) fn new(segment_len: usize) -> Self {
+        Self {
+            segment_len,
+            chain: Mutex::new(Chain {
+                segments: VecDeque::new(),
+                spare: None,
+            }),
+            len: AtomicUsize::new(0),
+        }
+    }
+
+    /// Events waiting to enter the ring
+    pub(crate) fn len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+
+    /// Bytes held by segments, including the spare one, for `Buffer::memory_usage`
+    pub(crate) fn memory_usage(&self) -> usize {
+        let chain = self.chain.lock().unwrap_or_else(|e| e.into_inner());
+        let entries: usize = chain
+            .segments
+            .iter()
+            .map(|segment| segment.entries.capacity())
+            .chain(chain.spare.as_ref().map(Vec::capacity))
+            .sum();
+        entries * size_of::<Entry<T, M>>() + chain.segments.capacity() * size_of::<Segment<T, M>>()
+    }
+
+    pub(crate) fn push(&self, entry: Entry<T, M>) {
+        let mut chain = self.chain.lock().unwrap_or_else(|e| e.into_inner());
+        let full = chain
+            .segments
+            .back()
+            .is_none_or(|segment| segment.entries.len() == self.segment_len);
+        if full {
+            let entries = chain
+                .spare
+                .take()
+                .unwrap_or_else(|| Vec::with_capacity(self.segment_len));
+            chain.segments.push_back(Segment { entries, read: 0 });
+        }
+        let segment = chain.segments.back_mut().expect("segment linked above");
+        segment.entries.push(entry);
+        self.len.fetch_add(1, Ordering::Release);
+    }
+
+    /// Hand entries to `place` oldest first until it returns false, returning how many it took.
+    /// An entry stays counted in `len` until `place` has taken it, so a producer that sees
+    /// `len() == 0` knows every spilled event already holds its ring slot.
+    pub(crate) fn drain_into(&self, mut place: impl FnMut(Entry<T, M>) -> bool) -> usize {
+        if self.len() == 0 {
+            return 0;
+        }
+        let mut chain = self.chain.lock().unwrap_or_else(|e| e.into_inner());
+        let mut taken = 0;
+        while let Some(segment) = chain.segments.front_mut() {
+            let Some(&entry) = segment.entries.get(segment.read) else {
+                break;
+            };
+            if !place(entry) {
+                break;
+            }
+            segment.read += 1;
+            taken += 1;
+            self.len.fetch_sub(1, Ordering::Release);
+
+            if segment.read == self.segment_len {
+                let mut emptied = chain.segments.pop_front().expect("front segment");
+                emptied.entries.clear();
+                chain.spare = Some(emptied.entries);
+            }
+        }
+        taken
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(payload: u64) -> Entry<u64, ()> {
+        Entry {
+            payload,
+            metadata: (),
+            timestamp: 0,
+            producer_id: 0,
+            flags: 0,
+        }
+    }
+
+    #[test]
+    fn entries_leave_in_push_order_across_segments() {
+        let overflow = Overflow::new(2);
+        for i in 0..5 {
+            overflow.push(entry(i));
+        }
+        assert_eq!(overflow.len(), 5);
+
+        let mut seen = Vec::new();
+        let taken = overflow.drain_into(|entry| {
+            seen.push(entry.payload);
+            seen.len() < 3
+        });
+        assert_eq!(taken, 2);
+        assert_eq!(overflow.len(), 3);
+
+        overflow.push(entry(5));
+        overflow.drain_into(|entry| {
+            seen.push(entry.payload);
+            true
+        });
+        assert_eq!(seen, vec![0, 1, 2, 2, 3, 4, 5]);
+        assert_eq!(overflow.len(), 0);
+    }
+}
@@ -1,84 +1,558 @@
+use crate::affinity;
 use crate::buffer::Buffer;
+use crate::numa;
 use crate::slot::SlotState;
-use std::sync::atomic::{AtomicBool, Ordering};
+use crate::sync::atomic::{fence, AtomicBool, AtomicU64, Ordering};
+use crate::wait::Waiter;
+use std::fmt;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver};
 use std::sync::Arc;
 use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// How often `join_timeout` checks whether the thread has exited
+const JOIN_POLL: Duration = Duration::from_millis(1);
+/// How often a parked sequencer looks for ring slots to move spilled events into.
+/// Consumers free slots without signalling the sequencer.
+const REFILL_POLL: Duration = Duration::from_micros(100);
+
+/// Flags shared between a `SequencerHandle` and its thread
+#[derive(Debug, Default)]
+struct Control {
+    stop: AtomicBool,
+    /// Sequence everything already published before exiting
+    drain: AtomicBool,
+    /// Pause requested by the handle
+    pause: AtomicBool,
+    /// Set by the thread once it has stopped sequencing in response to `pause`
+    paused: AtomicBool,
+    /// Set when the loop panicked and was not restarted
+    panicked: AtomicBool,
+    /// Times the loop was restarted after a panic
+    restarts: AtomicU64,
+    /// Next sequence number to assign, as of the last run
+    sequence: AtomicU64,
+    /// Events sequenced since the thread started
+    sequenced: AtomicU64,
+    /// Passes over the scan position, productive or not
+    scans: AtomicU64,
+    /// Nanoseconds spent waiting for work, excluding the current wait
+    idle_nanos: AtomicU64,
+    /// Start of the current wait in nanoseconds since the thread started, plus one; 0 while busy
+    idle_since: AtomicU64,
+}
 
 pub struct SequencerHandle {
-    stop: Arc<AtomicBool>,
+    control: Arc<Control>,
+    wake: Box<dyn Fn() + Send + Sync>,
     thread: Option<JoinHandle<()>>,
+    started: Instant,
+}
+
+/// Snapshot of a sequencer's throughput and headroom, from `SequencerHandle::stats`.
+/// Rates are averaged over the thread's lifetime.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SequencerStats {
+    /// Next sequence number the sequencer will assign
+    pub sequence: u64,
+    /// Events sequenced since the thread started
+    pub sequenced: u64,
+    pub events_per_sec: f64,
+    /// Passes over the scan position per event; close to 1 means the sequencer is saturated
+    pub scans_per_event: f64,
+    /// Time spent waiting for producers, i.e. spare capacity
+    pub spinning: Duration,
+    pub uptime: Duration,
 }
 
 impl SequencerHandle {
+    /// Ask the sequencer to exit. Safe to call repeatedly and from several threads.
     pub fn stop(&self) {
-        self.stop.store(true, Ordering::Release);
+        self.control.stop.store(true, Ordering::Release);
+        (self.wake)();
+    }
+
+    /// Stop once every event published so far has been sequenced.
+    /// Slots still being written are waited for; a paused sequencer drains too.
+    pub fn stop_and_drain(&self) {
+        self.control.drain.store(true, Ordering::Release);
+        self.stop();
+    }
+
+    /// Freeze sequencing. Returns once the sequencer has finished its current run;
+    /// published events wait in the ring until `resume`. The scan position is kept.
+    pub fn pause(&self) {
+        self.control.pause.store(true, Ordering::Release);
+        (self.wake)();
+        while !self.control.paused.load(Ordering::Acquire)
+            && self
+                .thread
+                .as_ref()
+                .is_some_and(|thread| !thread.is_finished())
+        {
+            thread::yield_now();
+        }
+    }
+
+    /// Continue sequencing from where `pause` left off
+    pub fn resume(&self) {
+        self.control.pause.store(false, Ordering::Release);
+        (self.wake)();
+    }
+
+    /// Whether the sequencer is currently paused
+    pub fn is_paused(&self) -> bool {
+        self.control.paused.load(Ordering::Acquire)
+    }
+
+    /// Whether the sequencer died from a panic. Nothing further will be sequenced.
+    pub fn panicked(&self) -> bool {
+        self.control.panicked.load(Ordering::Acquire)
+    }
+
+    /// How many times the sequencer was restarted after panicking
+    pub fn restarts(&self) -> u64 {
+        self.control.restarts.load(Ordering::Acquire)
+    }
+
+    /// Current counters. Cheap enough to poll from a metrics thread.
+    pub fn stats(&self) -> SequencerStats {
+        let control = &self.control;
+        let uptime = self.started.elapsed();
+        let sequenced = control.sequenced.load(Ordering::Relaxed);
+        let mut idle_nanos = control.idle_nanos.load(Ordering::Relaxed);
+        let idle_since = control.idle_since.load(Ordering::Relaxed);
+        if idle_since != 0 {
+            idle_nanos += (uptime.as_nanos() as u64).saturating_sub(idle_since - 1);
+        }
+        SequencerStats {
+            sequence: control.sequence.load(Ordering::Relaxed),
+            sequenced,
+            events_per_sec: sequenced as f64 / uptime.as_secs_f64(),
+            scans_per_event: control.scans.load(Ordering::Relaxed) as f64 / sequenced.max(1) as f64,
+            spinning: Duration::from_nanos(idle_nanos).min(uptime),
+            uptime,
+        }
     }
 
-    pub fn join(mut self) -> Result<(), Box<dyn std::error::Error>> {
+    /// Whether the sequencer is still alive: not stopped, crashed or joined.
+    /// A sequencer that is stopping counts as running until its thread exits.
+    pub fn is_running(&self) -> bool {
+        match &self.thread {
+            Some(thread) => !thread.is_finished(),
+            // Single-producer mode has no thread; it runs until stopped
+            None => !self.control.stop.load(Ordering::Acquire) && !self.panicked(),
+        }
+    }
+
+    /// Whether the sequencer thread has exited
+    pub fn is_finished(&self) -> bool {
+        self.thread
+            .as_ref()
+            .is_none_or(|thread| thread.is_finished())
+    }
+
+    /// Wait for the thread to exit. Borrows rather than consumes, so the handle can
+    /// still be queried afterwards; joining again returns immediately.
+    pub fn join(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.join_thread()
+    }
+
+    /// Like `join`, but gives up after `timeout`. On timeout the handle is left
+    /// intact so the caller can retry or report a stuck sequencer.
+    pub fn join_timeout(&mut self, timeout: Duration) -> Result<(), Box<dyn std::error::Error>> {
+        let deadline = Instant::now() + timeout;
+        while !self.is_finished() {
+            if Instant::now() >= deadline {
+                return Err("Sequencer did not exit before the timeout".into());
+            }
+            thread::sleep(JOIN_POLL.min(deadline.saturating_duration_since(Instant::now())));
+        }
+        self.join_thread()
+    }
+
+    fn join_thread(&mut self) -> Result<(), Box<dyn std::error::Error>> {
         if let Some(thread) = self.thread.take() {
             thread.join().map_err(|_| "Thread join failed")?;
         }
+        if self.panicked() {
+            return Err("Sequencer panicked".into());
+        }
         Ok(())
     }
 }
 
 impl Drop for SequencerHandle {
     fn drop(&mut self) {
-        self.stop.store(true, Ordering::Release);
+        self.stop();
         if let Some(thread) = self.thread.take() {
             let _ = thread.join();
         }
     }
 }
 
-pub fn start_sequencer<T>(buffer: Arc<Buffer<T>>) -> SequencerHandle
+/// Name, core, NUMA node and priority for the sequencer thread, set through `BufferBuilder`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct ThreadConfig {
+    pub(crate) name: Option<String>,
+    pub(crate) core: Option<usize>,
+    /// Run on any CPU of this node, unless `core` is set
+    pub(crate) node: Option<usize>,
+    pub(crate) priority: Option<i32>,
+}
+
+impl ThreadConfig {
+    /// Apply core pinning and priority to the calling thread
+    fn apply(&self) -> io::Result<()> {
+        if let Some(core) = self.core {
+            affinity::pin_to_core(core)?;
+        } else if let Some(node) = self.node {
+            affinity::pin_to_cores(&numa::cpus(node)?)?;
+        }
+        if let Some(priority) = self.priority {
+            affinity::set_priority(priority)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn start_sequencer<T, M>(buffer: Arc<Buffer<T, M>>) -> SequencerHandle
 where
     T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
 {
-    let stop = Arc::new(AtomicBool::new(false));
-    let stop_clone = stop.clone();
+    let (handle, _placed) = spawn_sequencer(buffer).expect("failed to spawn sequencer thread");
+    handle
+}
 
-    let thread = thread::spawn(move || {
-        sequencer_loop(&buffer, &stop_clone);
-    });
+/// Spawn the sequencer thread. The receiver reports whether its core pinning
+/// and priority were applied; the thread keeps running either way.
+pub(crate) fn spawn_sequencer<T, M>(
+    buffer: Arc<Buffer<T, M>>,
+) -> io::Result<(SequencerHandle, Receiver<io::Result<()>>)>
+where
+    T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
+{
+    let control = Arc::new(Control::default());
+    let thread_control = control.clone();
+    let started = Instant::now();
+    buffer.shutdown.store(false, Ordering::Release);
+
+    if buffer.single_producer {
+        // The producer sequences its own events; the handle only tells consumers when to stop
+        let stop_control = control.clone();
+        let (placed_tx, placed) = mpsc::channel();
+        let _ = placed_tx.send(Ok(()));
+        let handle = SequencerHandle {
+            control,
+            wake: Box::new(move || {
+                if stop_control.stop.load(Ordering::Acquire) {
+                    buffer.update_tail();
+                    buffer.shutdown.store(true, Ordering::Release);
+                    buffer.notifier.notify_all();
+                }
+            }),
+            thread: None,
+            started,
+        };
+        return Ok((handle, placed));
+    }
+
+    let config = buffer.sequencer_thread.clone();
+    let name = config
+        .name
+        .clone()
+        .unwrap_or_else(|| "lftes-sequencer".to_string());
+    let (placed_tx, placed) = mpsc::channel();
+
+    let wake_buffer = buffer.clone();
+    let thread = thread::Builder::new().name(name).spawn(move || {
+        let _ = placed_tx.send(config.apply());
+
+        // Wait out a manual `sequence_available` pass, then own sequencing until stopped
+        while buffer.sequencing.swap(true, Ordering::Acquire) {
+            thread::yield_now();
+        }
+        let next_seq = buffer.next_seq.load(Ordering::Acquire);
+        thread_control.sequence.store(next_seq, Ordering::Relaxed);
+        // Supervise the loop. The scan position lives in the buffer, so a restart resumes there.
+        loop {
+            let run = AssertUnwindSafe(|| sequencer_loop(&buffer, &thread_control, started));
+            if panic::catch_unwind(run).is_ok() {
+                break;
+            }
+            if !buffer.restart_sequencer || thread_control.stop.load(Ordering::Acquire) {
+                thread_control.panicked.store(true, Ordering::Release);
+                break;
+            }
+            buffer.rebuild_published();
+            thread_control.restarts.fetch_add(1, Ordering::AcqRel);
+        }
+        let closed = buffer.closed.load(Ordering::Acquire);
+        if (closed || thread_control.drain.load(Ordering::Acquire))
+            && !thread_control.panicked.load(Ordering::Acquire)
+        {
+            // Pairs with the producer's closed check after claiming; see `Producer::push`
+            fence(Ordering::SeqCst);
+            drain(&buffer);
+        }
+        buffer.sequencing.store(false, Ordering::Release);
 
-    SequencerHandle {
-        stop,
+        // Release consumers blocked on events that will never be sequenced
+        buffer.update_tail();
+        buffer.shutdown.store(true, Ordering::Release);
+        buffer.notifier.notify_all();
+    })?;
+
+    let handle = SequencerHandle {
+        control,
+        wake: Box::new(move || wake_buffer.publish_notifier.notify_all()),
         thread: Some(thread),
+        started,
+    };
+    Ok((handle, placed))
+}
+
+/// Callback run by the sequencer after a quiet period, set through `BufferBuilder`
+pub(crate) struct IdleHook {
+    pub(crate) interval: Duration,
+    pub(crate) hook: Box<dyn FnMut(Duration) + Send>,
+}
+
+impl fmt::Debug for IdleHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("IdleHook")
+            .field("interval", &self.interval)
+            .finish_non_exhaustive()
     }
 }
 
-fn sequencer_loop<T>(buffer: &Buffer<T>, stop: &AtomicBool) {
-    let mut next_seq: u64 = 0;
-    let mut scan_pos: usize = 0;
+/// Claim timeout and diagnostic hook, set through `BufferBuilder`
+pub(crate) struct StuckClaims {
+    pub(crate) timeout: Duration,
+    pub(crate) hook: Option<Box<dyn FnMut(u64) + Send>>,
+}
 
-    while !stop.load(Ordering::Relaxed) {
-        let slot_idx = scan_pos & buffer.mask;
-        let slot = &buffer.slots[slot_idx];
+impl fmt::Debug for StuckClaims {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StuckClaims")
+            .field("timeout", &self.timeout)
+            .finish_non_exhaustive()
+    }
+}
 
-        let state = slot.state.load(Ordering::Acquire);
+/// Times the sequencer's waits for `SequencerHandle::stats`
+struct IdleClock<'a> {
+    control: &'a Control,
+    started: Instant,
+    since: Option<Instant>,
+}
 
-        match state {
-            s if s == SlotState::Published as u8 => {
-                // Assign sequence number
-                slot.sequence.store(next_seq, Ordering::Release);
-                next_seq += 1;
+impl<'a> IdleClock<'a> {
+    fn new(control: &'a Control, started: Instant) -> Self {
+        Self {
+            control,
+            started,
+            since: None,
+        }
+    }
 
-                // Transition to Sequenced
-                slot.state
-                    .store(SlotState::Sequenced as u8, Ordering::Release);
+    fn idle(&mut self) {
+        if self.since.is_none() {
+            let now = Instant::now();
+            self.since = Some(now);
+            let offset = (now - self.started).as_nanos() as u64;
+            self.control.idle_since.store(offset + 1, Ordering::Relaxed);
+        }
+    }
 
-                scan_pos += 1;
-            }
-            s if s == SlotState::Claimed as u8 => {
-                // Producer still writing - spin on this slot
-                std::hint::spin_loop();
+    fn busy(&mut self) {
+        if let Some(since) = self.since.take() {
+            let waited = since.elapsed().as_nanos() as u64;
+            self.control.idle_nanos.fetch_add(waited, Ordering::Relaxed);
+            self.control.idle_since.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Drop for IdleClock<'_> {
+    fn drop(&mut self) {
+        self.busy();
+    }
+}
+
+fn sequencer_loop<T, M>(buffer: &Buffer<T, M>, control: &Control, started: Instant)
+where
+    T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
+{
+    let mut waiter = Waiter::new(buffer.sequencer_wait_strategy);
+    let mut idle_clock = IdleClock::new(control, started);
+    let mut idle_hook = buffer
+        .idle_hook
+        .as_ref()
+        .map(|hook| hook.lock().unwrap_or_else(|e| e.into_inner()));
+    let mut last_sequenced = Instant::now();
+    let mut next_idle_call = None;
+    let mut stuck_claims = buffer
+        .stuck_claims
+        .as_ref()
+        .map(|stuck| stuck.lock().unwrap_or_else(|e| e.into_inner()));
+    // Sequence number whose slot was first seen Claimed, and when
+    let mut claimed_at: Option<(u64, Instant)> = None;
+
+    while !control.stop.load(Ordering::Relaxed) && !buffer.closed.load(Ordering::Relaxed) {
+        if control.pause.load(Ordering::Acquire) {
+            idle_clock.busy();
+            control.paused.store(true, Ordering::Release);
+            waiter.wait(&buffer.publish_notifier, None, || {
+                control.stop.load(Ordering::Acquire)
+                    || buffer.closed.load(Ordering::Acquire)
+                    || !control.pause.load(Ordering::Acquire)
+            });
+            continue;
+        }
+        if control.paused.swap(false, Ordering::AcqRel) {
+            waiter.reset();
+        }
+
+        control.scans.fetch_add(1, Ordering::Relaxed);
+        let run = buffer.sequence_run();
+        if run > 0 {
+            idle_clock.busy();
+            control.sequenced.fetch_add(run as u64, Ordering::Relaxed);
+            control
+                .sequence
+                .store(buffer.next_seq.load(Ordering::Relaxed), Ordering::Relaxed);
+            waiter.reset();
+            next_idle_call = None;
+            continue;
+        }
+
+        // Run the idle hook once per interval of silence
+        if let Some(idle) = idle_hook.as_mut() {
+            let now = Instant::now();
+            let due = *next_idle_call.get_or_insert_with(|| {
+                last_sequenced = now;
+                now + idle.interval
+            });
+            if now >= due {
+                (idle.hook)(now - last_sequenced);
+                next_idle_call = Some(now + idle.interval);
             }
-            _ => {
-                // Free or Sequenced - nothing to do
-                std::hint::spin_loop();
+        }
+
+        // Skip a claim whose producer has not published within the timeout
+        let next = buffer.next_seq.load(Ordering::Relaxed) as usize & buffer.mask;
+        let mut skip_deadline = None;
+        if let Some(stuck) = stuck_claims.as_mut() {
+            match expire_claim(buffer, stuck, &mut claimed_at) {
+                Expiry::Skipped => {
+                    waiter.reset();
+                    continue;
+                }
+                Expiry::Due(deadline) => skip_deadline = Some(deadline),
+                Expiry::NotClaimed => {}
             }
         }
+
+        // Claimed (producer still writing), Free or Sequenced - nothing to do yet
+        idle_clock.idle();
+        let expiry = buffer.expire_events();
+        let refill = (buffer.overflowed() > 0).then(|| Instant::now() + REFILL_POLL);
+        let deadline = next_idle_call
+            .into_iter()
+            .chain(skip_deadline)
+            .chain(expiry)
+            .chain(refill)
+            .min();
+        waiter.wait(&buffer.publish_notifier, deadline, || {
+            control.stop.load(Ordering::Acquire)
+                || control.pause.load(Ordering::Acquire)
+                || buffer.closed.load(Ordering::Acquire)
+                || !buffer.publish_queue.is_empty()
+                || buffer.published.is_set(next)
+        });
+    }
+}
+
+/// What `expire_claim` found at the scan position
+enum Expiry {
+    /// The claim there had timed out, and was skipped
+    Skipped,
+    /// The claim there times out at this instant
+    Due(Instant),
+    NotClaimed,
+}
+
+/// Skip the claim at the scan position once its producer has held it for `stuck.timeout`
+/// without publishing. `claimed_at` remembers which sequence was first seen Claimed, and
+/// when, across calls.
+fn expire_claim<T, M>(
+    buffer: &Buffer<T, M>,
+    stuck: &mut StuckClaims,
+    claimed_at: &mut Option<(u64, Instant)>,
+) -> Expiry
+where
+    T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
+{
+    let next_seq = buffer.next_seq.load(Ordering::Relaxed);
+    let state = buffer.slots[next_seq as usize & buffer.mask]
+        .state
+        .load(Ordering::Acquire);
+    if state != SlotState::Claimed as u8 {
+        return Expiry::NotClaimed;
+    }
+    let now = Instant::now();
+    let since = match *claimed_at {
+        Some((sequence, since)) if sequence == next_seq => since,
+        _ => claimed_at.insert((next_seq, now)).1,
+    };
+    if now >= since + stuck.timeout && buffer.skip_claim(next_seq) {
+        if let Some(hook) = stuck.hook.as_mut() {
+            hook(next_seq);
+        }
+        return Expiry::Skipped;
+    }
+    Expiry::Due(since + stuck.timeout)
+}
+
+/// Sequence until the slot at the scan position is neither published nor being written.
+/// A claim held past the stuck-claim timeout is skipped here too, so a producer that
+/// unwound mid-push cannot hold up `close` or `stop_and_drain` for good.
+pub(crate) fn drain<T, M>(buffer: &Buffer<T, M>)
+where
+    T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
+{
+    // The sequencer loop has returned, so nothing else holds this
+    let mut stuck_claims = buffer
+        .stuck_claims
+        .as_ref()
+        .map(|stuck| stuck.lock().unwrap_or_else(|e| e.into_inner()));
+    let mut claimed_at = None;
+    loop {
+        if buffer.sequence_run() > 0 {
+            continue;
+        }
+        let next = buffer.next_seq.load(Ordering::Relaxed) as usize;
+        let state = buffer.slots[next & buffer.mask]
+            .state
+            .load(Ordering::Acquire);
+        // A Published slot here is only waiting for its producer to queue it
+        if state != SlotState::Claimed as u8 && state != SlotState::Published as u8 {
+            return;
+        }
+        if let Some(stuck) = stuck_claims.as_mut()
+            && let Expiry::Skipped = expire_claim(buffer, stuck, &mut claimed_at)
+        {
+            continue;
+        }
+        thread::yield_now();
     }
 }
 
@@ -87,7 +561,6 @@ mod tests {
     use super::*;
     use crate::buffer::Buffer;
     use std::thread;
-    use std::time::Duration;
 
     #[test]
     fn sequencer_assigns_monotonic_sequence() {
@@ -96,17 +569,14 @@ mod tests {
         // Manually publish some slots
         for i in 0..3 {
             let slot = &buffer.slots[i];
-            unsafe {
-                (*slot.payload.get()).write(100 + i as u64);
-                *slot.timestamp.get() = 1000 + i as u64;
-                *slot.producer_id.get() = 0;
-            }
+            slot.stage(100 + i as u64, 1000 + i as u64);
             slot.state
                 .store(SlotState::Published as u8, Ordering::Release);
+            buffer.published.set(i);
         }
 
         // Start sequencer
-        let handle = start_sequencer(buffer.clone());
+        let mut handle = start_sequencer(buffer.clone());
 
         // Wait for sequencer to process
         thread::sleep(Duration::from_millis(50));
@@ -139,17 +609,14 @@ mod tests {
         // But sequencer should process in slot order (0, 1, 2, 3)
         for i in (0..4).rev() {
             let slot = &buffer.slots[i];
-            unsafe {
-                (*slot.payload.get()).write(100 + i as u64);
-                *slot.timestamp.get() = 1000 + (3 - i) as u64; // Reverse timestamp too
-                *slot.producer_id.get() = 0;
-            }
+            slot.stage(100 + i as u64, 1000 + (3 - i) as u64); // Reverse timestamp too
             slot.state
                 .store(SlotState::Published as u8, Ordering::Release);
+            buffer.published.set(i);
         }
 
         // Start sequencer
-        let handle = start_sequencer(buffer.clone());
+        let mut handle = start_sequencer(buffer.clone());
 
         // Wait for sequencer to process
         thread::sleep(Duration::from_millis(50));
@@ -169,11 +636,371 @@ mod tests {
         handle.join().unwrap();
     }
 
+    #[test]
+    fn sequencer_sequences_full_lap_as_one_run() {
+        let buffer = Buffer::<u64>::builder().capacity(8).build().unwrap();
+        for i in 0..8 {
+            let slot = &buffer.slots[i];
+            slot.stage(i as u64, 0);
+            slot.state
+                .store(SlotState::Published as u8, Ordering::Release);
+            buffer.published.set(i);
+        }
+
+        let mut handle = start_sequencer(buffer.clone());
+        while buffer.next_seq.load(Ordering::Acquire) == 0 {
+            thread::yield_now();
+        }
+
+        // The whole lap became visible in a single step
+        assert_eq!(buffer.next_seq.load(Ordering::Acquire), 8);
+        for i in 0..8 {
+            assert_eq!(buffer.slots[i].sequence.load(Ordering::Acquire), i as u64);
+        }
+
+        handle.stop();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn parked_sequencer_wakes_on_publish_and_stop() {
+        use crate::wait::WaitStrategy;
+
+        let buffer = Buffer::<u64>::builder()
+            .capacity(16)
+            .sequencer_wait_strategy(WaitStrategy::Blocking)
+            .build()
+            .unwrap();
+        let mut handle = start_sequencer(buffer.clone());
+
+        // Give the sequencer time to park before anything is published
+        thread::sleep(Duration::from_millis(20));
+        buffer.producer().push(7).unwrap();
+
+        let mut consumer = buffer.consumer();
+        let event = consumer.next_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(event.payload, 7);
+
+        thread::sleep(Duration::from_millis(20));
+        handle.stop();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn pause_holds_events_until_resume() {
+        let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        let mut handle = start_sequencer(buffer.clone());
+        let producer = buffer.producer();
+
+        producer.push(1).unwrap();
+        while buffer.next_seq.load(Ordering::Acquire) < 1 {
+            thread::yield_now();
+        }
+
+        handle.pause();
+        assert!(handle.is_paused());
+        producer.push(2).unwrap();
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(buffer.next_seq.load(Ordering::Acquire), 1);
+
+        handle.resume();
+        while buffer.next_seq.load(Ordering::Acquire) < 2 {
+            thread::yield_now();
+        }
+        assert_eq!(buffer.slots[1].sequence.load(Ordering::Acquire), 1);
+        assert!(!handle.is_paused());
+
+        handle.stop();
+        handle.join().unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn sequencer_thread_is_named_and_pinned() {
+        let buffer = Buffer::<u64>::builder()
+            .capacity(16)
+            .sequencer_thread_name("seq-under-test")
+            .sequencer_core(0)
+            .build()
+            .unwrap();
+        let mut handle = buffer.try_start().unwrap();
+
+        let named = std::fs::read_dir("/proc/self/task").unwrap().any(|task| {
+            let comm = task.unwrap().path().join("comm");
+            std::fs::read_to_string(comm).is_ok_and(|name| name.trim() == "seq-under-test")
+        });
+        assert!(named);
+
+        handle.stop();
+        handle.join().unwrap();
+    }
+
+    /// Panics the first time it is asked to pick
+    struct PanicOnce(bool);
+
+    impl crate::policy::SequencerPolicy<u64> for PanicOnce {
+        fn window(&self) -> usize {
+            2
+        }
+
+        fn select(&mut self, _candidates: &[crate::policy::Candidate<'_, u64>]) -> usize {
+            if !self.0 {
+                self.0 = true;
+                panic!("policy failure");
+            }
+            0
+        }
+    }
+
+    #[test]
+    fn panicked_sequencer_is_reported_and_releases_consumers() {
+        let buffer = Buffer::<u64>::builder()
+            .capacity(16)
+            .sequencer_policy(PanicOnce(false))
+            .build()
+            .unwrap();
+        let mut handle = start_sequencer(buffer.clone());
+        let mut consumer = buffer.consumer();
+        buffer.producer().push(1).unwrap();
+
+        assert_eq!(consumer.blocking_iter().count(), 0);
+        assert!(handle.panicked());
+        assert!(handle.join().is_err());
+        assert!(!handle.is_running());
+    }
+
+    #[test]
+    fn sequencer_restarts_after_panic() {
+        let buffer = Buffer::<u64>::builder()
+            .capacity(16)
+            .sequencer_policy(PanicOnce(false))
+            .restart_sequencer_on_panic(true)
+            .build()
+            .unwrap();
+        let mut handle = start_sequencer(buffer.clone());
+        buffer.producer().push(1).unwrap();
+
+        let mut consumer = buffer.consumer();
+        let event = consumer.next_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!((event.sequence, event.payload), (0, 1));
+        assert_eq!(handle.restarts(), 1);
+        assert!(!handle.panicked());
+
+        handle.stop();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn join_timeout_reports_a_running_sequencer() {
+        let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        let mut handle = start_sequencer(buffer);
+
+        assert!(handle.join_timeout(Duration::from_millis(10)).is_err());
+        assert!(!handle.is_finished());
+
+        handle.stop();
+        handle.join_timeout(Duration::from_secs(5)).unwrap();
+        assert!(handle.is_finished());
+    }
+
+    #[test]
+    fn stop_and_drain_sequences_pending_events() {
+        let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        let mut handle = start_sequencer(buffer.clone());
+        handle.pause();
+
+        let producer = buffer.producer();
+        for i in 0..5 {
+            producer.push(i).unwrap();
+        }
+        assert_eq!(buffer.next_seq.load(Ordering::Acquire), 0);
+
+        handle.stop_and_drain();
+        handle.join().unwrap();
+        assert_eq!(buffer.next_seq.load(Ordering::Acquire), 5);
+    }
+
+    #[test]
+    fn idle_hook_runs_while_quiet() {
+        use crate::wait::WaitStrategy;
+        use std::sync::mpsc;
+
+        let (tx, rx) = mpsc::channel();
+        let buffer = Buffer::<u64>::builder()
+            .capacity(16)
+            .sequencer_wait_strategy(WaitStrategy::Blocking)
+            .on_sequencer_idle(Duration::from_millis(5), move |quiet| {
+                let _ = tx.send(quiet);
+            })
+            .build()
+            .unwrap();
+        let mut handle = start_sequencer(buffer.clone());
+
+        let quiet = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(quiet >= Duration::from_millis(5));
+        let quiet = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(quiet >= Duration::from_millis(10));
+
+        handle.stop();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn stuck_claim_is_skipped_after_timeout() {
+        use crate::slot::SlotState;
+        use std::sync::mpsc;
+
+        let (tx, rx) = mpsc::channel();
+        let buffer = Buffer::<u64>::builder()
+            .capacity(16)
+            .stuck_claim_timeout(Duration::from_millis(5))
+            .on_stuck_claim(move |sequence| {
+                let _ = tx.send(sequence);
+            })
+            .build()
+            .unwrap();
+        let producer = buffer.producer();
+        producer.push(1).unwrap();
+
+        // A producer that claims slot 1 and dies before publishing
+        buffer.slots[1]
+            .state
+            .store(SlotState::Claimed as u8, Ordering::Release);
+        buffer.slots[1].generation.fetch_add(1, Ordering::Relaxed);
+        buffer.head.fetch_add(1, Ordering::Release);
+        producer.push(3).unwrap();
+
+        let mut handle = start_sequencer(buffer.clone());
+        assert_eq!(rx.recv_timeout(Duration::from_secs(5)).unwrap(), 1);
+        while buffer.next_seq.load(Ordering::Acquire) < 3 {
+            thread::yield_now();
+        }
+        handle.stop();
+        handle.join().unwrap();
+
+        assert_eq!(buffer.skipped_claims(), 1);
+        let mut consumer = buffer.consumer();
+        let events: Vec<(u64, u64)> = consumer
+            .iter()
+            .map(|event| (event.sequence, event.payload))
+            .collect();
+        assert_eq!(events, vec![(0, 1), (2, 3)]);
+    }
+
+    /// Slot 1 claimed by a producer that died before publishing, between events 1 and 3
+    fn abandon_a_claim_between(buffer: &Arc<Buffer<u64>>) {
+        use crate::slot::SlotState;
+
+        let producer = buffer.producer();
+        producer.push(1).unwrap();
+        buffer.slots[1]
+            .state
+            .store(SlotState::Claimed as u8, Ordering::Release);
+        buffer.slots[1].generation.fetch_add(1, Ordering::Relaxed);
+        buffer.head.fetch_add(1, Ordering::Release);
+        producer.push(3).unwrap();
+    }
+
+    #[test]
+    fn stop_and_drain_skips_an_abandoned_claim() {
+        let buffer = Buffer::<u64>::builder()
+            .capacity(16)
+            .stuck_claim_timeout(Duration::from_millis(5))
+            .build()
+            .unwrap();
+        let mut handle = start_sequencer(buffer.clone());
+        handle.pause();
+        abandon_a_claim_between(&buffer);
+
+        handle.stop_and_drain();
+        handle.join_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(buffer.next_seq.load(Ordering::Acquire), 3);
+        assert_eq!(buffer.skipped_claims(), 1);
+    }
+
+    #[test]
+    fn a_skipped_slot_is_not_reused_until_its_producer_lets_go() {
+        use crate::error::PushError;
+        use crate::producer::{try_claim, Claim, OnFull};
+
+        let buffer = Buffer::<u64>::builder()
+            .capacity(2)
+            .on_full(OnFull::Fail)
+            .build()
+            .unwrap();
+        let Claim::Claimed(late) = try_claim(&buffer, None) else {
+            panic!("slot 0 is free");
+        };
+        assert!(buffer.skip_claim(0));
+        let producer = buffer.producer();
+        producer.push(1).unwrap();
+        buffer.sequence_available();
+        assert_eq!(buffer.next_seq.load(Ordering::Acquire), 2);
+
+        // The late producer may still be writing slot 0
+        assert_eq!(producer.push(2), Err(PushError::BufferFull));
+        late.slot.let_go();
+        producer.push(2).unwrap();
+        buffer.sequence_available();
+        let mut consumer = buffer.consumer();
+        let events: Vec<(u64, u64)> = consumer
+            .iter()
+            .map(|event| (event.sequence, event.payload))
+            .collect();
+        assert_eq!(events, vec![(1, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn stats_count_sequenced_events_and_idle_time() {
+        let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        let mut handle = start_sequencer(buffer.clone());
+        let producer = buffer.producer();
+        for i in 0..10 {
+            producer.push(i).unwrap();
+        }
+        while buffer.next_seq.load(Ordering::Acquire) < 10 {
+            thread::yield_now();
+        }
+        thread::sleep(Duration::from_millis(20));
+
+        let stats = handle.stats();
+        assert_eq!(stats.sequence, 10);
+        assert_eq!(stats.sequenced, 10);
+        assert!(stats.events_per_sec > 0.0);
+        assert!(stats.scans_per_event >= 0.1);
+        // Quiet for the last 20ms at least
+        assert!(stats.spinning >= Duration::from_millis(20));
+        assert!(stats.spinning <= stats.uptime);
+
+        handle.stop();
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn stop_is_idempotent_and_is_running_tracks_the_thread() {
+        let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
+        let mut handle = start_sequencer(buffer);
+        assert!(handle.is_running());
+
+        thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| handle.stop());
+            }
+        });
+        handle.stop();
+        handle.join().unwrap();
+        assert!(!handle.is_running());
+        assert!(!handle.panicked());
+
+        // Joining again is a no-op
+        handle.join().unwrap();
+    }
+
     #[test]
     fn sequencer_stops_on_signal() {
         let buffer = Buffer::<u64>::builder().capacity(16).build().unwrap();
 
-        let handle = start_sequencer(buffer);
+        let mut handle = start_sequencer(buffer);
 
         // Signal stop
         handle.stop();
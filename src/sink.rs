@@ -0,0 +1,280 @@
+use crate::consumer::{Consumer, Event};
+use crate::error::ConsumerError;
+use std::io::{self, Write};
+
+/// How a `Sink` frames each event
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SinkFormat {
+    /// One JSON object per line: `{"sequence":..,"timestamp":..,"producer_id":..,"payload":..}`
+    JsonLines,
+    /// Little-endian frames: `u32` frame length, `u64` sequence, `u64` timestamp,
+    /// `u8` producer id, then the payload bytes. The length covers everything after itself.
+    Binary,
+}
+
+/// Payloads a `Sink` knows how to encode
+pub trait SinkPayload {
+    /// Write the payload as a JSON value
+    fn write_json(&self, out: &mut dyn Write) -> io::Result<()>;
+
+    /// Append the payload's binary encoding to `out`
+    fn write_binary(&self, out: &mut Vec<u8>);
+}
+
+macro_rules! int_payload {
+    ($($ty:ty),*) => {$(
+        impl SinkPayload for $ty {
+            fn write_json(&self, out: &mut dyn Write) -> io::Result<()> {
+                write!(out, "{}", self)
+            }
+
+            fn write_binary(&self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+        }
+    )*};
+}
+
+int_payload!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+macro_rules! float_payload {
+    ($($ty:ty),*) => {$(
+        impl SinkPayload for $ty {
+            /// Non-finite values have no JSON form and are written as `null`
+            fn write_json(&self, out: &mut dyn Write) -> io::Result<()> {
+                if self.is_finite() {
+                    write!(out, "{}", self)
+                } else {
+                    write!(out, "null")
+                }
+            }
+
+            fn write_binary(&self, out: &mut Vec<u8>) {
+                out.extend_from_slice(&self.to_le_bytes());
+            }
+        }
+    )*};
+}
+
+float_payload!(f32, f64);
+
+impl SinkPayload for bool {
+    fn write_json(&self, out: &mut dyn Write) -> io::Result<()> {
+        write!(out, "{}", self)
+    }
+
+    fn write_binary(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+}
+
+/// Writes a consumer's events to any `io::Write`.
+///
+/// Events are written in batches of up to `batch_size`. The writer is flushed
+/// after every batch unless `flush_each_batch(false)` is set, in which case it is
+/// only flushed by `flush()` and when `run` returns. Lags are skipped over and
+/// counted rather than reported.
+pub struct Sink<T, W: Write, M = ()> {
+    consumer: Consumer<T, M>,
+    writer: W,
+    format: SinkFormat,
+    batch_size: usize,
+    flush_each_batch: bool,
+    /// Events lost to overruns
+    skipped: u64,
+    /// Scratch space for one binary frame
+    frame: Vec<u8>,
+}
+
+impl<T, W, M> Sink<T, W, M>
+where
+    T: Copy + Send + 'static + SinkPayload,
+    M: Copy + Send + 'static,
+    W: Write,
+{
+    pub(crate) fn new(consumer: Consumer<T, M>, writer: W, format: SinkFormat) -> Self {
+        Self {
+            consumer,
+            writer,
+            format,
+            batch_size: 64,
+            flush_each_batch: true,
+            skipped: 0,
+            frame: Vec::new(),
+        }
+    }
+
+    /// Write at most `batch_size` events between flushes
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Whether to flush the writer after every batch
+    pub fn flush_each_batch(mut self, flush: bool) -> Self {
+        self.flush_each_batch = flush;
+        self
+    }
+
+    /// Write everything currently sequenced, returning how many events were written
+    pub fn drain(&mut self) -> io::Result<u64> {
+        let mut written = 0;
+        loop {
+            let batch = self.write_available(self.batch_size)?;
+            if batch == 0 {
+                return Ok(written);
+            }
+            written += batch;
+            self.end_batch()?;
+        }
+    }
+
+    /// Write events as they arrive until the sequencer stops, returning how many were written
+    pub fn run(&mut self) -> io::Result<u64> {
+        let mut written = 0;
+        loop {
+            let first = match self.consumer.blocking_iter().next() {
+                None => break,
+                Some(Ok(event)) => event,
+                Some(Err(ConsumerError::Lagged { skipped })) => {
+                    self.skipped += skipped;
+                    continue;
+                }
+                Some(Err(err)) => return Err(io::Error::other(err)),
+            };
+            self.write_event(&first)?;
+            written += 1 + self.write_available(self.batch_size - 1)?;
+            self.end_batch()?;
+        }
+        self.writer.flush()?;
+        Ok(written)
+    }
+
+    /// Flush the underlying writer
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Number of events lost because the sink fell behind
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+
+    /// Get the consumer and writer back. Does not flush.
+    pub fn into_inner(self) -> (Consumer<T, M>, W) {
+        (self.consumer, self.writer)
+    }
+
+    /// Write up to `max` events that are available without waiting
+    fn write_available(&mut self, max: usize) -> io::Result<u64> {
+        let mut written = 0;
+        while written < max as u64 {
+            match self.consumer.try_next() {
+                Ok(Some(event)) => {
+                    self.write_event(&event)?;
+                    written += 1;
+                }
+                Ok(None) => break,
+                Err(ConsumerError::Lagged { skipped }) => self.skipped += skipped,
+                Err(err) => return Err(io::Error::other(err)),
+            }
+        }
+        Ok(written)
+    }
+
+    fn end_batch(&mut self) -> io::Result<()> {
+        if self.flush_each_batch {
+            self.writer.flush()?;
+        }
+        Ok(())
+    }
+
+    fn write_event(&mut self, event: &Event<T, M>) -> io::Result<()> {
+        match self.format {
+            SinkFormat::JsonLines => {
+                write!(
+                    self.writer,
+                    "{{\"sequence\":{},\"timestamp\":{},\"producer_id\":{},\"payload\":",
+                    event.sequence, event.timestamp, event.producer_id
+                )?;
+                event.payload.write_json(&mut self.writer)?;
+                self.writer.write_all(b"}\n")
+            }
+            SinkFormat::Binary => {
+                self.frame.clear();
+                self.frame.extend_from_slice(&[0; 4]);
+                self.frame.extend_from_slice(&event.sequence.to_le_bytes());
+                self.frame.extend_from_slice(&event.timestamp.to_le_bytes());
+                self.frame.push(event.producer_id);
+                event.payload.write_binary(&mut self.frame);
+
+                let len = (self.frame.len() - 4) as u32;
+                self.frame[..4].copy_from_slice(&len.to_le_bytes());
+                self.writer.write_all(&self.frame)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::Buffer;
+    use crate::conflate::sequenced;
+    use std::sync::atomic::Ordering;
+
+    #[test]
+    fn json_lines_one_object_per_event() {
+        let buffer = sequenced(&[7u32, 8]);
+        let mut sink = buffer.consumer().sink(Vec::new(), SinkFormat::JsonLines);
+        assert_eq!(sink.drain().unwrap(), 2);
+
+        let (_, out) = sink.into_inner();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("{\"sequence\":0,\"timestamp\":"));
+        assert!(lines[1].ends_with("\"producer_id\":0,\"payload\":8}"));
+    }
+
+    #[test]
+    fn binary_frames_are_length_prefixed() {
+        let buffer = sequenced(&[0xAABBCCDDu32]);
+        let mut sink = buffer
+            .consumer()
+            .sink(Vec::new(), SinkFormat::Binary)
+            .batch_size(1);
+        sink.drain().unwrap();
+
+        let (_, out) = sink.into_inner();
+        assert_eq!(out.len(), 4 + 8 + 8 + 1 + 4);
+        assert_eq!(u32::from_le_bytes(out[..4].try_into().unwrap()), 21);
+        assert_eq!(u64::from_le_bytes(out[4..12].try_into().unwrap()), 0);
+        assert_eq!(u32::from_le_bytes(out[21..].try_into().unwrap()), 0xAABBCCDD);
+    }
+
+    #[test]
+    fn run_returns_when_sequencer_stops() {
+        let buffer = Buffer::<u32>::builder().capacity(16).build().unwrap();
+        let mut handle = buffer.start();
+        let producer = buffer.producer();
+        let mut sink = buffer.consumer().sink(Vec::new(), SinkFormat::JsonLines);
+
+        let writer = std::thread::spawn(move || {
+            let written = sink.run().unwrap();
+            (written, sink.into_inner().1)
+        });
+        for i in 0..3 {
+            producer.push(i).unwrap();
+        }
+        while buffer.next_seq.load(Ordering::Acquire) < 3 {
+            std::thread::yield_now();
+        }
+        handle.stop();
+        handle.join().unwrap();
+
+        let (written, out) = writer.join().unwrap();
+        assert_eq!(written, 3);
+        assert_eq!(out.iter().filter(|&&b| b == b'\n').count(), 3);
+    }
+}
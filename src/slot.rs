@@ -1,7 +1,22 @@
+use crate::affinity;
+use crate::numa;
+#[cfg(not(feature = "sequence-32"))]
+use crate::sync::atomic::AtomicU64;
+use crate::sync::atomic::{AtomicU32, AtomicU8, Ordering};
+use crate::sync::{const_fn, zeroed_slice, UnsafeCell};
 use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
 use std::mem::MaybeUninit;
-use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::path::Path;
+use std::ptr::NonNull;
 
+/// Lifecycle of a ring slot.
+///
+/// Free only describes a slot that has never been used. A Sequenced slot is recycled
+/// straight back to Claimed by the next producer once every registered consumer has
+/// passed its sequence (see `Buffer::recyclable`), so the last `capacity` events stay
+/// readable for `peek`, seeks and weak consumers until they are actually overwritten.
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum SlotState {
@@ -11,43 +26,661 @@ pub enum SlotState {
     Sequenced = 3,
 }
 
-#[repr(C, align(64))]
-pub struct Slot<T> {
+/// Flag for a slot whose event must not be delivered: the sequencer gave up on its
+/// claim, or its push raced `Buffer::close`. It still takes a sequence number, and
+/// readers step over it.
+pub(crate) const SKIPPED: u8 = 1;
+
+/// Flag for a slot whose claim the sequencer skipped. Its producer may still be writing,
+/// so the slot stays the producer's, and is not reused, until it also has `LET_GO`.
+pub(crate) const EXPIRED: u8 = 4;
+
+/// Flag the producer of a claim sets once it is done with the slot without publishing
+/// it: its publish found the claim skipped, or it unwound first.
+pub(crate) const LET_GO: u8 = 8;
+
+#[cfg(not(feature = "sequence-32"))]
+type UserFlags = AtomicU32;
+#[cfg(feature = "sequence-32")]
+type UserFlags = AtomicU8;
+
+/// The payload sits at a multiple of `align_of::<T>()` whatever the layout, and a slot
+/// is aligned to the larger of that and its cache line, so over-aligned `T` (SIMD
+/// vectors, say) can be loaded with aligned instructions straight from the slot.
+///
+/// With `compact-slots` the compiler packs the fields instead, with no padding out to
+/// a cache line.
+#[cfg_attr(
+    all(not(feature = "align-128"), not(feature = "compact-slots")),
+    repr(C, align(64))
+)]
+#[cfg_attr(
+    all(feature = "align-128", not(feature = "compact-slots")),
+    repr(C, align(128))
+)]
+pub struct Slot<T, M = ()> {
     pub(crate) state: AtomicU8,
-    pub(crate) producer_id: std::cell::UnsafeCell<u8>,
-    _pad1: [u8; 6],
-    pub(crate) sequence: AtomicU64,
-    pub(crate) timestamp: std::cell::UnsafeCell<u64>,
-    pub(crate) payload: std::cell::UnsafeCell<MaybeUninit<T>>,
+    producer_id: UnsafeCell<u8>,
+    /// Written by whoever owns the slot before it becomes Sequenced: the claiming
+    /// producer, or the sequencer when it skips a stuck claim
+    pub(crate) flags: AtomicU8,
+    /// The producer's tags for the event, surfaced as `Event::flags`. A 32-bit sequence
+    /// leaves no room beside the checksum, so only the low 8 bits are kept, in the byte
+    /// that would otherwise pad the header.
+    #[cfg(feature = "sequence-32")]
+    user_flags: UserFlags,
+    #[cfg(not(feature = "sequence-32"))]
+    _pad1: [u8; 1],
+    /// Bumped by every claim, so it equals the lap of the sequence held plus one.
+    /// Readers check it to tell this lap's event from a stale one.
+    pub(crate) generation: AtomicU32,
+    pub(crate) sequence: SlotSequence,
+    /// CRC of the payload, with `BufferBuilder::checksums`. Beside a 32-bit sequence,
+    /// so the two share eight bytes.
+    #[cfg(all(feature = "sequence-32", not(feature = "compact-slots")))]
+    checksum: UnsafeCell<u32>,
+    timestamp: UnsafeCell<u64>,
+    /// As above, after the timestamp so it does not pad out the sequence
+    #[cfg(not(feature = "sequence-32"))]
+    checksum: UnsafeCell<u32>,
+    /// As above, all 32 bits, beside the checksum in the padding before an 8-byte
+    /// aligned payload
+    #[cfg(not(feature = "sequence-32"))]
+    user_flags: UserFlags,
+    /// The buffer's user header, written beside the payload
+    metadata: UnsafeCell<MaybeUninit<M>>,
+    payload: UnsafeCell<MaybeUninit<T>>,
 }
 
-// SAFETY: Slot<T> is Sync because:
-// 1. The state machine (Free -> Claimed -> Published -> Sequenced) ensures exclusive access
-// 2. Only the thread that transitions to Claimed can write to producer_id, timestamp, payload
+// SAFETY: Slot<T, M> is Sync because:
+// 1. The state machine (Free/Sequenced -> Claimed -> Published -> Sequenced) ensures exclusive access
+// 2. Only the thread that transitions to Claimed can write to producer_id, timestamp, metadata, payload
 // 3. Atomic operations with proper ordering (Acquire/Release) synchronize access
-// 4. Once Published/Sequenced, fields are read-only until recycled to Free
-unsafe impl<T: Send> Sync for Slot<T> {}
+// 4. Once Published/Sequenced, fields are read-only until a producer reclaims the slot
+unsafe impl<T: Send, M: Send> Sync for Slot<T, M> {}
+
+impl<T, M> Slot<T, M> {
+    const_fn! {
+        /// A Free slot. Every field starts at zero, so a zeroed allocation is one too.
+        pub fn new() -> Self {
+            Self {
+                state: AtomicU8::new(SlotState::Free as u8),
+                producer_id: UnsafeCell::new(0),
+                flags: AtomicU8::new(0),
+                #[cfg(not(feature = "sequence-32"))]
+                _pad1: [0; 1],
+                generation: AtomicU32::new(0),
+                sequence: SlotSequence::new(),
+                timestamp: UnsafeCell::new(0),
+                #[cfg(not(feature = "compact-slots"))]
+                checksum: UnsafeCell::new(0),
+                user_flags: UserFlags::new(0),
+                metadata: UnsafeCell::new(MaybeUninit::uninit()),
+                payload: UnsafeCell::new(MaybeUninit::uninit()),
+            }
+        }
+    }
+}
+
+impl<T, M> Slot<T, M> {
+    /// Whether this sequenced slot was skipped rather than published
+    pub(crate) fn is_skipped(&self) -> bool {
+        self.flags.load(Ordering::Relaxed) & SKIPPED != 0
+    }
+
+    /// Whether the producer of a skipped claim may still be writing to the slot.
+    /// Acquire, pairing with `let_go`, so a claim that finds it done sees its last writes.
+    pub(crate) fn is_held(&self) -> bool {
+        self.flags.load(Ordering::Acquire) & (EXPIRED | LET_GO) == EXPIRED
+    }
+
+    /// Hand the slot back after claiming it, for whoever reuses it once the claim is
+    /// skipped
+    pub(crate) fn let_go(&self) {
+        self.flags.fetch_or(LET_GO, Ordering::Release);
+    }
+
+    /// Write every byte of the contents once, so their pages are faulted in.
+    ///
+    /// SAFETY: nothing else may reach the slot yet, as while its ring is being built.
+    pub(crate) unsafe fn prefault(&self) {
+        // All-zero bytes are a valid `MaybeUninit`
+        unsafe {
+            std::ptr::write_volatile(self.metadata.get(), MaybeUninit::zeroed());
+            std::ptr::write_volatile(self.payload.get(), MaybeUninit::zeroed());
+        }
+    }
+
+    /// Exchange payload, metadata, timestamp, producer id, checksum and flags with `other`.
+    ///
+    /// SAFETY: the caller must have exclusive access to both slots' contents,
+    /// e.g. the sequencer while both are Published, and both must be initialized.
+    pub(crate) unsafe fn swap_contents(&self, other: &Self) {
+        unsafe {
+            std::ptr::swap(self.payload.get(), other.payload.get());
+            std::ptr::swap(self.metadata.get(), other.metadata.get());
+            std::ptr::swap(self.timestamp.get(), other.timestamp.get());
+            std::ptr::swap(self.producer_id.get(), other.producer_id.get());
+            #[cfg(not(feature = "compact-slots"))]
+            std::ptr::swap(self.checksum.get(), other.checksum.get());
+        }
+        let flags = self.flags.load(Ordering::Relaxed);
+        self.flags
+            .store(other.flags.swap(flags, Ordering::Relaxed), Ordering::Relaxed);
+        let user_flags = self.user_flags.load(Ordering::Relaxed);
+        self.user_flags
+            .store(other.user_flags.swap(user_flags, Ordering::Relaxed), Ordering::Relaxed);
+    }
+}
+
+/// A slot's contents copied out while a producer may be reclaiming the slot. The copy
+/// can be torn, so nothing in it means anything until the reader has checked, behind an
+/// Acquire fence, that the slot still holds the lap it was copied from.
+pub(crate) struct SlotCopy<T, M> {
+    pub(crate) payload: MaybeUninit<T>,
+    pub(crate) metadata: MaybeUninit<M>,
+    pub(crate) timestamp: u64,
+    pub(crate) producer_id: u8,
+    pub(crate) flags: u32,
+    pub(crate) checksum: Option<u32>,
+}
+
+impl<T, M> Slot<T, M> {
+    /// Copy the contents out with volatile reads, seqlock style: a producer that
+    /// reclaims the slot meanwhile can tear the copy, but the compiler cannot assume
+    /// the bytes hold still. See `Buffer::copy_slot` for the checks around it.
+    pub(crate) fn copy_volatile(&self) -> SlotCopy<T, M> {
+        // SAFETY: Every pointer is valid and aligned, and `MaybeUninit` and plain
+        // integers hold whatever bytes are there, torn or not
+        unsafe {
+            SlotCopy {
+                payload: std::ptr::read_volatile(self.payload.get()),
+                metadata: std::ptr::read_volatile(self.metadata.get()),
+                timestamp: std::ptr::read_volatile(self.timestamp.get()),
+                producer_id: std::ptr::read_volatile(self.producer_id.get()),
+                #[cfg(not(feature = "sequence-32"))]
+                flags: self.user_flags.load(Ordering::Relaxed),
+                #[cfg(feature = "sequence-32")]
+                flags: self.user_flags.load(Ordering::Relaxed).into(),
+                #[cfg(not(feature = "compact-slots"))]
+                checksum: Some(std::ptr::read_volatile(self.checksum.get())),
+                #[cfg(feature = "compact-slots")]
+                checksum: None,
+            }
+        }
+    }
+}
+
+/// Write access to a claimed slot's contents, held by whoever moved the slot to
+/// Claimed until it publishes it: a producer, or the sequencer refilling the ring.
+pub(crate) struct SlotWriteGuard<'a, T, M> {
+    slot: &'a Slot<T, M>,
+    written: bool,
+}
 
-impl<T> Slot<T> {
-    pub fn new() -> Self {
+impl<'a, T, M> SlotWriteGuard<'a, T, M> {
+    /// SAFETY: the caller must own the slot's contents while the guard lives, as the
+    /// thread that claimed it does, so nothing else reads or writes them.
+    pub(crate) unsafe fn new(slot: &'a Slot<T, M>) -> Self {
         Self {
-            state: AtomicU8::new(SlotState::Free as u8),
-            producer_id: std::cell::UnsafeCell::new(0),
-            _pad1: [0; 6],
-            sequence: AtomicU64::new(0),
-            timestamp: std::cell::UnsafeCell::new(0),
-            payload: std::cell::UnsafeCell::new(MaybeUninit::uninit()),
+            slot,
+            written: false,
+        }
+    }
+
+    pub(crate) fn write_payload(&mut self, payload: T) {
+        // SAFETY: The guard owns the contents
+        unsafe { (*self.slot.payload.get()).write(payload) };
+        self.written = true;
+    }
+
+    /// The payload, as the previous lap left it
+    pub(crate) fn payload_mut(&mut self) -> &mut MaybeUninit<T> {
+        // SAFETY: As above
+        unsafe { &mut *self.slot.payload.get() }
+    }
+
+    /// Vouch for a payload written through `payload_mut`
+    ///
+    /// SAFETY: the payload must be initialized.
+    pub(crate) unsafe fn assume_written(&mut self) {
+        self.written = true;
+    }
+
+    /// Write the event's header and checksum beside its payload, and flag the slot as
+    /// holding an event. Compact slots have no room for the checksum and drop it, and
+    /// with `sequence-32` only the low 8 bits of `flags` are kept.
+    ///
+    /// # Panics
+    ///
+    /// If the payload was not written, before touching the slot.
+    pub(crate) fn finish(
+        self,
+        metadata: M,
+        timestamp: u64,
+        producer_id: u8,
+        flags: u32,
+        checksum: Option<fn(&T) -> u32>,
+    ) {
+        assert!(self.written, "slot published without a payload");
+        let slot = self.slot;
+        slot.user_flags.store(flags as _, Ordering::Relaxed);
+        // SAFETY: The guard owns the contents, and the payload is initialized
+        unsafe {
+            (*slot.metadata.get()).write(metadata);
+            *slot.timestamp.get() = timestamp;
+            *slot.producer_id.get() = producer_id;
+            if let Some(checksum) = checksum {
+                let sum = checksum((*slot.payload.get()).assume_init_ref());
+                #[cfg(not(feature = "compact-slots"))]
+                {
+                    *slot.checksum.get() = sum;
+                }
+                #[cfg(feature = "compact-slots")]
+                let _ = sum;
+            }
         }
     }
 }
 
-impl<T> Default for Slot<T> {
+/// Read access to a written slot's contents: Published, which only the sequencer
+/// touches, or Sequenced, until a producer reclaims it.
+pub(crate) struct SlotReadGuard<'a, T, M> {
+    slot: &'a Slot<T, M>,
+}
+
+impl<'a, T, M> SlotReadGuard<'a, T, M> {
+    /// SAFETY: the slot must hold a written event, and nothing may rewrite it while the
+    /// guard or anything read through it lives. Where a producer may reclaim the slot
+    /// regardless, the caller must copy the event out and throw the copy away unless
+    /// the slot's generation shows it was not reclaimed meanwhile.
+    pub(crate) unsafe fn new(slot: &'a Slot<T, M>) -> Self {
+        Self { slot }
+    }
+
+    pub(crate) fn payload(&self) -> &'a T {
+        // SAFETY: The event is written and stays put, as the caller of `new` promised
+        unsafe { (*self.slot.payload.get()).assume_init_ref() }
+    }
+
+    pub(crate) fn metadata(&self) -> M
+    where
+        M: Copy,
+    {
+        // SAFETY: As above
+        unsafe { (*self.slot.metadata.get()).assume_init_read() }
+    }
+
+    pub(crate) fn timestamp(&self) -> u64 {
+        // SAFETY: As above
+        unsafe { *self.slot.timestamp.get() }
+    }
+
+    pub(crate) fn producer_id(&self) -> u8 {
+        // SAFETY: As above
+        unsafe { *self.slot.producer_id.get() }
+    }
+
+    pub(crate) fn flags(&self) -> u32 {
+        #[cfg(not(feature = "sequence-32"))]
+        return self.slot.user_flags.load(Ordering::Relaxed);
+        #[cfg(feature = "sequence-32")]
+        return self.slot.user_flags.load(Ordering::Relaxed).into();
+    }
+
+    /// The checksum taken when the event was written, or `None` for compact slots
+    pub(crate) fn checksum(&self) -> Option<u32> {
+        #[cfg(not(feature = "compact-slots"))]
+        // SAFETY: As above
+        return Some(unsafe { *self.slot.checksum.get() });
+        #[cfg(feature = "compact-slots")]
+        return None;
+    }
+}
+
+#[cfg(test)]
+impl<T, M: Default> Slot<T, M> {
+    /// Write `payload` as producer 0 would have at `timestamp`, for tests that stage
+    /// the ring by hand. The caller still sets the state and sequence.
+    pub(crate) fn stage(&self, payload: T, timestamp: u64) {
+        // SAFETY: Tests stage slots before any producer, sequencer or consumer reaches them
+        let mut contents = unsafe { SlotWriteGuard::new(self) };
+        contents.write_payload(payload);
+        contents.finish(M::default(), timestamp, 0, 0, None);
+    }
+}
+
+/// The sequence number a slot holds. With `sequence-32` only its low 32 bits are
+/// stored; claims tell the slot's lap from its generation.
+#[derive(Debug)]
+pub(crate) struct SlotSequence {
+    #[cfg(not(feature = "sequence-32"))]
+    value: AtomicU64,
+    #[cfg(feature = "sequence-32")]
+    value: AtomicU32,
+}
+
+impl SlotSequence {
+    const_fn! {
+        fn new() -> Self {
+            Self {
+                #[cfg(not(feature = "sequence-32"))]
+                value: AtomicU64::new(0),
+                #[cfg(feature = "sequence-32")]
+                value: AtomicU32::new(0),
+            }
+        }
+    }
+
+    pub(crate) fn store(&self, sequence: u64, order: Ordering) {
+        #[cfg(not(feature = "sequence-32"))]
+        self.value.store(sequence, order);
+        #[cfg(feature = "sequence-32")]
+        self.value.store(sequence as u32, order);
+    }
+
+    /// The stored sequence number; only its low 32 bits with `sequence-32`
+    pub(crate) fn load(&self, order: Ordering) -> u64 {
+        #[cfg(not(feature = "sequence-32"))]
+        return self.value.load(order);
+        #[cfg(feature = "sequence-32")]
+        return self.value.load(order).into();
+    }
+}
+
+/// Version of the ring file layout, in its header
+const RING_FILE_VERSION: u32 = 1;
+
+/// Bytes of a ring file header that describe its layout and must match to reopen it:
+/// the magic `LFTESRNG`, then as little-endian integers the version, slot size and
+/// alignment (`u32`s), and the capacity, payload size and metadata size (`u64`s)
+const RING_LAYOUT_LEN: usize = 44;
+
+/// The layout, then a `u32` that is 1 once the ring was closed cleanly and 0 while it
+/// is open, then the `u64` sequence it resumes from
+const RING_HEADER_LEN: usize = RING_LAYOUT_LEN + 12;
+
+/// Ring storage: allocated by the builder, or borrowed from a `StaticBuffer`
+pub(crate) enum Slots<T, M = ()> {
+    Heap(Box<[Slot<T, M>]>),
+    /// Points into a `'static` array, kept as a pointer so `Buffer<T, M>` needs no `'static` bounds
+    #[cfg_attr(loom, allow(dead_code))]
+    Static(NonNull<[Slot<T, M>]>),
+    /// An anonymous mapping, on huge pages or bound to a NUMA node, `len` bytes long,
+    /// unmapped on drop
+    Mapped {
+        slots: NonNull<[Slot<T, M>]>,
+        len: usize,
+    },
+    /// A shared mapping of a ring file, `len` bytes long: a page of header, then the
+    /// slots. Unmapped on drop.
+    File {
+        slots: NonNull<[Slot<T, M>]>,
+        map: NonNull<u8>,
+        len: usize,
+        /// Holds the file's lock for as long as it is mapped
+        _file: File,
+    },
+}
+
+// SAFETY: Every variant hands out shared access to the slots, exactly like
+// `Box<[Slot<T, M>]>` and `&'static [Slot<T, M>]`, which are Send and Sync when T and M are Send
+unsafe impl<T: Send, M: Send> Send for Slots<T, M> {}
+unsafe impl<T: Send, M: Send> Sync for Slots<T, M> {}
+
+impl<T, M> std::ops::Deref for Slots<T, M> {
+    type Target = [Slot<T, M>];
+
+    fn deref(&self) -> &[Slot<T, M>] {
+        match self {
+            Slots::Heap(slots) => slots,
+            // SAFETY: Built from a `&'static [Slot<T, M>]`, so it is valid for as long as we are
+            Slots::Static(slots) => unsafe { slots.as_ref() },
+            // SAFETY: `allocate` initialized every slot, and the mapping lives until drop
+            Slots::Mapped { slots, .. } => unsafe { slots.as_ref() },
+            // SAFETY: `map_file` left every slot valid, and the mapping lives until drop
+            Slots::File { slots, .. } => unsafe { slots.as_ref() },
+        }
+    }
+}
+
+impl<T, M> Slots<T, M> {
+    /// `capacity` free slots: on huge pages if the `huge-pages` feature is on and the
+    /// ring fills one, and with pages taken from `node` if given. Only a failure to
+    /// place the ring on `node` is an error; huge pages fall back to the heap. So do
+    /// slots aligned to more than a page, which a mapping cannot guarantee.
+    ///
+    /// Rings of a huge page and up are always mapped: a new mapping is zeroed pages the
+    /// OS only faults in when first touched, while the allocator zeroes cache-aligned
+    /// memory by hand, so building a multi-GB ring would write every byte of it. So are
+    /// smaller ones with `own_pages`, which then share no page with another allocation.
+    pub(crate) fn allocate(
+        capacity: usize,
+        node: Option<usize>,
+        own_pages: bool,
+    ) -> io::Result<Self> {
+        let bytes = capacity * size_of::<Slot<T, M>>();
+        let large = bytes >= affinity::HUGE_PAGE;
+        let huge = cfg!(feature = "huge-pages") && large;
+        let mappable = align_of::<Slot<T, M>>() <= affinity::PAGE;
+        if !mappable && node.is_some() {
+            return Err(io::ErrorKind::InvalidInput.into());
+        }
+        // SAFETY: A new slot is all zero bytes
+        let heap = || Slots::Heap(unsafe { zeroed_slice(capacity, Slot::new) });
+        if !(large || node.is_some() || own_pages) || !mappable {
+            return Ok(heap());
+        }
+        let Some((ptr, len)) = affinity::map_anonymous(bytes, huge) else {
+            if node.is_some() {
+                return Err(io::Error::last_os_error());
+            }
+            return Ok(heap());
+        };
+        // Bind before anything faults the pages in
+        if let Some(node) = node
+            && let Err(err) = numa::bind(ptr, len, node)
+        {
+            affinity::unmap(ptr, len);
+            return Err(err);
+        }
+        // The mapping is zeroed, and so already holds free slots
+        let first = ptr.cast::<Slot<T, M>>();
+        #[cfg(loom)]
+        for index in 0..capacity {
+            // SAFETY: The mapping is at least `bytes` long and page-aligned
+            unsafe { first.add(index).write(Slot::new()) };
+        }
+        Ok(Slots::Mapped {
+            slots: NonNull::slice_from_raw_parts(first, capacity),
+            len,
+        })
+    }
+
+    /// `capacity` slots in a shared mapping of the ring file at `path`, and the sequence
+    /// to resume from. A file `close_file` marked clean keeps its sequenced events, and
+    /// its other slots are freed so their positions are claimed afresh; a new file, or
+    /// one whose last owner died with it open, starts out empty. A file that is not a
+    /// ring file, is laid out for another capacity or slot type, or was closed at the
+    /// wrong length is `InvalidData` and left as it is; one that another handle holds
+    /// is `WouldBlock`.
+    pub(crate) fn map_file(path: &Path, capacity: usize) -> io::Result<(Self, u64)> {
+        if cfg!(loom) {
+            return Err(io::ErrorKind::Unsupported.into());
+        }
+        if align_of::<Slot<T, M>>() > affinity::PAGE {
+            return Err(io::ErrorKind::InvalidInput.into());
+        }
+        let len = ring_file_len::<T, M>(capacity).ok_or(io::ErrorKind::InvalidInput)?;
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        affinity::lock_file(&file)?;
+        let layout = ring_layout::<T, M>(capacity);
+
+        // Only an empty file, new or left so, or a ring file laid out like this one is
+        // ever written to; anything else is someone else's
+        let mut resume = None;
+        let file_len = file.metadata()?.len();
+        if file_len > 0 {
+            let mut header = [0; RING_HEADER_LEN];
+            file.read_exact(&mut header).map_err(|_| io::ErrorKind::InvalidData)?;
+            if header[..RING_LAYOUT_LEN] != layout {
+                return Err(io::ErrorKind::InvalidData.into());
+            }
+            let clean = u32::from_le_bytes(header[RING_LAYOUT_LEN..][..4].try_into().unwrap());
+            let next_seq = u64::from_le_bytes(header[RING_LAYOUT_LEN + 4..].try_into().unwrap());
+            if clean == 1 {
+                // Mapping a closed ring file that is the wrong length would read past it
+                if file_len != len as u64 {
+                    return Err(io::ErrorKind::InvalidData.into());
+                }
+                resume = Some(next_seq);
+            }
+        }
+        if resume.is_none() {
+            // Truncating first turns whatever was there into zero bytes, and so free
+            // slots. The header goes in before the file is sized, so a crash in between
+            // leaves a ring file marked open, which starts over, not one with no header.
+            file.set_len(0)?;
+            file.seek(SeekFrom::Start(0))?;
+            file.write_all(&ring_header(&layout, 0, 0))?;
+            file.set_len(len as u64)?;
+        }
+        let next_seq = resume.unwrap_or(0);
+
+        let map = affinity::map_file(&file, len)?;
+        // SAFETY: The mapping is `len` bytes, at least a page of header plus the slots,
+        // and page-aligned, which is enough for a slot
+        let first = unsafe { map.add(affinity::PAGE) }.cast::<Slot<T, M>>();
+        let slots = Slots::File {
+            slots: NonNull::slice_from_raw_parts(first, capacity),
+            map,
+            len,
+            _file: file,
+        };
+        if resume.is_some() {
+            slots.free_unsequenced(next_seq);
+        }
+        // Marked open until `close_file`, so a crash leaves it to start over
+        slots.write_header(&layout, 0, next_seq)?;
+        Ok((slots, next_seq))
+    }
+
+    /// Free every slot that is not Sequenced, with the generation its next claim, at
+    /// or after `next_seq`, expects to find
+    fn free_unsequenced(&self, next_seq: u64) {
+        let shift = self.len().trailing_zeros();
+        let mask = self.len() as u64 - 1;
+        for (index, slot) in self.iter().enumerate() {
+            if slot.state.load(Ordering::Relaxed) == SlotState::Sequenced as u8 {
+                // No producer outlives the run before, so none holds a skipped claim
+                slot.let_go();
+                continue;
+            }
+            let position = next_seq + ((index as u64).wrapping_sub(next_seq) & mask);
+            slot.generation.store((position >> shift) as u32, Ordering::Relaxed);
+            slot.flags.store(0, Ordering::Relaxed);
+            slot.state.store(SlotState::Free as u8, Ordering::Relaxed);
+        }
+    }
+
+    /// Write a ring file's slots back to disk, then mark it closed cleanly with
+    /// `next_seq` as the sequence to resume from. Does nothing for other rings.
+    pub(crate) fn close_file(&self, next_seq: u64) -> io::Result<()> {
+        if let Slots::File { map, len, .. } = self {
+            affinity::sync_mapping(*map, *len)?;
+            self.write_header(&ring_layout::<T, M>(self.len()), 1, next_seq)?;
+        }
+        Ok(())
+    }
+
+    fn write_header(
+        &self,
+        layout: &[u8; RING_LAYOUT_LEN],
+        clean: u32,
+        next_seq: u64,
+    ) -> io::Result<()> {
+        let Slots::File { map, .. } = self else {
+            return Ok(());
+        };
+        let header = ring_header(layout, clean, next_seq);
+        // SAFETY: The header page is ours alone; slots start a page in
+        unsafe { std::ptr::copy_nonoverlapping(header.as_ptr(), map.as_ptr(), RING_HEADER_LEN) };
+        affinity::sync_mapping(*map, affinity::PAGE)
+    }
+
+    /// Bytes the ring takes up: the whole mapping for mapped rings, rounded up to pages
+    pub(crate) fn memory_usage(&self) -> usize {
+        match self {
+            Slots::Mapped { len, .. } | Slots::File { len, .. } => *len,
+            _ => size_of_val::<[Slot<T, M>]>(self),
+        }
+    }
+
+    /// Address and length in bytes of the whole ring, for `mlock`
+    pub(crate) fn byte_range(&self) -> (*const u8, usize) {
+        (self.as_ptr().cast(), size_of_val::<[Slot<T, M>]>(self))
+    }
+}
+
+impl<T, M> Drop for Slots<T, M> {
+    fn drop(&mut self) {
+        match self {
+            Slots::Mapped { slots, len } => affinity::unmap(slots.cast(), *len),
+            Slots::File { map, len, .. } => affinity::unmap(*map, *len),
+            _ => {}
+        }
+    }
+}
+
+/// The layout part of a ring file header for `capacity` slots of `Slot<T, M>`
+fn ring_layout<T, M>(capacity: usize) -> [u8; RING_LAYOUT_LEN] {
+    let mut layout = [0; RING_LAYOUT_LEN];
+    layout[..8].copy_from_slice(b"LFTESRNG");
+    layout[8..12].copy_from_slice(&RING_FILE_VERSION.to_le_bytes());
+    layout[12..16].copy_from_slice(&(size_of::<Slot<T, M>>() as u32).to_le_bytes());
+    layout[16..20].copy_from_slice(&(align_of::<Slot<T, M>>() as u32).to_le_bytes());
+    layout[20..28].copy_from_slice(&(capacity as u64).to_le_bytes());
+    layout[28..36].copy_from_slice(&(size_of::<T>() as u64).to_le_bytes());
+    layout[36..44].copy_from_slice(&(size_of::<M>() as u64).to_le_bytes());
+    layout
+}
+
+/// A ring file header: `layout`, then `clean` and `next_seq`
+fn ring_header(layout: &[u8; RING_LAYOUT_LEN], clean: u32, next_seq: u64) -> [u8; RING_HEADER_LEN] {
+    let mut header = [0; RING_HEADER_LEN];
+    header[..RING_LAYOUT_LEN].copy_from_slice(layout);
+    header[RING_LAYOUT_LEN..][..4].copy_from_slice(&clean.to_le_bytes());
+    header[RING_LAYOUT_LEN + 4..].copy_from_slice(&next_seq.to_le_bytes());
+    header
+}
+
+/// Bytes in a ring file of `capacity` slots: a page of header, then the slots, rounded
+/// up to whole pages. `None` if that does not fit in a `usize`.
+pub(crate) fn ring_file_len<T, M>(capacity: usize) -> Option<usize> {
+    capacity
+        .checked_mul(size_of::<Slot<T, M>>())?
+        .checked_add(affinity::PAGE)?
+        .checked_next_multiple_of(affinity::PAGE)
+}
+
+impl<T, M> fmt::Debug for Slots<T, M> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T, M> Default for Slot<T, M> {
     fn default() -> Self {
         Self::new()
     }
 }
 
-impl<T> fmt::Debug for Slot<T> {
+impl<T, M> fmt::Debug for Slot<T, M> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Slot")
             .field("state", &self.state.load(Ordering::Relaxed))
@@ -55,6 +688,7 @@ impl<T> fmt::Debug for Slot<T> {
                 "producer_id",
                 unsafe { &*self.producer_id.get() },
             )
+            .field("generation", &self.generation.load(Ordering::Relaxed))
             .field("sequence", &self.sequence.load(Ordering::Relaxed))
             .field("timestamp", unsafe { &*self.timestamp.get() })
             .finish_non_exhaustive()
@@ -64,6 +698,8 @@ impl<T> fmt::Debug for Slot<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    #[cfg(not(feature = "compact-slots"))]
+    use crate::pad::CACHE_LINE;
 
     #[test]
     fn slot_state_values_are_correct() {
@@ -74,16 +710,103 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "compact-slots"))]
     fn slot_is_cache_line_aligned() {
-        assert_eq!(std::mem::align_of::<Slot<u64>>(), 64);
+        assert_eq!(std::mem::align_of::<Slot<u64>>(), CACHE_LINE);
     }
 
     #[test]
+    #[cfg(not(feature = "compact-slots"))]
     fn slot_size_with_payload() {
-        // Slot should be cache-line aligned (64 bytes minimum)
-        // With small payload like u64, it should still be 64 bytes
+        // Slot should be cache-line aligned (64 bytes minimum, 128 with `align-128`)
+        // With small payload like u64, it should still be one line
         let size = std::mem::size_of::<Slot<u64>>();
-        assert!(size >= 64, "Slot size {} should be at least 64 bytes", size);
-        assert_eq!(size % 64, 0, "Slot size {} should be multiple of 64 bytes", size);
+        assert!(size >= CACHE_LINE, "Slot size {} should be at least {} bytes", size, CACHE_LINE);
+        assert_eq!(size % CACHE_LINE, 0, "Slot size {} should be multiple of {} bytes", size, CACHE_LINE);
+    }
+
+    #[test]
+    #[cfg(not(feature = "compact-slots"))]
+    fn metadata_shares_the_payload_cache_line() {
+        // A u64 header beside a u64 payload still fits one line, with no wrapper struct
+        assert_eq!(std::mem::size_of::<Slot<u64, u64>>(), CACHE_LINE);
+        assert_eq!(std::mem::size_of::<Slot<u64, ()>>(), CACHE_LINE);
+    }
+
+    #[test]
+    fn ring_file_length_overflow_is_caught() {
+        assert_eq!(ring_file_len::<u64, ()>(8), Some(affinity::PAGE * 2));
+        assert_eq!(ring_file_len::<[u8; 1 << 40], ()>(1 << 30), None);
+    }
+
+    #[test]
+    #[cfg(not(feature = "compact-slots"))]
+    fn sequence_32_shrinks_the_header() {
+        // Flags and generation, sequence, timestamp and checksum, then the payload
+        let header = std::mem::offset_of!(Slot<u64>, payload);
+        assert_eq!(header, if cfg!(feature = "sequence-32") { 24 } else { 32 });
+        // User flags sit in padding either way: the header's spare byte, or the word
+        // between the checksum and an 8-byte payload
+        if cfg!(feature = "sequence-32") {
+            assert_eq!(std::mem::offset_of!(Slot<u32>, payload), 24);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "compact-slots")]
+    fn compact_slots_are_packed() {
+        // Header bytes with 8-bit user flags, generation, 32-bit sequence, timestamp and
+        // payload; no padding
+        assert_eq!(std::mem::size_of::<Slot<u32>>(), 24);
+        assert_eq!(std::mem::size_of::<Slot<u64>>(), 32);
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    #[repr(align(32))]
+    struct Simd([f32; 8]);
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    #[repr(align(8192))]
+    struct PageAligned(u64);
+
+    #[test]
+    fn over_aligned_payloads_are_aligned_in_every_slot() {
+        fn check<T>(slots: &Slots<T>) {
+            assert!(align_of::<Slot<T>>() >= align_of::<T>());
+            for slot in slots.iter() {
+                assert!(slot.payload.get().is_aligned());
+            }
+        }
+        check(&Slots::<Simd>::allocate(8, None, false).unwrap());
+        check(&Slots::<PageAligned>::allocate(4, None, false).unwrap());
+        #[cfg(not(feature = "compact-slots"))]
+        assert_eq!(std::mem::offset_of!(Slot<Simd>, payload) % 32, 0);
+    }
+
+    #[test]
+    fn slots_aligned_past_a_page_are_not_mapped() {
+        // Big enough to map on huge pages, which only promise page alignment
+        let slots = Slots::<PageAligned>::allocate(affinity::HUGE_PAGE / 8192, None, false);
+        let slots = slots.unwrap();
+        assert!(matches!(slots, Slots::Heap(_)));
+        assert!(slots.as_ptr().is_aligned());
+        let node = Slots::<PageAligned>::allocate(4, Some(0), false);
+        assert_eq!(node.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn publishing_without_a_payload_panics_before_flagging_the_slot() {
+        let slots = Slots::<u64>::allocate(2, None, false).unwrap();
+        let finished = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            // SAFETY: Nothing else can reach the slots
+            unsafe { SlotWriteGuard::new(&slots[0]) }.finish((), 1, 0, 0, None)
+        }));
+        assert!(finished.is_err());
+        assert_eq!(slots[0].flags.load(Ordering::Relaxed), 0);
+
+        slots[1].stage(7, 1);
+        // SAFETY: Slot 1 was just written and nothing rewrites it
+        let contents = unsafe { SlotReadGuard::new(&slots[1]) };
+        assert_eq!((*contents.payload(), contents.timestamp()), (7, 1));
     }
 }
@@ -0,0 +1,330 @@
+use crate::checksum::Crc32;
+use crate::persist::LogPayload;
+use crate::replay::ReplayConsumer;
+use std::fs::{self, File};
+use std::hash::Hasher;
+use std::io::{self, Write};
+use std::marker::PhantomData;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// First bytes of every snapshot file, versioned like log segments
+const SNAPSHOT_HEADER: [u8; 12] = *b"LFTESSNP\x01\0\0\0";
+
+/// Aggregate state a `Snapshotter` can save and load back
+pub trait SnapshotState: Sized {
+    /// Append the state's encoding to `out`
+    fn write_snapshot(&self, out: &mut Vec<u8>);
+
+    /// The state `bytes` encode, or `None` if they are not a valid encoding
+    fn read_snapshot(bytes: &[u8]) -> Option<Self>;
+}
+
+/// A saved state and the last sequence applied to it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot<S> {
+    pub sequence: u64,
+    pub state: S,
+}
+
+/// Saves an aggregate's state every so often, tagged with the last sequence applied to
+/// it, so a restart loads the newest snapshot and replays only the events after it
+/// rather than the whole log.
+///
+/// Call `applied` after applying each event; it saves once `event_interval` events
+/// (10,000 by default) or `time_interval` have gone by since the last snapshot. Each
+/// snapshot is a file in the directory named for its sequence
+/// (`00000000000000000042.snap`): `LFTESSNP` and a `u32` version, then the `u64`
+/// sequence, the `SnapshotState::write_snapshot` bytes and a `u32` CRC-32 of both. It
+/// is written to a temporary file, fsynced and renamed into place. Then snapshots older
+/// than it are deleted once `keep` (2 by default) are left counting it and any newer,
+/// as are any that are not intact; the one just written is always kept, even if a
+/// newer one exists. `load` takes the newest that is intact, so a damaged snapshot
+/// falls back to the one before it.
+pub struct Snapshotter<S> {
+    dir: PathBuf,
+    event_interval: u64,
+    time_interval: Option<Duration>,
+    keep: usize,
+    /// Events applied since the last snapshot
+    applied: u64,
+    last_save: Instant,
+    /// Encoding buffer, reused across snapshots
+    bytes: Vec<u8>,
+    _state: PhantomData<fn() -> S>,
+}
+
+impl<S: SnapshotState> Snapshotter<S> {
+    /// Keep snapshots in `dir`, creating it if needed
+    pub fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            event_interval: 10_000,
+            time_interval: None,
+            keep: 2,
+            applied: 0,
+            last_save: Instant::now(),
+            bytes: Vec::new(),
+            _state: PhantomData,
+        })
+    }
+
+    /// Save after this many applied events (10,000 by default)
+    pub fn event_interval(mut self, events: u64) -> Self {
+        self.event_interval = events.max(1);
+        self
+    }
+
+    /// Also save once this long has passed since the last snapshot, checked as events
+    /// are applied (off by default)
+    pub fn time_interval(mut self, interval: Duration) -> Self {
+        self.time_interval = Some(interval);
+        self
+    }
+
+    /// Keep this many snapshots (2 by default, at least 1)
+    pub fn keep(mut self, count: usize) -> Self {
+        self.keep = count.max(1);
+        self
+    }
+
+    /// Note that the event `sequence` has been applied to `state`, saving a snapshot if
+    /// one is due. Returns whether it saved.
+    pub fn applied(&mut self, sequence: u64, state: &S) -> io::Result<bool> {
+        self.applied += 1;
+        let due = self.applied >= self.event_interval
+            || self.time_interval.is_some_and(|interval| self.last_save.elapsed() >= interval);
+        if due {
+            self.save(sequence, state)?;
+        }
+        Ok(due)
+    }
+
+    /// Save `state`, with every event up to `sequence` applied to it, now
+    pub fn save(&mut self, sequence: u64, state: &S) -> io::Result<()> {
+        self.bytes.clear();
+        self.bytes.extend_from_slice(&SNAPSHOT_HEADER);
+        self.bytes.extend_from_slice(&sequence.to_le_bytes());
+        state.write_snapshot(&mut self.bytes);
+        let mut crc = Crc32::new();
+        crc.write(&self.bytes[SNAPSHOT_HEADER.len()..]);
+        self.bytes.extend_from_slice(&(crc.finish() as u32).to_le_bytes());
+
+        let path = self.dir.join(format!("{:020}.snap", sequence));
+        let tmp = path.with_extension("snap.tmp");
+        let mut file = File::create(&tmp)?;
+        file.write_all(&self.bytes)?;
+        file.sync_all()?;
+        fs::rename(&tmp, &path)?;
+        // The new name is only durable once the directory is
+        #[cfg(unix)]
+        File::open(&self.dir)?.sync_all()?;
+
+        let mut kept = 1;
+        for (other, old) in self.snapshots()?.into_iter().rev() {
+            if other == sequence {
+                continue;
+            }
+            let intact = fs::read(&old).is_ok_and(|bytes| Self::decode(other, &bytes).is_some());
+            if !intact || (other < sequence && kept >= self.keep) {
+                fs::remove_file(&old)?;
+            } else {
+                kept += 1;
+            }
+        }
+        self.applied = 0;
+        self.last_save = Instant::now();
+        Ok(())
+    }
+
+    /// The newest intact snapshot, or `None` if there is none
+    pub fn load(&self) -> io::Result<Option<Snapshot<S>>> {
+        for (sequence, path) in self.snapshots()?.into_iter().rev() {
+            if let Some(state) = Self::decode(sequence, &fs::read(&path)?) {
+                return Ok(Some(Snapshot { sequence, state }));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The state the snapshot file `bytes` for `sequence` holds, or `None` if it is
+    /// not intact
+    fn decode(sequence: u64, bytes: &[u8]) -> Option<S> {
+        let body = bytes.strip_prefix(&SNAPSHOT_HEADER)?;
+        let (body, stored) = body.split_last_chunk::<4>()?;
+        let mut crc = Crc32::new();
+        crc.write(body);
+        if *stored != (crc.finish() as u32).to_le_bytes()
+            || body.get(..8) != Some(&sequence.to_le_bytes()[..])
+        {
+            return None;
+        }
+        S::read_snapshot(&body[8..])
+    }
+
+    /// Pick up where the last run left off: load the newest snapshot and move `replay`
+    /// on to the first event it does not cover. Without a snapshot, `replay` stays put.
+    pub fn resume<T, M>(
+        &self,
+        replay: &mut ReplayConsumer<T, M>,
+    ) -> io::Result<Option<Snapshot<S>>>
+    where
+        T: LogPayload,
+        M: Default,
+    {
+        let snapshot = self.load()?;
+        if let Some(snapshot) = &snapshot {
+            replay.seek(snapshot.sequence + 1).map_err(io::Error::other)?;
+        }
+        Ok(snapshot)
+    }
+
+    /// `(sequence, path)` of every snapshot, oldest first
+    fn snapshots(&self) -> io::Result<Vec<(u64, PathBuf)>> {
+        let mut snapshots = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            let sequence = path
+                .file_name()
+                .and_then(|name| name.to_str()?.strip_suffix(".snap")?.parse::<u64>().ok());
+            if let Some(sequence) = sequence {
+                snapshots.push((sequence, path));
+            }
+        }
+        snapshots.sort();
+        Ok(snapshots)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::EventSource;
+    use crate::buffer::Buffer;
+    use crate::persist::{temp_dir, LogReader};
+
+    /// Count and sum of the payloads applied
+    #[derive(Debug, Default, PartialEq)]
+    struct Totals {
+        count: u64,
+        sum: u64,
+    }
+
+    impl SnapshotState for Totals {
+        fn write_snapshot(&self, out: &mut Vec<u8>) {
+            out.extend_from_slice(&self.count.to_le_bytes());
+            out.extend_from_slice(&self.sum.to_le_bytes());
+        }
+
+        fn read_snapshot(bytes: &[u8]) -> Option<Self> {
+            let (count, sum) = bytes.split_first_chunk::<8>()?;
+            Some(Self {
+                count: u64::from_le_bytes(*count),
+                sum: u64::from_le_bytes(sum.try_into().ok()?),
+            })
+        }
+    }
+
+    /// Log payloads 0..count
+    fn write_log(dir: &PathBuf, count: u32) {
+        let buffer = Buffer::<u32>::builder().capacity(64).build().unwrap();
+        let producer = buffer.producer();
+        for i in 0..count {
+            producer.push(i).unwrap();
+        }
+        buffer.flush();
+        let mut writer = buffer.consumer().log_writer(dir).unwrap();
+        writer.drain().unwrap();
+        writer.sync().unwrap();
+    }
+
+    /// Apply the rest of `replay` to `totals`, snapshotting as it goes
+    fn apply(
+        replay: &mut ReplayConsumer<u32>,
+        totals: &mut Totals,
+        snapshotter: &mut Snapshotter<Totals>,
+    ) {
+        while let Some(event) = replay.try_next().unwrap() {
+            totals.count += 1;
+            totals.sum += event.payload as u64;
+            snapshotter.applied(event.sequence, totals).unwrap();
+        }
+    }
+
+    #[test]
+    fn restart_loads_the_newest_snapshot_and_replays_the_rest() {
+        let dir = temp_dir("snapshot");
+        write_log(&dir.join("log"), 10);
+        let log = LogReader::new(dir.join("log"));
+        let mut snapshotter = Snapshotter::new(dir.join("snap")).unwrap().event_interval(3);
+
+        let mut replay = log.replay().unwrap();
+        assert_eq!(snapshotter.resume(&mut replay).unwrap(), None);
+        let mut totals = Totals::default();
+        apply(&mut replay, &mut totals, &mut snapshotter);
+
+        // Snapshots after 2, 5 and 8; the oldest is gone
+        let mut names: Vec<_> = fs::read_dir(dir.join("snap"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["00000000000000000005.snap", "00000000000000000008.snap"]);
+
+        let mut replay = log.replay().unwrap();
+        let snapshot = snapshotter.resume(&mut replay).unwrap().unwrap();
+        assert_eq!(snapshot.sequence, 8);
+        assert_eq!(snapshot.state, Totals { count: 9, sum: 36 });
+        let mut restored = snapshot.state;
+        apply(&mut replay, &mut restored, &mut snapshotter);
+        assert_eq!(restored, totals);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_damaged_snapshot_falls_back_to_the_one_before() {
+        let dir = temp_dir("snapshot-damaged");
+        let mut snapshotter = Snapshotter::new(&dir).unwrap().time_interval(Duration::ZERO);
+        assert!(snapshotter.applied(4, &Totals { count: 5, sum: 10 }).unwrap());
+        assert!(snapshotter.applied(9, &Totals { count: 10, sum: 45 }).unwrap());
+
+        let newest = dir.join("00000000000000000009.snap");
+        let mut bytes = fs::read(&newest).unwrap();
+        bytes[20] ^= 1;
+        fs::write(&newest, bytes).unwrap();
+        let snapshot = snapshotter.load().unwrap().unwrap();
+        assert_eq!(snapshot.sequence, 4);
+        assert_eq!(snapshot.state, Totals { count: 5, sum: 10 });
+
+        // The next save prunes the damaged one rather than an intact older one
+        assert!(snapshotter.applied(12, &Totals { count: 13, sum: 78 }).unwrap());
+        assert!(!newest.exists());
+        assert_eq!(snapshotter.load().unwrap().unwrap().sequence, 12);
+        assert!(dir.join("00000000000000000004.snap").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_save_behind_newer_snapshots_is_kept() {
+        let dir = temp_dir("snapshot-behind");
+        let mut snapshotter = Snapshotter::new(&dir).unwrap().keep(1);
+        snapshotter.save(9, &Totals { count: 10, sum: 45 }).unwrap();
+        snapshotter.save(2, &Totals { count: 3, sum: 3 }).unwrap();
+        assert!(dir.join("00000000000000000002.snap").exists());
+        assert!(dir.join("00000000000000000009.snap").exists());
+
+        // Once past it, the older one goes
+        snapshotter.save(11, &Totals { count: 12, sum: 66 }).unwrap();
+        let names: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        assert_eq!(names, ["00000000000000000011.snap"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,139 @@
+use crate::consumer::Checkpoint;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// Where committed consumer positions are kept, keyed by consumer name
+pub trait CursorStore: Send {
+    /// The last committed checkpoint for `name`, if any
+    fn load(&self, name: &str) -> io::Result<Option<Checkpoint>>;
+
+    /// Durably record `checkpoint` as committed for `name`
+    fn save(&mut self, name: &str, checkpoint: Checkpoint) -> io::Result<()>;
+}
+
+/// In-process store. Clones share the same offsets, so a consumer can be
+/// recreated from a store kept elsewhere in the program.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryCursorStore {
+    offsets: Arc<Mutex<HashMap<String, Checkpoint>>>,
+}
+
+impl MemoryCursorStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl CursorStore for MemoryCursorStore {
+    fn load(&self, name: &str) -> io::Result<Option<Checkpoint>> {
+        let offsets = self.offsets.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(offsets.get(name).copied())
+    }
+
+    fn save(&mut self, name: &str, checkpoint: Checkpoint) -> io::Result<()> {
+        let mut offsets = self.offsets.lock().unwrap_or_else(|e| e.into_inner());
+        offsets.insert(name.to_string(), checkpoint);
+        Ok(())
+    }
+}
+
+/// One file per consumer (`<dir>/<name>.cursor`) holding the decimal sequence.
+/// Saves write a temporary file, fsync it, rename it over the old one and fsync the
+/// directory. A name that is empty, `.`, `..` or holds a path separator is
+/// `InvalidInput`, so every cursor file stays in `dir`.
+#[derive(Debug, Clone)]
+pub struct FileCursorStore {
+    dir: PathBuf,
+}
+
+impl FileCursorStore {
+    /// Use `dir` for cursor files, creating it if needed
+    pub fn new(dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    fn path(&self, name: &str) -> io::Result<PathBuf> {
+        if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\\']) {
+            let msg = format!("cursor name {:?} is not a plain file name", name);
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, msg));
+        }
+        Ok(self.dir.join(format!("{}.cursor", name)))
+    }
+}
+
+impl CursorStore for FileCursorStore {
+    fn load(&self, name: &str) -> io::Result<Option<Checkpoint>> {
+        match fs::read_to_string(self.path(name)?) {
+            Ok(contents) => contents
+                .parse()
+                .map(Some)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn save(&mut self, name: &str, checkpoint: Checkpoint) -> io::Result<()> {
+        let path = self.path(name)?;
+        let tmp = path.with_extension("cursor.tmp");
+
+        let mut file = File::create(&tmp)?;
+        write!(file, "{}", checkpoint)?;
+        file.sync_all()?;
+        fs::rename(&tmp, &path)?;
+        // The new name is only durable once the directory is
+        #[cfg(unix)]
+        File::open(&self.dir)?.sync_all()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn memory_store_clones_share_offsets() {
+        let mut store = MemoryCursorStore::new();
+        let other = store.clone();
+        assert_eq!(other.load("a").unwrap(), None);
+
+        store.save("a", Checkpoint::new(7)).unwrap();
+        assert_eq!(other.load("a").unwrap(), Some(Checkpoint::new(7)));
+    }
+
+    #[test]
+    fn file_store_persists_across_instances() {
+        let dir = std::env::temp_dir().join(format!("lftes-store-{}", std::process::id()));
+        let mut store = FileCursorStore::new(&dir).unwrap();
+        assert_eq!(store.load("orders").unwrap(), None);
+
+        store.save("orders", Checkpoint::new(42)).unwrap();
+        store.save("orders", Checkpoint::new(43)).unwrap();
+
+        let reopened = FileCursorStore::new(&dir).unwrap();
+        assert_eq!(reopened.load("orders").unwrap(), Some(Checkpoint::new(43)));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn file_store_keeps_cursor_files_in_its_directory() {
+        let dir = std::env::temp_dir().join(format!("lftes-store-names-{}", std::process::id()));
+        let mut store = FileCursorStore::new(&dir).unwrap();
+        for name in ["", ".", "..", "../orders", "a/b", "a\\b"] {
+            let err = store.save(name, Checkpoint::new(1)).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidInput, "{:?}", name);
+            assert_eq!(store.load(name).unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        }
+        store.save("a..b", Checkpoint::new(2)).unwrap();
+        assert_eq!(store.load("a..b").unwrap(), Some(Checkpoint::new(2)));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
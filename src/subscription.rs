@@ -0,0 +1,116 @@
+use crate::buffer::Buffer;
+use crate::consumer::Event;
+use crate::error::ConsumerError;
+use crate::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+/// A handler thread invoking a callback for every event. Stops and joins on drop.
+pub struct SubscriptionHandle {
+    stop: Arc<AtomicBool>,
+    wake: Box<dyn Fn() + Send + Sync>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl SubscriptionHandle {
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Release);
+        (self.wake)();
+    }
+
+    pub fn join(mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(thread) = self.thread.take() {
+            thread.join().map_err(|_| "Subscription thread panicked")?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for SubscriptionHandle {
+    fn drop(&mut self) {
+        self.stop();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+pub fn start_subscription<T, M, F>(buffer: Arc<Buffer<T, M>>, mut handler: F) -> SubscriptionHandle
+where
+    T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
+    F: FnMut(Event<T, M>) + Send + 'static,
+{
+    let stop = Arc::new(AtomicBool::new(false));
+    let stop_clone = stop.clone();
+
+    // Register before spawning so nothing sequenced after subscribe() returns is missed
+    let mut consumer = buffer.consumer();
+    let thread = thread::spawn(move || loop {
+        match consumer.next_until(&stop_clone) {
+            Ok(Some(event)) => handler(event),
+            Ok(None) => break,
+            // Resynced to the oldest resident event - keep going
+            Err(ConsumerError::Lagged { .. }) => continue,
+            Err(_) => break,
+        }
+    });
+
+    SubscriptionHandle {
+        stop,
+        wake: Box::new(move || buffer.notifier.notify_all()),
+        thread: Some(thread),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wait::WaitStrategy;
+    use std::sync::Mutex;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn handler_sees_every_event() {
+        let buffer = Buffer::<u64>::builder().capacity(64).build().unwrap();
+        let mut sequencer = buffer.start();
+
+        let seen = Arc::new(Mutex::new(vec![]));
+        let seen_clone = seen.clone();
+        let subscription = buffer.subscribe(move |event| {
+            seen_clone.lock().unwrap().push(event.payload);
+        });
+
+        let producer = buffer.producer();
+        for i in 0..10 {
+            producer.push(i).unwrap();
+        }
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while seen.lock().unwrap().len() < 10 && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(1));
+        }
+        assert_eq!(*seen.lock().unwrap(), (0..10).collect::<Vec<u64>>());
+
+        subscription.stop();
+        subscription.join().unwrap();
+        sequencer.stop();
+        sequencer.join().unwrap();
+    }
+
+    #[test]
+    fn stop_wakes_a_parked_handler() {
+        let buffer = Buffer::<u64>::builder()
+            .capacity(16)
+            .wait_strategy(WaitStrategy::Blocking)
+            .build()
+            .unwrap();
+
+        let subscription = buffer.subscribe(|_| {});
+        thread::sleep(Duration::from_millis(20));
+
+        subscription.stop();
+        subscription.join().unwrap();
+        assert_eq!(buffer.registered_consumers(), 0);
+    }
+}
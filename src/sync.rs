@@ -0,0 +1,125 @@
+//! Atomics, cells and spin hints for the ring's state machine. Built with
+//! `--cfg loom` they are loom's instead, so the models in `tests/loom.rs` can explore
+//! every interleaving and ordering the claim/publish/sequence/consume protocol allows.
+
+use crate::affinity;
+use std::fmt;
+use std::ops::Deref;
+use std::ptr::NonNull;
+
+#[cfg(not(loom))]
+pub(crate) use std::cell::UnsafeCell;
+#[cfg(not(loom))]
+pub(crate) use std::{hint, sync::atomic};
+
+#[cfg(loom)]
+pub(crate) use loom::{hint, sync::atomic};
+
+/// Loom's cell behind `std::cell::UnsafeCell`'s `get`. Every `get` is recorded as a
+/// write at the time of the call, reads included, so loom reports any two accesses
+/// from different threads that nothing orders, even if both only read.
+#[cfg(loom)]
+#[derive(Debug)]
+pub(crate) struct UnsafeCell<T>(loom::cell::UnsafeCell<T>);
+
+#[cfg(loom)]
+impl<T> UnsafeCell<T> {
+    #[track_caller]
+    pub(crate) fn new(value: T) -> Self {
+        Self(loom::cell::UnsafeCell::new(value))
+    }
+
+    #[track_caller]
+    pub(crate) fn get(&self) -> *mut T {
+        self.0.with_mut(|ptr| ptr)
+    }
+}
+
+/// `len` values like `zero()`, which must be all zero bytes. Allocated zeroed, so the
+/// OS can hand out pages that stay untouched until first written, instead of writing
+/// every value up front. Loom's atomics aren't plain bytes, so under loom they are.
+///
+/// SAFETY: `zero()` must return a value whose bytes are all zero.
+pub(crate) unsafe fn zeroed_slice<T>(len: usize, zero: impl Fn() -> T) -> Box<[T]> {
+    #[cfg(not(loom))]
+    {
+        let _ = zero;
+        // SAFETY: All zero bytes are a valid `T`, as the caller promised
+        unsafe { Box::new_zeroed_slice(len).assume_init() }
+    }
+    #[cfg(loom)]
+    (0..len).map(|_| zero()).collect()
+}
+
+/// `zeroed_slice`'s values, on the heap or on pages of their own
+pub(crate) enum ZeroedSlice<T> {
+    Heap(Box<[T]>),
+    /// An anonymous mapping, `len` bytes long, unmapped on drop
+    Mapped { values: NonNull<[T]>, len: usize },
+}
+
+// SAFETY: Both variants own their values, like `Box<[T]>`
+unsafe impl<T: Send> Send for ZeroedSlice<T> {}
+unsafe impl<T: Sync> Sync for ZeroedSlice<T> {}
+
+impl<T> ZeroedSlice<T> {
+    /// `len` values like `zero()`, as `zeroed_slice` makes them. With `own_pages` they
+    /// are mapped where the OS can map them, so no other allocation shares their pages
+    /// and `munlock` on them unlocks nothing else.
+    ///
+    /// SAFETY: `zero()` must return a value whose bytes are all zero.
+    pub(crate) unsafe fn new(len: usize, own_pages: bool, zero: impl Fn() -> T) -> Self {
+        let mappable = own_pages && !cfg!(loom) && align_of::<T>() <= affinity::PAGE;
+        if mappable
+            && let Some((ptr, bytes)) = affinity::map_anonymous(len * size_of::<T>(), false)
+        {
+            // The mapping is zeroed, and so already holds `len` values like `zero()`
+            let values = NonNull::slice_from_raw_parts(ptr.cast(), len);
+            return ZeroedSlice::Mapped { values, len: bytes };
+        }
+        // SAFETY: As the caller promised
+        ZeroedSlice::Heap(unsafe { zeroed_slice(len, zero) })
+    }
+}
+
+impl<T> Deref for ZeroedSlice<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match self {
+            ZeroedSlice::Heap(values) => values,
+            // SAFETY: `new` left every value valid, and the mapping lives until drop
+            ZeroedSlice::Mapped { values, .. } => unsafe { values.as_ref() },
+        }
+    }
+}
+
+impl<T> Drop for ZeroedSlice<T> {
+    fn drop(&mut self) {
+        if let ZeroedSlice::Mapped { values, len } = *self {
+            // SAFETY: The values are ours, and nothing uses them after this
+            unsafe { values.drop_in_place() };
+            affinity::unmap(values.cast(), len);
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ZeroedSlice<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self[..].fmt(f)
+    }
+}
+
+/// A `const fn`, except under loom, whose atomics can't be built in a constant
+macro_rules! const_fn {
+    ($(#[$attr:meta])* $vis:vis fn $($rest:tt)*) => {
+        #[cfg(not(loom))]
+        $(#[$attr])*
+        $vis const fn $($rest)*
+
+        #[cfg(loom)]
+        $(#[$attr])*
+        $vis fn $($rest)*
+    };
+}
+pub(crate) use const_fn;
@@ -0,0 +1,136 @@
+use crate::sync::atomic::{AtomicU64, Ordering};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Expires sequenced events once they are older than `ttl`.
+///
+/// Event timestamps are raw cycle counts, so ages come from when each run was
+/// sequenced instead. Everything below `floor` is expired: producers may reclaim
+/// its slots whether or not consumers have read them, and readers see a lag.
+#[derive(Debug)]
+pub(crate) struct Ttl {
+    ttl: Duration,
+    /// End of each sequenced run and when it was sequenced, oldest first.
+    /// Only touched by whoever holds `Buffer::sequencing`.
+    runs: Mutex<VecDeque<(u64, Instant)>>,
+    floor: AtomicU64,
+    expired: AtomicU64,
+}
+
+impl Ttl {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            runs: Mutex::new(VecDeque::new()),
+            floor: AtomicU64::new(0),
+            expired: AtomicU64::new(0),
+        }
+    }
+
+    /// First sequence that has not expired
+    pub(crate) fn floor(&self) -> u64 {
+        self.floor.load(Ordering::Acquire)
+    }
+
+    /// Events expired while still resident
+    pub(crate) fn expired(&self) -> u64 {
+        self.expired.load(Ordering::Relaxed)
+    }
+
+    /// Bytes held for runs not yet expired, for `Buffer::memory_usage`
+    pub(crate) fn memory_usage(&self) -> usize {
+        let runs = self.runs.lock().unwrap_or_else(|e| e.into_inner());
+        runs.capacity() * size_of::<(u64, Instant)>()
+    }
+
+    /// Note that everything below `end` was sequenced at `now`
+    pub(crate) fn record(&self, end: u64, now: Instant) {
+        self.runs
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push_back((end, now));
+    }
+
+    /// Expire runs older than the TTL. `oldest` is the oldest sequence still in the ring;
+    /// anything below it was overwritten and is not counted. Returns when the next run expires.
+    pub(crate) fn expire(&self, now: Instant, oldest: u64) -> Option<Instant> {
+        let mut runs = self.runs.lock().unwrap_or_else(|e| e.into_inner());
+        let old_floor = self.floor.load(Ordering::Relaxed);
+        let mut floor = old_floor;
+        while let Some(&(end, at)) = runs.front() {
+            if end > oldest && at + self.ttl > now {
+                break;
+            }
+            floor = floor.max(end);
+            runs.pop_front();
+        }
+
+        if floor > old_floor {
+            let counted = floor.saturating_sub(old_floor.max(oldest));
+            self.expired.fetch_add(counted, Ordering::Relaxed);
+            self.floor.store(floor, Ordering::Release);
+        }
+        runs.front().map(|&(_, at)| at + self.ttl)
+    }
+
+    /// Forget every run, for a buffer starting over from sequence 0
+    pub(crate) fn reset(&mut self) {
+        self.runs.get_mut().unwrap_or_else(|e| e.into_inner()).clear();
+        self.floor.store(0, Ordering::Relaxed);
+        self.expired.store(0, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::Buffer;
+    use crate::error::ConsumerError;
+
+    #[test]
+    fn overwritten_runs_are_dropped_without_counting() {
+        let ttl = Ttl::new(Duration::from_secs(60));
+        let now = Instant::now();
+        ttl.record(4, now);
+        ttl.record(8, now);
+
+        assert_eq!(ttl.expire(now, 4), Some(now + Duration::from_secs(60)));
+        assert_eq!(ttl.floor(), 4);
+        assert_eq!(ttl.expired(), 0);
+
+        ttl.expire(now + Duration::from_secs(60), 4);
+        assert_eq!(ttl.floor(), 8);
+        assert_eq!(ttl.expired(), 4);
+    }
+
+    #[test]
+    fn expired_events_are_skipped_and_their_slots_freed() {
+        let buffer = Buffer::<u64>::builder()
+            .capacity(4)
+            .ttl(Duration::from_millis(10))
+            .build()
+            .unwrap();
+        let producer = buffer.producer();
+        let mut consumer = buffer.consumer();
+        for i in 0..4 {
+            producer.push(i).unwrap();
+        }
+        buffer.sequence_available();
+        assert!(!buffer.recyclable(0));
+
+        std::thread::sleep(Duration::from_millis(20));
+        buffer.sequence_available();
+        assert_eq!(buffer.expired(), 4);
+        assert!(buffer.recyclable(3));
+
+        // The unread consumer no longer pins the ring
+        producer.push(4).unwrap();
+        buffer.sequence_available();
+        assert_eq!(
+            consumer.try_next().unwrap_err(),
+            ConsumerError::Lagged { skipped: 4 }
+        );
+        assert_eq!(consumer.try_next().unwrap().unwrap().payload, 4);
+    }
+}
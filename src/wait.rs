@@ -0,0 +1,177 @@
+use crate::sync::atomic::{fence, AtomicUsize, Ordering};
+use crate::sync::hint;
+use std::sync::{Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// How a thread waits for the buffer to make progress
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WaitStrategy {
+    /// Spin on the CPU. Lowest latency, burns a core while idle.
+    BusySpin,
+    /// Spin for a while, then yield to the scheduler between checks.
+    #[default]
+    Yielding,
+    /// Spin briefly, then park until the sequencer signals progress.
+    Blocking,
+    /// Spin briefly, then sleep for exponentially longer intervals up to `MAX_BACKOFF`.
+    Backoff,
+}
+
+const SPIN_LIMIT: u32 = 100;
+const YIELD_LIMIT: u32 = 10;
+const MAX_BACKOFF: Duration = Duration::from_millis(1);
+
+/// Wakes parked waiters. Only touches the mutex when someone is parked.
+#[derive(Debug, Default)]
+pub(crate) struct Notifier {
+    waiters: AtomicUsize,
+    lock: Mutex<()>,
+    cvar: Condvar,
+}
+
+impl Notifier {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wake all parked waiters. Must be called after the progress it signals is visible.
+    pub(crate) fn notify_all(&self) {
+        // Pairs with the fence in `park`: either we see the waiter, or it sees our stores
+        fence(Ordering::SeqCst);
+        if self.waiters.load(Ordering::Relaxed) > 0 {
+            let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+            self.cvar.notify_all();
+        }
+    }
+
+    /// Park until notified, `ready` returns true, or `deadline` passes
+    pub(crate) fn park(&self, deadline: Option<Instant>, mut ready: impl FnMut() -> bool) {
+        self.waiters.fetch_add(1, Ordering::Relaxed);
+        fence(Ordering::SeqCst);
+
+        let guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        if !ready() {
+            match deadline {
+                Some(deadline) => {
+                    let timeout = deadline.saturating_duration_since(Instant::now());
+                    drop(self.cvar.wait_timeout(guard, timeout));
+                }
+                None => {
+                    drop(self.cvar.wait(guard));
+                }
+            }
+        }
+
+        self.waiters.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// Per-call wait state that escalates spin -> yield -> park according to the strategy
+pub(crate) struct Waiter {
+    strategy: WaitStrategy,
+    step: u32,
+}
+
+impl Waiter {
+    pub(crate) fn new(strategy: WaitStrategy) -> Self {
+        Self { strategy, step: 0 }
+    }
+
+    /// Start escalating from the beginning again after progress was made
+    pub(crate) fn reset(&mut self) {
+        self.step = 0;
+    }
+
+    /// Wait once. Callers re-check their condition after every call.
+    pub(crate) fn wait(
+        &mut self,
+        notifier: &Notifier,
+        deadline: Option<Instant>,
+        ready: impl FnMut() -> bool,
+    ) {
+        match self.strategy {
+            WaitStrategy::BusySpin => hint::spin_loop(),
+            WaitStrategy::Yielding => {
+                if self.step < SPIN_LIMIT {
+                    self.step += 1;
+                    hint::spin_loop();
+                } else {
+                    std::thread::yield_now();
+                }
+            }
+            WaitStrategy::Blocking => {
+                if self.step < SPIN_LIMIT {
+                    self.step += 1;
+                    hint::spin_loop();
+                } else if self.step < SPIN_LIMIT + YIELD_LIMIT {
+                    self.step += 1;
+                    std::thread::yield_now();
+                } else {
+                    notifier.park(deadline, ready);
+                }
+            }
+            WaitStrategy::Backoff => {
+                if self.step < SPIN_LIMIT {
+                    self.step += 1;
+                    hint::spin_loop();
+                } else {
+                    let shift = (self.step - SPIN_LIMIT).min(10);
+                    self.step += 1;
+                    let mut sleep = Duration::from_micros(1 << shift).min(MAX_BACKOFF);
+                    if let Some(deadline) = deadline {
+                        sleep = sleep.min(deadline.saturating_duration_since(Instant::now()));
+                    }
+                    std::thread::sleep(sleep);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn default_strategy_is_yielding() {
+        assert_eq!(WaitStrategy::default(), WaitStrategy::Yielding);
+    }
+
+    #[test]
+    fn park_wakes_on_notify() {
+        let notifier = Arc::new(Notifier::new());
+        let flag = Arc::new(AtomicBool::new(false));
+
+        let waiter = {
+            let notifier = notifier.clone();
+            let flag = flag.clone();
+            thread::spawn(move || {
+                while !flag.load(Ordering::Acquire) {
+                    notifier.park(None, || flag.load(Ordering::Acquire));
+                }
+            })
+        };
+
+        thread::sleep(Duration::from_millis(20));
+        flag.store(true, Ordering::Release);
+        notifier.notify_all();
+
+        waiter.join().unwrap();
+    }
+
+    #[test]
+    fn park_returns_at_deadline() {
+        let notifier = Notifier::new();
+        let start = Instant::now();
+        let deadline = start + Duration::from_millis(20);
+        // Condvars may wake spuriously; callers loop until the deadline
+        while Instant::now() < deadline {
+            notifier.park(Some(deadline), || false);
+        }
+        assert!(start.elapsed() >= Duration::from_millis(20));
+    }
+}
@@ -0,0 +1,155 @@
+use crate::adapter::EventSource;
+use crate::buffer::Buffer;
+use crate::consumer::Event;
+use crate::error::ConsumerError;
+use crate::sync::atomic::Ordering;
+use crate::wait::Waiter;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// A consumer that never holds back slot recycling.
+///
+/// It is not registered with the buffer, so producers never wait for it. When it
+/// is overrun it silently jumps to the oldest resident event; `skipped()` counts
+/// what it missed. Meant for debuggers, dashboards and samplers.
+pub struct WeakConsumer<T, M = ()> {
+    buffer: Arc<Buffer<T, M>>,
+    cursor: u64,
+    skipped: u64,
+}
+
+impl<T, M> WeakConsumer<T, M>
+where
+    T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
+{
+    pub(crate) fn new(buffer: Arc<Buffer<T, M>>) -> Self {
+        let cursor = buffer.resident_range().start;
+        Self {
+            buffer,
+            cursor,
+            skipped: 0,
+        }
+    }
+
+    /// Next sequence this consumer will read
+    pub fn position(&self) -> u64 {
+        self.cursor
+    }
+
+    /// Number of events overwritten before this consumer reached them
+    pub fn skipped(&self) -> u64 {
+        self.skipped
+    }
+
+    /// The most recently sequenced event, without moving the cursor
+    pub fn latest(&self) -> Option<Event<T, M>> {
+        let next = self.buffer.next_seq.load(Ordering::Acquire);
+        next.checked_sub(1).and_then(|sequence| self.copy(sequence))
+    }
+
+    /// Copy out `sequence`, or `None` if it is not resident or was a skipped claim
+    fn copy(&self, sequence: u64) -> Option<Event<T, M>> {
+        self.buffer.copy_resident(sequence)
+    }
+}
+
+impl<T, M> EventSource for WeakConsumer<T, M>
+where
+    T: Copy + Send + 'static,
+    M: Copy + Send + 'static,
+{
+    type Item = Event<T, M>;
+
+    fn try_next(&mut self) -> Result<Option<Event<T, M>>, ConsumerError> {
+        loop {
+            if let Some(event) = self.copy(self.cursor) {
+                self.cursor += 1;
+                return Ok(Some(event));
+            }
+            if self.cursor >= self.buffer.next_seq.load(Ordering::Acquire) {
+                return Ok(None);
+            }
+            if self
+                .buffer
+                .sequenced_slot(self.cursor)
+                .is_some_and(|slot| slot.is_skipped())
+            {
+                self.cursor += 1;
+                continue;
+            }
+            // Overwritten before we got to it
+            let oldest = self.buffer.resident_range().start.max(self.cursor + 1);
+            self.skipped += oldest - self.cursor;
+            self.cursor = oldest;
+        }
+    }
+
+    fn next(&mut self) -> Result<Event<T, M>, ConsumerError> {
+        let mut waiter = Waiter::new(self.buffer.wait_strategy);
+        loop {
+            let drained = self.buffer.is_drained();
+            if let Some(event) = self.try_next()? {
+                return Ok(event);
+            }
+            if drained {
+                return Err(ConsumerError::Closed);
+            }
+            let cursor = self.cursor;
+            waiter.wait(&self.buffer.notifier, None, || {
+                cursor < self.buffer.next_seq.load(Ordering::Acquire) || self.buffer.is_drained()
+            });
+        }
+    }
+
+    fn next_timeout(&mut self, timeout: Duration) -> Result<Event<T, M>, ConsumerError> {
+        let deadline = Instant::now() + timeout;
+        let mut waiter = Waiter::new(self.buffer.wait_strategy);
+        loop {
+            let drained = self.buffer.is_drained();
+            if let Some(event) = self.try_next()? {
+                return Ok(event);
+            }
+            if drained {
+                return Err(ConsumerError::Closed);
+            }
+            if Instant::now() >= deadline {
+                return Err(ConsumerError::Timeout);
+            }
+            let cursor = self.cursor;
+            waiter.wait(&self.buffer.notifier, Some(deadline), || {
+                cursor < self.buffer.next_seq.load(Ordering::Acquire) || self.buffer.is_drained()
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn weak_consumer_does_not_gate_producers() {
+        let buffer = Buffer::<u64>::builder().capacity(4).build().unwrap();
+        let mut handle = buffer.start();
+        let mut weak = buffer.weak_consumer();
+        assert_eq!(buffer.registered_consumers(), 0);
+
+        // Laps the ring twice; a registered consumer would have stalled the producer
+        let producer = buffer.producer();
+        for i in 0..12 {
+            producer.push(i).unwrap();
+            while buffer.next_seq.load(Ordering::Acquire) <= i {
+                std::thread::yield_now();
+            }
+        }
+        handle.stop();
+        handle.join().unwrap();
+
+        assert_eq!(weak.latest().unwrap().payload, 11);
+        let first = weak.try_next().unwrap().unwrap();
+        assert_eq!(first.sequence, 8);
+        assert_eq!(weak.skipped(), 8);
+        assert_eq!(weak.try_next_batch(10).unwrap().len(), 3);
+    }
+}
@@ -1,7 +1,6 @@
 use lftes::Buffer;
 use std::collections::HashSet;
 use std::thread;
-use std::time::Duration;
 
 #[test]
 fn multiple_producers_no_lost_events() {
@@ -10,7 +9,7 @@ fn multiple_producers_no_lost_events() {
     const TOTAL_EVENTS: usize = NUM_PRODUCERS * EVENTS_PER_PRODUCER;
 
     let buffer: std::sync::Arc<Buffer<u64>> = Buffer::<u64>::builder().capacity(512).build().unwrap();
-    let handle: lftes::SequencerHandle = buffer.start();
+    let mut handle: lftes::SequencerHandle = buffer.start();
 
     // Spawn multiple producers
     let mut producer_threads: Vec<thread::JoinHandle<()>> = vec![];
@@ -31,21 +30,15 @@ fn multiple_producers_no_lost_events() {
         thread.join().unwrap();
     }
 
-    // Give sequencer time to process
-    thread::sleep(Duration::from_millis(100));
+    // Wait for the sequencer to catch up
+    buffer.flush();
 
     // Consume all events
     let mut consumer: lftes::Consumer<u64> = buffer.consumer();
     let mut events: Vec<lftes::Event<u64>> = vec![];
     for _ in 0..TOTAL_EVENTS {
-        if let Some(event) = consumer.try_next() {
+        if let Some(event) = consumer.try_next().unwrap() {
             events.push(event);
-        } else {
-            // Wait a bit and retry
-            thread::sleep(Duration::from_millis(10));
-            if let Some(event) = consumer.try_next() {
-                events.push(event);
-            }
         }
     }
 
@@ -86,7 +79,7 @@ fn sequential_push_and_consume() {
         .capacity(BUFFER_SIZE)
         .build()
         .unwrap();
-    let handle: lftes::SequencerHandle = buffer.start();
+    let mut handle: lftes::SequencerHandle = buffer.start();
 
     // Push events
     let producer: lftes::Producer<u64> = buffer.producer();
@@ -94,13 +87,13 @@ fn sequential_push_and_consume() {
         producer.push(i as u64).unwrap();
     }
 
-    // Give sequencer time to process
-    thread::sleep(Duration::from_millis(100));
+    // Wait for the sequencer to catch up
+    buffer.flush();
 
     // Consume events
     let mut consumer: lftes::Consumer<u64> = buffer.consumer();
     let mut consumed = 0;
-    while let Some(_event) = consumer.try_next() {
+    while let Some(_event) = consumer.try_next().unwrap() {
         consumed += 1;
         if consumed >= NUM_EVENTS {
             break;
@@ -112,3 +105,48 @@ fn sequential_push_and_consume() {
     handle.stop();
     handle.join().unwrap();
 }
+
+#[test]
+fn growing_buffer_keeps_every_event_under_burst() {
+    const NUM_PRODUCERS: u64 = 4;
+    const EVENTS_PER_PRODUCER: u64 = 500;
+
+    let buffer: std::sync::Arc<Buffer<u64>> = Buffer::<u64>::builder()
+        .capacity(8)
+        .sequencer_wait_strategy(lftes::WaitStrategy::Blocking)
+        .on_full(lftes::OnFull::Grow)
+        .build()
+        .unwrap();
+    let mut handle: lftes::SequencerHandle = buffer.start();
+    let mut consumer: lftes::Consumer<u64> = buffer.consumer();
+
+    // Bursts never block, even though the consumer has not started reading
+    let producer_threads: Vec<thread::JoinHandle<()>> = (0..NUM_PRODUCERS)
+        .map(|producer_id| {
+            let buffer_clone: std::sync::Arc<Buffer<u64>> = buffer.clone();
+            thread::spawn(move || {
+                let producer: lftes::Producer<u64> = buffer_clone.producer();
+                for i in 0..EVENTS_PER_PRODUCER {
+                    producer.push(producer_id * 1000 + i).unwrap();
+                }
+            })
+        })
+        .collect();
+    for thread in producer_threads {
+        thread.join().unwrap();
+    }
+
+    // Each producer's events arrive complete and in push order
+    let mut next_expected: Vec<u64> = vec![0; NUM_PRODUCERS as usize];
+    for sequence in 0..NUM_PRODUCERS * EVENTS_PER_PRODUCER {
+        let event: lftes::Event<u64> = consumer.next().unwrap();
+        assert_eq!(event.sequence, sequence);
+        let producer_id = (event.payload / 1000) as usize;
+        assert_eq!(event.payload % 1000, next_expected[producer_id]);
+        next_expected[producer_id] += 1;
+    }
+    assert_eq!(buffer.overflowed(), 0);
+
+    handle.stop();
+    handle.join().unwrap();
+}
@@ -1,13 +1,11 @@
 use lftes::{Buffer, Event};
-use std::thread;
-use std::time::Duration;
 
 #[test]
 fn deterministic_replay_same_order() {
     const NUM_EVENTS: usize = 50;
 
     let buffer: std::sync::Arc<Buffer<u64>> = Buffer::<u64>::builder().capacity(256).build().unwrap();
-    let handle: lftes::SequencerHandle = buffer.start();
+    let mut handle: lftes::SequencerHandle = buffer.start();
 
     // Push events from a single producer
     let producer: lftes::Producer<u64> = buffer.producer();
@@ -15,8 +13,8 @@ fn deterministic_replay_same_order() {
         producer.push(i as u64).unwrap();
     }
 
-    // Give sequencer time to process
-    thread::sleep(Duration::from_millis(100));
+    // Wait for the sequencer to catch up
+    buffer.flush();
 
     // Create two consumers starting from sequence 0
     let mut consumer1: lftes::Consumer<u64> = buffer.consumer();
@@ -24,11 +22,11 @@ fn deterministic_replay_same_order() {
 
     // Collect events from both consumers
     let events1: Vec<Event<u64>> = (0..NUM_EVENTS)
-        .filter_map(|_| consumer1.try_next())
+        .filter_map(|_| consumer1.try_next().unwrap())
         .collect();
 
     let events2: Vec<Event<u64>> = (0..NUM_EVENTS)
-        .filter_map(|_| consumer2.try_next())
+        .filter_map(|_| consumer2.try_next().unwrap())
         .collect();
 
     // Verify both consumers received the same events in the same order
@@ -57,7 +55,7 @@ fn deterministic_replay_same_order() {
 #[test]
 fn consumer_tracks_minimum_cursor() {
     let buffer: std::sync::Arc<Buffer<u64>> = Buffer::<u64>::builder().capacity(64).build().unwrap();
-    let handle: lftes::SequencerHandle = buffer.start();
+    let mut handle: lftes::SequencerHandle = buffer.start();
 
     // Push some events
     let producer: lftes::Producer<u64> = buffer.producer();
@@ -65,8 +63,8 @@ fn consumer_tracks_minimum_cursor() {
         producer.push(i as u64).unwrap();
     }
 
-    // Give sequencer time to process
-    thread::sleep(Duration::from_millis(50));
+    // Wait for the sequencer to catch up
+    buffer.flush();
 
     // Create two consumers
     let mut consumer1: lftes::Consumer<u64> = buffer.consumer();
@@ -74,20 +72,21 @@ fn consumer_tracks_minimum_cursor() {
 
     // Consumer1 reads 5 events
     for _ in 0..5 {
-        consumer1.try_next();
+        consumer1.try_next().unwrap();
     }
 
     // Consumer2 reads 3 events
     for _ in 0..3 {
-        consumer2.try_next();
+        consumer2.try_next().unwrap();
     }
 
     // Both consumers are at different positions
-    // This demonstrates independent cursor tracking
-    // (A full implementation would track min cursor for slot recycling)
+    // This demonstrates independent cursor tracking; both are registered,
+    // so the slowest one gates slot recycling
+    assert_eq!(buffer.registered_consumers(), 2);
 
-    let event1: Option<Event<u64>> = consumer1.try_next();
-    let event2: Option<Event<u64>> = consumer2.try_next();
+    let event1: Option<Event<u64>> = consumer1.try_next().unwrap();
+    let event2: Option<Event<u64>> = consumer2.try_next().unwrap();
 
     assert!(event1.is_some());
     assert!(event2.is_some());
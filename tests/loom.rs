@@ -0,0 +1,127 @@
+//! Model checks of the claim/publish/sequence/consume protocol. Run with
+//! `RUSTFLAGS="--cfg loom" cargo test --release --test loom`; `LOOM_MAX_PREEMPTIONS`
+//! widens the search past the default bound below.
+//!
+//! Producers run on their own threads and the main thread sequences and consumes,
+//! retrying until the events arrive. Nothing but the buffer orders the two, so loom
+//! reports any payload read that the buffer's atomics don't make safe.
+#![cfg(loom)]
+
+use lftes::{Buffer, Consumer, Event};
+use loom::thread;
+
+/// Explore every interleaving with at most one preemption. Two take the claims
+/// model past half an hour.
+fn model(f: impl Fn() + Sync + Send + 'static) {
+    let mut builder = loom::model::Builder::new();
+    builder.preemption_bound.get_or_insert(1);
+    // Building a buffer alone takes a few hundred atomic operations
+    builder.max_branches = 100_000;
+    builder.check(f);
+}
+
+/// Sequence and read the next event, yielding to the producers until it is there
+fn next(buffer: &Buffer<u64>, consumer: &mut Consumer<u64>) -> Event<u64> {
+    loop {
+        buffer.sequence_available();
+        if let Some(event) = consumer.try_next().unwrap() {
+            return event;
+        }
+        thread::yield_now();
+    }
+}
+
+#[test]
+fn concurrent_claims_get_distinct_sequences() {
+    model(|| {
+        let buffer = Buffer::<u64>::builder().capacity(4).build().unwrap();
+        let mut consumer = buffer.consumer();
+        let producers: Vec<_> = (1..=2)
+            .map(|value| {
+                let producer = buffer.producer();
+                thread::spawn(move || producer.push(value).unwrap())
+            })
+            .collect();
+
+        let first = next(&buffer, &mut consumer);
+        let second = next(&buffer, &mut consumer);
+        assert_eq!((first.sequence, second.sequence), (0, 1));
+        let mut payloads = [first.payload, second.payload];
+        payloads.sort();
+        assert_eq!(payloads, [1, 2]);
+        for producer in producers {
+            producer.join().unwrap();
+        }
+    });
+}
+
+#[test]
+fn consumers_see_the_payload_of_a_sequenced_event() {
+    model(|| {
+        let buffer = Buffer::<u64>::builder().capacity(4).build().unwrap();
+        let mut consumer = buffer.consumer();
+        let producer = buffer.producer();
+        let pushing = thread::spawn(move || {
+            producer.push(10).unwrap();
+            producer.push(11).unwrap();
+        });
+
+        for sequence in 0..2 {
+            let event = next(&buffer, &mut consumer);
+            assert_eq!((event.sequence, event.payload), (sequence, 10 + sequence));
+        }
+        pushing.join().unwrap();
+    });
+}
+
+#[test]
+fn slots_are_reused_only_after_the_consumer_reads_them() {
+    model(|| {
+        let buffer = Buffer::<u64>::builder().capacity(2).build().unwrap();
+        let mut consumer = buffer.consumer();
+        let producer = buffer.producer();
+        producer.push(0).unwrap();
+        producer.push(1).unwrap();
+
+        // The ring is full, so this push waits for slot 0 and then overwrites it
+        let pushing = thread::spawn(move || producer.push(2).unwrap());
+        for sequence in 0..3 {
+            let event = next(&buffer, &mut consumer);
+            assert_eq!((event.sequence, event.payload), (sequence, sequence));
+        }
+        pushing.join().unwrap();
+    });
+}
+
+#[test]
+fn a_claim_from_a_stale_head_does_not_take_a_sequenced_position() {
+    model(|| {
+        let buffer = Buffer::<u64>::builder().capacity(2).build().unwrap();
+        let mut consumer = buffer.consumer();
+        let first = buffer.producer();
+        first.push(0).unwrap();
+        first.push(1).unwrap();
+        // Starts out expecting position 0, and finds its way to 2 while `first` may
+        // already be claiming it; by the time it claims, 2 may even be sequenced
+        let second = buffer.producer();
+        for sequence in 0..2 {
+            assert_eq!(next(&buffer, &mut consumer).payload, sequence);
+        }
+
+        let pushing = [
+            thread::spawn(move || first.push(20).unwrap()),
+            thread::spawn(move || second.push(30).unwrap()),
+        ];
+        let mut payloads = [0; 2];
+        for (sequence, payload) in (2..4).zip(&mut payloads) {
+            let event = next(&buffer, &mut consumer);
+            assert_eq!(event.sequence, sequence);
+            *payload = event.payload;
+        }
+        payloads.sort();
+        assert_eq!(payloads, [20, 30]);
+        for pushing in pushing {
+            pushing.join().unwrap();
+        }
+    });
+}